@@ -1,14 +1,29 @@
-use std::{process, sync::Arc};
+use std::{collections::HashMap, path::PathBuf, process, sync::Arc, time::Duration};
 
 use brwse_bridge_cli::BridgeArgs;
-use brwse_bridge_http::bridge::HTTPBridge;
+use brwse_bridge_http::{
+    bridge::{
+        Credentials, HTTPBridge, Limit, LimitOptions, OAuth2Config, Pagination, PaginationOptions,
+        Polling, PollingOptions, ResponseOptions, RetryOptions, SchemaOptions, SchemaProfile,
+        ToolFilter, server_url,
+    },
+    client::ClientOptions,
+};
 use clap::Parser;
+use openapiv3::OpenAPI;
+use reqwest::Url;
 use tracing::{error, info};
 
 #[derive(Parser)]
-#[command(author, version, about = "HTTP Bridge - HTTP API protocol bridge for OpenAPI specs")]
+#[command(
+    author,
+    version,
+    about = "HTTP Bridge - HTTP API protocol bridge for OpenAPI specs",
+    after_help = "Options naming a TOOL take the name agents see it under: its x-brwse-name or \
+                  operationId, prefixed with NAMESPACE_ when its API has a namespace."
+)]
 struct Args {
-    /// Path to OpenAPI specification file (JSON or YAML)
+    /// Path or http(s) URL of the OpenAPI specification (JSON or YAML)
     #[arg(long, env = "BRWSE_OPENAPI_SPEC_PATH")]
     openapi_spec: String,
 
@@ -16,14 +31,467 @@ struct Args {
     #[arg(long, env = "BRWSE_API_BASE_URL")]
     base_url: Option<String>,
 
-    /// Default timeout for HTTP requests in seconds
+    /// Prefix for the names of the spec's tools and prompts, to tell them
+    /// apart from those of other APIs
+    #[arg(long, env = "BRWSE_NAMESPACE")]
+    namespace: Option<String>,
+
+    /// Another OpenAPI spec to serve as NAMESPACE=LOCATION, called at its own
+    /// servers (repeatable). Its credentials and OAuth2 settings are given for
+    /// schemes written as NAMESPACE/SCHEME, and filters only it applies as
+    /// NAMESPACE/VALUE
+    #[arg(
+        long = "api",
+        env = "BRWSE_APIS",
+        value_delimiter = ',',
+        value_parser = parse_key_value::<String>
+    )]
+    apis: Vec<(String, String)>,
+
+    /// Value for a variable of the spec's server URLs as NAME=VALUE
+    /// (repeatable)
+    #[arg(
+        long = "server-variable",
+        env = "BRWSE_SERVER_VARIABLES",
+        value_delimiter = ',',
+        value_parser = parse_key_value::<String>
+    )]
+    server_variables: Vec<(String, String)>,
+
+    /// Base URL for an API given with --api as NAMESPACE=URL, overriding its
+    /// spec's servers (repeatable)
+    #[arg(
+        long = "api-base-url",
+        env = "BRWSE_API_BASE_URLS",
+        value_delimiter = ',',
+        value_parser = parse_key_value::<String>
+    )]
+    api_base_urls: Vec<(String, String)>,
+
+    /// Poll the OpenAPI spec file or URL every N seconds and reload it when it
+    /// changes, notifying clients when the tools change
+    #[arg(
+        long,
+        env = "BRWSE_OPENAPI_SPEC_POLL_INTERVAL",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    spec_poll_interval: Option<u64>,
+
+    /// Timeout in seconds for tool calls including their retries, and for
+    /// streamed responses that stop sending data
     #[arg(long, default_value = "30", env = "BRWSE_HTTP_TIMEOUT")]
     timeout: u64,
 
+    /// PEM file of certificate authorities to trust for upstream TLS, besides
+    /// the system's
+    #[arg(long, env = "BRWSE_HTTP_CA_BUNDLE")]
+    ca_bundle: Option<PathBuf>,
+
+    /// PEM file of the client certificate presented to upstreams requiring
+    /// mutual TLS
+    #[arg(long, env = "BRWSE_HTTP_CLIENT_CERT")]
+    client_cert: Option<PathBuf>,
+
+    /// PEM file of the client certificate's private key, if not in the
+    /// certificate file
+    #[arg(long, env = "BRWSE_HTTP_CLIENT_KEY", requires = "client_cert")]
+    client_key: Option<PathBuf>,
+
+    /// URL of the proxy to send upstream requests through, overriding
+    /// HTTP_PROXY and HTTPS_PROXY
+    #[arg(long, env = "BRWSE_HTTP_PROXY")]
+    proxy: Option<String>,
+
+    /// Host, domain or IP range to reach without --proxy (repeatable). Use
+    /// NO_PROXY for the proxies of the environment
+    #[arg(
+        long = "no-proxy",
+        env = "BRWSE_HTTP_NO_PROXY",
+        value_delimiter = ',',
+        requires = "proxy"
+    )]
+    no_proxy: Vec<String>,
+
+    /// Header sent with every upstream request as "NAME: VALUE" (repeatable),
+    /// OAuth2 token requests included
+    #[arg(long = "header", env = "BRWSE_HTTP_HEADER", value_parser = parse_header)]
+    headers: Vec<(String, String)>,
+
+    /// Retry idempotent requests failing with 429, 502, 503, 504 or a
+    /// connection error up to N times
+    #[arg(long, default_value = "0", env = "BRWSE_HTTP_MAX_RETRIES")]
+    max_retries: u32,
+
+    /// Delay before the first retry in milliseconds, doubled for each
+    /// subsequent one
+    #[arg(long, default_value = "500", env = "BRWSE_HTTP_RETRY_BACKOFF_MS")]
+    retry_backoff_ms: u64,
+
+    /// Upper bound of the retry delay in milliseconds. Responses asking to
+    /// wait longer with Retry-After are not retried
+    #[arg(long, default_value = "30000", env = "BRWSE_HTTP_RETRY_MAX_BACKOFF_MS")]
+    retry_max_backoff_ms: u64,
+
+    /// Upstream calls allowed per second, across all operations
+    #[arg(long, env = "BRWSE_RATE_LIMIT")]
+    rate_limit: Option<f64>,
+
+    /// Upstream calls allowed in flight at once, across all operations. Zero
+    /// leaves them unlimited, as for the other limits
+    #[arg(long, env = "BRWSE_MAX_IN_FLIGHT")]
+    max_in_flight: Option<usize>,
+
+    /// Calls per second allowed to a host as HOST=RATE (repeatable)
+    #[arg(
+        long = "host-rate-limit",
+        env = "BRWSE_HOST_RATE_LIMITS",
+        value_delimiter = ',',
+        value_parser = parse_key_value::<f64>
+    )]
+    host_rate_limits: Vec<(String, f64)>,
+
+    /// Calls in flight allowed to a host as HOST=COUNT (repeatable)
+    #[arg(
+        long = "host-max-in-flight",
+        env = "BRWSE_HOST_MAX_IN_FLIGHT",
+        value_delimiter = ',',
+        value_parser = parse_key_value::<usize>
+    )]
+    host_max_in_flight: Vec<(String, usize)>,
+
+    /// Calls per second allowed to a tool as TOOL=RATE, overriding the spec's
+    /// x-brwse-rate-limit (repeatable)
+    #[arg(
+        long = "operation-rate-limit",
+        env = "BRWSE_OPERATION_RATE_LIMITS",
+        value_delimiter = ',',
+        value_parser = parse_key_value::<f64>
+    )]
+    operation_rate_limits: Vec<(String, f64)>,
+
+    /// Calls in flight allowed to a tool as TOOL=COUNT, overriding the spec's
+    /// x-brwse-rate-limit (repeatable)
+    #[arg(
+        long = "operation-max-in-flight",
+        env = "BRWSE_OPERATION_MAX_IN_FLIGHT",
+        value_delimiter = ',',
+        value_parser = parse_key_value::<usize>
+    )]
+    operation_max_in_flight: Vec<(String, usize)>,
+
+    /// Fail tool calls exceeding a limit instead of queuing them
+    #[arg(long, env = "BRWSE_REJECT_WHEN_LIMITED")]
+    reject_when_limited: bool,
+
+    /// Prepend the HTTP status and selected headers to every tool result
+    #[arg(long, env = "BRWSE_RESPONSE_ENVELOPE")]
+    response_envelope: bool,
+
+    /// Response header to include in the envelope (repeatable)
+    #[arg(long = "response-header", env = "BRWSE_RESPONSE_HEADERS", value_delimiter = ',')]
+    response_headers: Vec<String>,
+
+    /// Check JSON responses against the schemas declared in the spec, noting
+    /// mismatches in tool results
+    #[arg(long, env = "BRWSE_VALIDATE_RESPONSES")]
+    validate_responses: bool,
+
+    /// Credential for a security scheme as SCHEME=SECRET (repeatable).
+    /// HTTP basic credentials are given as SCHEME=USERNAME:PASSWORD, and
+    /// those of other APIs as NAMESPACE/SCHEME=SECRET
+    #[arg(
+        long = "credential",
+        env = "BRWSE_CREDENTIALS",
+        value_delimiter = ',',
+        value_parser = parse_key_value::<String>
+    )]
+    credentials: Vec<(String, String)>,
+
+    /// OAuth2 client for a security scheme as SCHEME=CLIENT_ID[:CLIENT_SECRET]
+    /// (repeatable)
+    #[arg(
+        long = "oauth2-client",
+        env = "BRWSE_OAUTH2_CLIENTS",
+        value_delimiter = ',',
+        value_parser = parse_key_value::<String>
+    )]
+    oauth2_clients: Vec<(String, String)>,
+
+    /// OAuth2 refresh token for a security scheme as SCHEME=TOKEN (repeatable)
+    #[arg(
+        long = "oauth2-refresh-token",
+        env = "BRWSE_OAUTH2_REFRESH_TOKENS",
+        value_delimiter = ',',
+        value_parser = parse_key_value::<String>
+    )]
+    oauth2_refresh_tokens: Vec<(String, String)>,
+
+    /// OAuth2 token URL for a security scheme as SCHEME=URL, overriding the
+    /// spec (repeatable)
+    #[arg(
+        long = "oauth2-token-url",
+        env = "BRWSE_OAUTH2_TOKEN_URLS",
+        value_delimiter = ',',
+        value_parser = parse_key_value::<String>
+    )]
+    oauth2_token_urls: Vec<(String, String)>,
+
+    /// OAuth2 scope to request for a security scheme as SCHEME=SCOPE
+    /// (repeatable)
+    #[arg(
+        long = "oauth2-scope",
+        env = "BRWSE_OAUTH2_SCOPES",
+        value_delimiter = ',',
+        value_parser = parse_key_value::<String>
+    )]
+    oauth2_scopes: Vec<(String, String)>,
+
+    /// Shape of tool input schemas: full, strict or simplified
+    #[arg(long, default_value = "full", env = "BRWSE_SCHEMA_PROFILE")]
+    schema_profile: SchemaProfile,
+
+    /// Nesting depth beyond which the simplified profile collapses schemas
+    #[arg(long, default_value = "5", env = "BRWSE_SCHEMA_MAX_DEPTH")]
+    schema_max_depth: usize,
+
+    /// Emit shared component schemas under $defs instead of inlining them
+    #[arg(long, env = "BRWSE_SCHEMA_DEFS")]
+    schema_defs: bool,
+
+    /// Number of tools returned per tools/list page
+    #[arg(
+        long,
+        default_value = "10",
+        env = "BRWSE_TOOLS_PAGE_SIZE",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    tools_page_size: u64,
+
+    /// Follow the Link rel="next" pages of this TOOL and merge their items
+    /// (repeatable). Cursor-based pagination is configured with the spec's
+    /// x-brwse-pagination extension
+    #[arg(long = "paginate", env = "BRWSE_PAGINATE", value_delimiter = ',')]
+    paginate: Vec<String>,
+
+    /// Pages fetched per call of a paginated operation at most
+    #[arg(
+        long,
+        default_value = "5",
+        env = "BRWSE_MAX_PAGES",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    max_pages: u64,
+
+    /// Items after which no further page is fetched in a call
+    #[arg(long, env = "BRWSE_MAX_ITEMS")]
+    max_items: Option<usize>,
+
+    /// Poll the Location of this TOOL's 202 Accepted responses until the
+    /// operation completes (repeatable). Status documents are configured with
+    /// the spec's x-brwse-polling extension
+    #[arg(long = "poll", env = "BRWSE_POLL", value_delimiter = ',')]
+    poll: Vec<String>,
+
+    /// Delay before the first poll in milliseconds, doubled for each
+    /// subsequent one
+    #[arg(long, default_value = "1000", env = "BRWSE_POLL_INTERVAL_MS")]
+    poll_interval_ms: u64,
+
+    /// Upper bound of the delay between polls in milliseconds, unless the
+    /// upstream asks for longer with Retry-After
+    #[arg(long, default_value = "30000", env = "BRWSE_POLL_MAX_INTERVAL_MS")]
+    poll_max_interval_ms: u64,
+
+    /// Time to wait for a polled operation to complete in seconds
+    #[arg(long, default_value = "300", env = "BRWSE_POLL_TIMEOUT")]
+    poll_timeout: u64,
+
+    /// Keep cookies set by the API for the rest of each MCP session
+    #[arg(long, env = "BRWSE_SESSION_COOKIES")]
+    session_cookies: bool,
+
+    /// Only expose operations with this tag (repeatable)
+    #[arg(long = "include-tag", env = "BRWSE_INCLUDE_TAGS", value_delimiter = ',')]
+    include_tags: Vec<String>,
+
+    /// Hide operations with this tag (repeatable)
+    #[arg(long = "exclude-tag", env = "BRWSE_EXCLUDE_TAGS", value_delimiter = ',')]
+    exclude_tags: Vec<String>,
+
+    /// Only expose operations whose TOOL name or path matches this glob
+    /// (repeatable)
+    #[arg(long = "include-operation", env = "BRWSE_INCLUDE_OPERATIONS", value_delimiter = ',')]
+    include_operations: Vec<String>,
+
+    /// Hide operations whose TOOL name or path matches this glob (repeatable)
+    #[arg(long = "exclude-operation", env = "BRWSE_EXCLUDE_OPERATIONS", value_delimiter = ',')]
+    exclude_operations: Vec<String>,
+
+    /// Only expose operations with this HTTP method (repeatable)
+    #[arg(long = "include-method", env = "BRWSE_INCLUDE_METHODS", value_delimiter = ',')]
+    include_methods: Vec<String>,
+
+    /// Hide operations with this HTTP method (repeatable)
+    #[arg(long = "exclude-method", env = "BRWSE_EXCLUDE_METHODS", value_delimiter = ',')]
+    exclude_methods: Vec<String>,
+
+    /// Hide operations marked as deprecated
+    #[arg(long, env = "BRWSE_EXCLUDE_DEPRECATED")]
+    exclude_deprecated: bool,
+
+    /// Only expose GET, HEAD and OPTIONS operations
+    #[arg(long, env = "BRWSE_READ_ONLY")]
+    read_only: bool,
+
     #[command(flatten)]
     bridge: BridgeArgs,
 }
 
+/// Parses a KEY=VALUE argument. It is split at the first `=`, as values
+/// such as secrets and URLs may contain more of them.
+fn parse_key_value<T>(value: &str) -> Result<(String, T), String>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    let (key, parsed) =
+        value.split_once('=').ok_or_else(|| format!("expected KEY=VALUE, got `{value}`"))?;
+    let parsed = parsed.parse().map_err(|e| format!("invalid value in `{value}`: {e}"))?;
+    Ok((key.to_string(), parsed))
+}
+
+/// Selects the values given for the schemes of one API: the main one when
+/// `api` is `None`, whose schemes are unqualified, or the one in namespace
+/// `api`, whose schemes are written as NAMESPACE/SCHEME.
+fn scoped<'a>(
+    values: &'a [(String, String)],
+    api: Option<&'a str>,
+) -> impl Iterator<Item = (String, &'a String)> {
+    values.iter().filter_map(move |(scheme, value)| {
+        let scheme = match (scheme.split_once('/'), api) {
+            (Some((namespace, scheme)), Some(api)) if namespace == api => scheme,
+            (None, None) => scheme,
+            _ => return None,
+        };
+        Some((scheme.to_string(), value))
+    })
+}
+
+fn parse_header(value: &str) -> Result<(String, String), String> {
+    value
+        .split_once(':')
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .ok_or_else(|| format!("expected NAME: VALUE, got `{value}`"))
+}
+
+/// Picks the base URL of an API: the one `given` for it, or else the first
+/// server of its spec. Relative server URLs are resolved against `location`,
+/// when the spec was loaded from a URL.
+fn resolve_base_url(
+    given: Option<&str>,
+    spec: &OpenAPI,
+    location: &str,
+    variables: &HashMap<String, String>,
+) -> Result<String, String> {
+    if let Some(given) = given {
+        return Ok(given.to_string());
+    }
+    let server = spec.servers.first().ok_or("no servers found in its spec")?;
+    let url = server_url(server, variables)?;
+    if Url::parse(&url).is_ok() {
+        return Ok(url);
+    }
+    let location = Url::parse(location).map_err(|_| {
+        format!("server URL `{url}` is relative, but the spec was not loaded from a URL")
+    })?;
+    location.join(&url).map(String::from).map_err(|e| format!("invalid server URL `{url}`: {e}"))
+}
+
+impl Args {
+    /// Selects the filter values that apply to the API in `namespace`: those
+    /// written as NAMESPACE/VALUE for it, and unqualified ones, which apply to
+    /// every API. Prefixes naming no API are part of the value, as in a tag
+    /// like `admin/users`.
+    fn filter_values(&self, values: &[String], namespace: Option<&str>) -> Vec<String> {
+        let is_namespace = |prefix: &str| {
+            self.namespace.as_deref() == Some(prefix)
+                || self.apis.iter().any(|(api, _)| api == prefix)
+        };
+        values
+            .iter()
+            .filter_map(|value| match value.split_once('/') {
+                Some((prefix, value)) if is_namespace(prefix) => {
+                    (Some(prefix) == namespace).then(|| value.to_string())
+                }
+                _ => Some(value.clone()),
+            })
+            .collect()
+    }
+
+    fn filter(&self, namespace: Option<&str>) -> ToolFilter {
+        ToolFilter {
+            include_tags: self.filter_values(&self.include_tags, namespace),
+            exclude_tags: self.filter_values(&self.exclude_tags, namespace),
+            include_operations: self.filter_values(&self.include_operations, namespace),
+            exclude_operations: self.filter_values(&self.exclude_operations, namespace),
+            include_methods: self.filter_values(&self.include_methods, namespace),
+            exclude_methods: self.filter_values(&self.exclude_methods, namespace),
+            exclude_deprecated: self.exclude_deprecated,
+            read_only: self.read_only,
+        }
+    }
+
+    fn credentials(&self, api: Option<&str>) -> Credentials {
+        scoped(&self.credentials, api).map(|(scheme, secret)| (scheme, secret.clone())).collect()
+    }
+
+    fn oauth2_clients(&self, api: Option<&str>) -> HashMap<String, OAuth2Config> {
+        let mut clients = HashMap::<String, OAuth2Config>::new();
+        for (scheme, client) in scoped(&self.oauth2_clients, api) {
+            let (client_id, client_secret) = match client.split_once(':') {
+                Some((id, secret)) => (id, Some(secret.to_string())),
+                None => (client.as_str(), None),
+            };
+            let config = clients.entry(scheme).or_default();
+            config.client_id = client_id.to_string();
+            config.client_secret = client_secret;
+        }
+        for (scheme, token) in scoped(&self.oauth2_refresh_tokens, api) {
+            clients.entry(scheme).or_default().refresh_token = Some(token.clone());
+        }
+        for (scheme, url) in scoped(&self.oauth2_token_urls, api) {
+            clients.entry(scheme).or_default().token_url = Some(url.clone());
+        }
+        for (scheme, scope) in scoped(&self.oauth2_scopes, api) {
+            clients.entry(scheme).or_default().scopes.push(scope.clone());
+        }
+        clients
+    }
+
+    fn limits(&self) -> LimitOptions {
+        let keyed = |rates: &[(String, f64)], max_in_flight: &[(String, usize)]| {
+            let mut limits = HashMap::<String, Limit>::new();
+            for (key, rate) in rates {
+                limits.entry(key.clone()).or_default().rate = Some(*rate);
+            }
+            for (key, max) in max_in_flight {
+                limits.entry(key.clone()).or_default().max_in_flight = Some(*max);
+            }
+            limits
+        };
+        LimitOptions {
+            global: Limit {
+                rate: self.rate_limit,
+                max_in_flight: self.max_in_flight,
+                ..Default::default()
+            },
+            hosts: keyed(&self.host_rate_limits, &self.host_max_in_flight),
+            operations: keyed(&self.operation_rate_limits, &self.operation_max_in_flight),
+            reject: self.reject_when_limited,
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt::init();
@@ -43,30 +511,134 @@ async fn main() {
 
     info!("OpenAPI spec loaded: {} (v{})", spec.info.title, spec.info.version);
 
-    // Determine base URL
-    let base_url = args
-        .base_url
-        .or_else(|| spec.servers.first().map(|s| s.url.clone()))
-        .unwrap_or_else(|| {
-            error!("No base URL provided and no servers found in OpenAPI spec");
-            process::exit(1);
-        });
+    // Determine base URL. Without an explicit one, each operation is sent to
+    // the servers declared in the spec.
+    let server_variables: HashMap<_, _> = args.server_variables.iter().cloned().collect();
+    let base_url =
+        resolve_base_url(args.base_url.as_deref(), &spec, &args.openapi_spec, &server_variables)
+            .unwrap_or_else(|e| {
+                error!("No base URL for the API, {}; set one with --base-url", e);
+                process::exit(1);
+            });
 
     info!("Using base URL: {}", base_url);
 
     // Build the HTTP bridge
     info!("Starting HTTP bridge on {} -> {}", args.bridge.listen, base_url);
 
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(args.timeout))
-        .build()
-        .expect("Failed to build HTTP client");
+    // Tool calls time out in full through the retry options, so streamed
+    // responses are only cut by the client when they go idle
+    let client = ClientOptions {
+        timeout: None,
+        read_timeout: Some(Duration::from_secs(args.timeout)),
+        ca_bundle: args.ca_bundle.clone(),
+        client_cert: args.client_cert.clone(),
+        client_key: args.client_key.clone(),
+        proxy: args.proxy.clone(),
+        no_proxy: args.no_proxy.clone(),
+        headers: args.headers.clone(),
+    };
+    let client = Arc::new(client.build().unwrap_or_else(|e| {
+        error!("{}", e);
+        process::exit(1);
+    }));
+
+    let pagination = PaginationOptions {
+        operations: args
+            .paginate
+            .iter()
+            .map(|operation| (operation.clone(), Pagination::default()))
+            .collect(),
+        max_pages: args.max_pages as usize,
+        max_items: args.max_items,
+    };
+    let polling = PollingOptions {
+        operations: args
+            .poll
+            .iter()
+            .map(|operation| (operation.clone(), Polling::default()))
+            .collect(),
+        interval: Duration::from_millis(args.poll_interval_ms),
+        max_interval: Duration::from_millis(args.poll_max_interval_ms),
+        timeout: Duration::from_secs(args.poll_timeout),
+    };
+    // Every API is configured alike, but for its credentials and filters.
+    // Limits are set on the main one, which shares them with the others
+    let configure = |bridge: HTTPBridge, api: Option<&str>| {
+        bridge
+            .with_credentials(args.credentials(api))
+            .with_oauth2(args.oauth2_clients(api))
+            .with_response_options(ResponseOptions {
+                envelope: args.response_envelope,
+                headers: args.response_headers.clone(),
+                validate: args.validate_responses,
+            })
+            .with_retry_options(RetryOptions {
+                max_retries: args.max_retries,
+                initial_backoff: Duration::from_millis(args.retry_backoff_ms),
+                max_backoff: Duration::from_millis(args.retry_max_backoff_ms),
+                timeout: Some(Duration::from_secs(args.timeout)),
+            })
+            .with_pagination(pagination.clone())
+            .with_polling(polling.clone())
+            .with_tool_filter(args.filter(api.or(args.namespace.as_deref())))
+            .with_schema_options(SchemaOptions {
+                profile: args.schema_profile,
+                max_depth: args.schema_max_depth,
+                defs: args.schema_defs,
+            })
+            .with_page_size(args.tools_page_size as usize)
+            .with_session_cookies(args.session_cookies)
+    };
+
+    let mut bridge = configure(HTTPBridge::new(spec, base_url, Arc::clone(&client)), None)
+        .with_limits(args.limits());
+    if let Some(namespace) = &args.namespace {
+        bridge = bridge.with_namespace(namespace.clone());
+    }
+    if args.base_url.is_none() {
+        bridge = bridge.with_spec_servers(server_variables.clone());
+    }
+
+    for (namespace, location) in &args.apis {
+        info!("Loading OpenAPI spec of {} from: {}", namespace, location);
+        let spec = brwse_bridge_http::openapi::load_spec(location).await.unwrap_or_else(|e| {
+            error!("Failed to load OpenAPI spec of {}: {}", namespace, e);
+            process::exit(1);
+        });
+        let given = args.api_base_urls.iter().find(|(api, _)| api == namespace);
+        let given = given.map(|(_, base_url)| base_url.as_str());
+        let base_url =
+            resolve_base_url(given, &spec, location, &server_variables).unwrap_or_else(|e| {
+                error!("No base URL for {}, {}; set one with --api-base-url", namespace, e);
+                process::exit(1);
+            });
+        info!(
+            "Serving {} (v{}) as {} -> {}",
+            spec.info.title, spec.info.version, namespace, base_url
+        );
 
-    let bridge = HTTPBridge::new(spec, base_url, Arc::new(client));
+        let api = HTTPBridge::new(Arc::new(spec), base_url, Arc::clone(&client));
+        let mut api = configure(api, Some(namespace)).with_namespace(namespace.clone());
+        if given.is_none() {
+            api = api.with_spec_servers(server_variables.clone());
+        }
+        bridge = bridge.with_api(api);
+    }
+
+    if let Some(interval) = args.spec_poll_interval {
+        info!("Polling OpenAPI specs for changes every {}s", interval);
+        let interval = Duration::from_secs(interval);
+        tokio::spawn(bridge.clone().poll_spec(args.openapi_spec.clone(), interval));
+        for (api, (_, location)) in bridge.apis().zip(&args.apis) {
+            tokio::spawn(api.clone().poll_spec(location.clone(), interval));
+        }
+    }
 
-    let mcp_ct = brwse_bridge_mcp::bridge::start(&args.bridge.listen, bridge)
-        .await
-        .expect("failed to start MCP server");
+    let mcp_ct =
+        brwse_bridge_mcp::bridge::start_with(&args.bridge.listen, move || bridge.for_session())
+            .await
+            .expect("failed to start MCP server");
 
     let _result = tokio::signal::ctrl_c().await;
     info!("Received shutdown signal, stopping bridge...");
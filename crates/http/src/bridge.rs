@@ -1,55 +1,133 @@
 use std::{
     borrow::Cow,
-    collections::{BTreeMap, HashSet},
-    sync::Arc,
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Write as _,
+    iter,
+    sync::{Arc, RwLock},
+    time::Duration,
 };
 
-use genawaiter::sync::Gen;
 use openapiv3::{OpenAPI, Operation, Parameter, PathItem, ReferenceOr};
 use rmcp::{
-    RoleServer,
+    Peer, RoleServer,
     model::{
-        CallToolRequestParam, CallToolResult, Content, ListToolsResult, ServerCapabilities,
-        ServerInfo, Tool,
+        CallToolRequestParam, CallToolResult, GetPromptRequestParam, GetPromptResult,
+        ListPromptsResult, ListResourcesResult, ListToolsResult, PaginatedRequestParam,
+        ReadResourceRequestParam, ReadResourceResult, ServerCapabilities, ServerInfo, Tool,
     },
-    service::RequestContext,
+    service::{NotificationContext, RequestContext},
 };
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+mod auth;
+mod body;
+mod cookies;
+mod docs;
+mod extensions;
+mod filter;
+mod index;
+mod limits;
+mod oauth;
+mod pagination;
+mod polling;
+mod profile;
+mod progress;
+mod reference;
+mod response;
+mod retry;
+mod servers;
+
+pub use self::{
+    auth::Credentials,
+    filter::ToolFilter,
+    limits::{Limit, LimitOptions},
+    oauth::OAuth2Config,
+    pagination::{Pagination, PaginationOptions},
+    polling::{Polling, PollingOptions},
+    profile::{SchemaOptions, SchemaProfile},
+    response::ResponseOptions,
+    retry::RetryOptions,
+    servers::server_url,
+};
+use self::{
+    auth::{Authorization, TokenSources},
+    cookies::SessionCookies,
+    index::{ToolIndex, Validators},
+    limits::Limiter,
+    oauth::TokenSource,
+    pagination::NEXT_PAGE,
+    progress::Progress,
+    reference::operation_parameters,
+};
+
+/// State threaded through the resolution of a schema.
+#[derive(Default)]
+struct Resolver {
+    /// References being resolved, to cut cycles.
+    visited: HashSet<String>,
+    /// Component schemas collected for `$defs` when references are kept
+    /// rather than inlined.
+    defs: Option<BTreeMap<String, Value>>,
+}
+
+impl Resolver {
+    fn with_defs() -> Self {
+        Self { defs: Some(BTreeMap::new()), ..Default::default() }
+    }
 
-fn resolve_schema_with_visited(
+    /// Returns the collected `$defs`, if any.
+    fn into_defs(self) -> Option<BTreeMap<String, Value>> {
+        self.defs.filter(|defs| !defs.is_empty())
+    }
+}
+
+fn resolve_schema_with(
     schema_ref: &ReferenceOr<openapiv3::Schema>,
     spec: &OpenAPI,
-    visited: &mut HashSet<String>,
+    resolver: &mut Resolver,
 ) -> Value {
     match schema_ref {
-        ReferenceOr::Item(schema) => resolve_schema_object(schema, spec, visited),
+        ReferenceOr::Item(schema) => resolve_schema_object(schema, spec, resolver),
         ReferenceOr::Reference { reference } => {
+            // Extract the component name from the reference
+            // References are typically like "#/components/schemas/ComponentName"
+            let component = reference.strip_prefix("#/components/schemas/").and_then(|name| {
+                match spec.components.as_ref()?.schemas.get(name)? {
+                    ReferenceOr::Item(schema) => Some((name, schema)),
+                    ReferenceOr::Reference { .. } => None,
+                }
+            });
+
+            if let Some((name, schema)) = component
+                && let Some(defs) = resolver.defs.as_mut()
+            {
+                if !defs.contains_key(name) {
+                    // Claim the name first so cycles refer back to it.
+                    defs.insert(name.to_string(), Value::Null);
+                    let resolved = resolve_schema_object(schema, spec, resolver);
+                    resolver.defs.get_or_insert_default().insert(name.to_string(), resolved);
+                }
+                return json!({"$ref": format!("#/$defs/{name}")});
+            }
+
             // Prevent infinite recursion
-            if visited.contains(reference) {
+            if resolver.visited.contains(reference) {
                 return json!({
                     "type": "object",
                     "description": format!("Circular reference to {}", reference)
                 });
             }
 
-            visited.insert(reference.clone());
-
-            // Extract the component name from the reference
-            // References are typically like "#/components/schemas/ComponentName"
-            if let Some(component_name) = reference.strip_prefix("#/components/schemas/") {
-                if let Some(components) = &spec.components {
-                    if let Some(ReferenceOr::Item(referenced_schema)) =
-                        components.schemas.get(component_name)
-                    {
-                        let result = resolve_schema_object(referenced_schema, spec, visited);
-                        visited.remove(reference);
-                        return result;
-                    }
-                }
+            if let Some((_, schema)) = component {
+                resolver.visited.insert(reference.clone());
+                let result = resolve_schema_object(schema, spec, resolver);
+                resolver.visited.remove(reference);
+                return result;
             }
 
-            visited.remove(reference);
             json!({
                 "type": "object",
                 "description": format!("Unresolved reference to {}", reference)
@@ -59,56 +137,66 @@ fn resolve_schema_with_visited(
 }
 
 pub fn resolve_schema(schema_ref: &ReferenceOr<openapiv3::Schema>, spec: &OpenAPI) -> Value {
-    let mut visited = HashSet::new();
-    resolve_schema_with_visited(schema_ref, spec, &mut visited)
+    resolve_schema_with(schema_ref, spec, &mut Resolver::default())
 }
 
 fn resolve_schema_object(
     schema: &openapiv3::Schema,
     spec: &OpenAPI,
-    visited: &mut HashSet<String>,
+    resolver: &mut Resolver,
 ) -> Value {
     let mut json_schema = json!({});
+    let data = &schema.schema_data;
 
     // Handle schema data
-    if let Some(description) = &schema.schema_data.description {
+    if let Some(title) = &data.title {
+        json_schema["title"] = json!(title);
+    }
+
+    if let Some(description) = &data.description {
         json_schema["description"] = json!(description);
     }
 
-    if let Some(default) = &schema.schema_data.default {
+    if let Some(default) = &data.default {
         json_schema["default"] = default.clone();
     }
 
-    if let Some(example) = &schema.schema_data.example {
-        json_schema["example"] = example.clone();
+    if let Some(example) = &data.example {
+        json_schema["examples"] = json!([example]);
+    }
+
+    if data.read_only {
+        json_schema["readOnly"] = json!(true);
     }
 
-    // Handle enum values - note: enums are typically handled at the type level in
-    // OpenAPI 3.0 Individual enum constraints are usually found in the specific
-    // type definitions
+    if data.write_only {
+        json_schema["writeOnly"] = json!(true);
+    }
+
+    if data.deprecated {
+        json_schema["deprecated"] = json!(true);
+    }
 
     // Handle schema kind
     match &schema.schema_kind {
         openapiv3::SchemaKind::Type(type_def) => {
-            resolve_type_schema(type_def, spec, visited, &mut json_schema);
+            resolve_type_schema(type_def, spec, resolver, &mut json_schema);
         }
         openapiv3::SchemaKind::OneOf { one_of } => {
-            let resolved_schemas: Vec<Value> =
-                one_of.iter().map(|s| resolve_schema_with_visited(s, spec, visited)).collect();
-            json_schema["oneOf"] = json!(resolved_schemas);
+            json_schema["oneOf"] =
+                json!(resolve_variants(one_of, data.discriminator.as_ref(), spec, resolver));
         }
         openapiv3::SchemaKind::AllOf { all_of } => {
             let resolved_schemas: Vec<Value> =
-                all_of.iter().map(|s| resolve_schema_with_visited(s, spec, visited)).collect();
+                all_of.iter().map(|s| resolve_schema_with(s, spec, resolver)).collect();
             json_schema["allOf"] = json!(resolved_schemas);
         }
         openapiv3::SchemaKind::AnyOf { any_of } => {
-            let resolved_schemas: Vec<Value> =
-                any_of.iter().map(|s| resolve_schema_with_visited(s, spec, visited)).collect();
-            json_schema["anyOf"] = json!(resolved_schemas);
+            json_schema["anyOf"] =
+                json!(resolve_variants(any_of, data.discriminator.as_ref(), spec, resolver));
         }
         openapiv3::SchemaKind::Not { not } => {
-            json_schema["not"] = resolve_schema_with_visited(not, spec, visited);
+            json_schema["not"] = resolve_schema_with(not, spec, resolver);
         }
         openapiv3::SchemaKind::Any(_) => {
             // For "any" type, don't specify a type constraint
@@ -116,13 +204,130 @@ fn resolve_schema_object(
         }
     }
 
+    if data.nullable {
+        make_nullable(&mut json_schema);
+    }
+
     json_schema
 }
 
+/// Resolves the variants of a `oneOf` or `anyOf`, pinning the discriminator
+/// property of each referenced variant to the value that selects it.
+fn resolve_variants(
+    variants: &[ReferenceOr<openapiv3::Schema>],
+    discriminator: Option<&openapiv3::Discriminator>,
+    spec: &OpenAPI,
+    resolver: &mut Resolver,
+) -> Vec<Value> {
+    variants
+        .iter()
+        .map(|variant| {
+            let mut resolved = resolve_schema_with(variant, spec, resolver);
+            if let Some(discriminator) = discriminator
+                && let ReferenceOr::Reference { reference } = variant
+            {
+                let value = discriminator_value(discriminator, reference);
+                pin_property(&mut resolved, &discriminator.property_name, value);
+            }
+            resolved
+        })
+        .collect()
+}
+
+/// Returns the discriminator value selecting the schema at `reference`: its
+/// key in the mapping, or else the schema's component name.
+fn discriminator_value<'a>(
+    discriminator: &'a openapiv3::Discriminator,
+    reference: &'a str,
+) -> &'a str {
+    let name = reference.rsplit('/').next().unwrap_or(reference);
+    discriminator
+        .mapping
+        .iter()
+        .find(|(_, target)| *target == reference || *target == name)
+        .map_or(name, |(value, _)| value.as_str())
+}
+
+/// Requires `name` to be present and equal to `value`.
+fn pin_property(schema: &mut Value, name: &str, value: &str) {
+    let Some(properties) = schema.get_mut("properties").and_then(Value::as_object_mut) else {
+        let pinned = json!({"properties": {name: {"const": value}}, "required": [name]});
+        *schema = json!({"allOf": [schema.take(), pinned]});
+        return;
+    };
+    match properties.get_mut(name) {
+        Some(property) if property.is_object() => property["const"] = json!(value),
+        _ => {
+            properties.insert(name.to_string(), json!({"const": value}));
+        }
+    }
+    match schema.get_mut("required").and_then(Value::as_array_mut) {
+        Some(required) if required.contains(&json!(name)) => {}
+        Some(required) => required.push(json!(name)),
+        None => schema["required"] = json!([name]),
+    }
+}
+
+/// Extends a schema to also accept `null`, the JSON Schema equivalent of
+/// OpenAPI's `nullable`.
+fn make_nullable(schema: &mut Value) {
+    if let Some(Value::String(type_name)) = schema.get("type").cloned() {
+        schema["type"] = json!([type_name, "null"]);
+        if let Some(values) = schema.get_mut("enum").and_then(Value::as_array_mut)
+            && !values.contains(&Value::Null)
+        {
+            values.push(Value::Null);
+        }
+    } else if let Some(keyword) = ["oneOf", "anyOf"].into_iter().find(|k| schema[k].is_array()) {
+        if let Value::Array(variants) = &mut schema[keyword] {
+            variants.push(json!({"type": "null"}));
+        }
+    } else if ["allOf", "not", "$ref"].into_iter().any(|k| schema.get(k).is_some()) {
+        *schema = json!({"anyOf": [schema.take(), {"type": "null"}]});
+    }
+}
+
+/// Removes properties carrying `flag` (`readOnly` or `writeOnly`), at any
+/// depth, along with their `required` entries.
+fn remove_flagged_properties(schema: &mut Value, flag: &str) {
+    let Some(object) = schema.as_object_mut() else {
+        return;
+    };
+    if let Some(Value::Object(properties)) = object.get_mut("properties") {
+        let flagged = properties
+            .iter()
+            .filter(|(_, property)| property.get(flag) == Some(&json!(true)))
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        for name in &flagged {
+            properties.remove(name);
+        }
+        if let Some(Value::Array(required)) = object.get_mut("required") {
+            required.retain(|name| {
+                !name.as_str().is_some_and(|name| flagged.iter().any(|f| f == name))
+            });
+        }
+    }
+    for (keyword, value) in object.iter_mut() {
+        match (keyword.as_str(), value) {
+            ("properties", Value::Object(properties)) => properties
+                .values_mut()
+                .for_each(|property| remove_flagged_properties(property, flag)),
+            ("allOf" | "anyOf" | "oneOf", Value::Array(variants)) => {
+                variants.iter_mut().for_each(|variant| remove_flagged_properties(variant, flag))
+            }
+            ("items" | "additionalProperties" | "not", value) => {
+                remove_flagged_properties(value, flag)
+            }
+            _ => {}
+        }
+    }
+}
+
 fn resolve_type_schema(
     type_def: &openapiv3::Type,
     spec: &OpenAPI,
-    visited: &mut HashSet<String>,
+    resolver: &mut Resolver,
     json_schema: &mut Value,
 ) {
     match type_def {
@@ -131,7 +336,7 @@ fn resolve_type_schema(
 
             match &string_type.format {
                 openapiv3::VariantOrUnknownOrEmpty::Item(string_format) => {
-                    json_schema["format"] = json!(format!("{string_format:?}").to_lowercase());
+                    json_schema["format"] = json!(string_format);
                 }
                 openapiv3::VariantOrUnknownOrEmpty::Unknown(custom_format) => {
                     json_schema["format"] = json!(custom_format);
@@ -150,13 +355,17 @@ fn resolve_type_schema(
             if let Some(max_length) = string_type.max_length {
                 json_schema["maxLength"] = json!(max_length);
             }
+
+            if !string_type.enumeration.is_empty() {
+                json_schema["enum"] = json!(string_type.enumeration);
+            }
         }
         openapiv3::Type::Number(number_type) => {
             json_schema["type"] = json!("number");
 
             match &number_type.format {
                 openapiv3::VariantOrUnknownOrEmpty::Item(number_format) => {
-                    json_schema["format"] = json!(format!("{number_format:?}").to_lowercase());
+                    json_schema["format"] = json!(number_format);
                 }
                 openapiv3::VariantOrUnknownOrEmpty::Unknown(custom_format) => {
                     json_schema["format"] = json!(custom_format);
@@ -164,32 +373,33 @@ fn resolve_type_schema(
                 openapiv3::VariantOrUnknownOrEmpty::Empty => {}
             }
 
+            // OpenAPI 3.0 flags exclusive bounds, JSON Schema gives them as numbers.
             if let Some(minimum) = number_type.minimum {
-                json_schema["minimum"] = json!(minimum);
+                let keyword =
+                    if number_type.exclusive_minimum { "exclusiveMinimum" } else { "minimum" };
+                json_schema[keyword] = json!(minimum);
             }
 
             if let Some(maximum) = number_type.maximum {
-                json_schema["maximum"] = json!(maximum);
-            }
-
-            if number_type.exclusive_minimum {
-                json_schema["exclusiveMinimum"] = json!(true);
-            }
-
-            if number_type.exclusive_maximum {
-                json_schema["exclusiveMaximum"] = json!(true);
+                let keyword =
+                    if number_type.exclusive_maximum { "exclusiveMaximum" } else { "maximum" };
+                json_schema[keyword] = json!(maximum);
             }
 
             if let Some(multiple_of) = number_type.multiple_of {
                 json_schema["multipleOf"] = json!(multiple_of);
             }
+
+            if !number_type.enumeration.is_empty() {
+                json_schema["enum"] = json!(number_type.enumeration);
+            }
         }
         openapiv3::Type::Integer(integer_type) => {
             json_schema["type"] = json!("integer");
 
             match &integer_type.format {
                 openapiv3::VariantOrUnknownOrEmpty::Item(integer_format) => {
-                    json_schema["format"] = json!(format!("{integer_format:?}").to_lowercase());
+                    json_schema["format"] = json!(integer_format);
                 }
                 openapiv3::VariantOrUnknownOrEmpty::Unknown(custom_format) => {
                     json_schema["format"] = json!(custom_format);
@@ -197,28 +407,33 @@ fn resolve_type_schema(
                 openapiv3::VariantOrUnknownOrEmpty::Empty => {}
             }
 
+            // OpenAPI 3.0 flags exclusive bounds, JSON Schema gives them as numbers.
             if let Some(minimum) = integer_type.minimum {
-                json_schema["minimum"] = json!(minimum);
+                let keyword =
+                    if integer_type.exclusive_minimum { "exclusiveMinimum" } else { "minimum" };
+                json_schema[keyword] = json!(minimum);
             }
 
             if let Some(maximum) = integer_type.maximum {
-                json_schema["maximum"] = json!(maximum);
-            }
-
-            if integer_type.exclusive_minimum {
-                json_schema["exclusiveMinimum"] = json!(true);
-            }
-
-            if integer_type.exclusive_maximum {
-                json_schema["exclusiveMaximum"] = json!(true);
+                let keyword =
+                    if integer_type.exclusive_maximum { "exclusiveMaximum" } else { "maximum" };
+                json_schema[keyword] = json!(maximum);
             }
 
             if let Some(multiple_of) = integer_type.multiple_of {
                 json_schema["multipleOf"] = json!(multiple_of);
             }
+
+            if !integer_type.enumeration.is_empty() {
+                json_schema["enum"] = json!(integer_type.enumeration);
+            }
         }
-        openapiv3::Type::Boolean(_) => {
+        openapiv3::Type::Boolean(boolean_type) => {
             json_schema["type"] = json!("boolean");
+
+            if !boolean_type.enumeration.is_empty() {
+                json_schema["enum"] = json!(boolean_type.enumeration);
+            }
         }
         openapiv3::Type::Object(object_type) => {
             json_schema["type"] = json!("object");
@@ -230,17 +445,17 @@ fn resolve_type_schema(
                 for (prop_name, prop_schema) in &object_type.properties {
                     match prop_schema {
                         ReferenceOr::Item(schema_box) => {
-                            properties[prop_name] = resolve_schema_with_visited(
+                            properties[prop_name] = resolve_schema_with(
                                 &ReferenceOr::Item(*(*schema_box).clone()),
                                 spec,
-                                visited,
+                                resolver,
                             );
                         }
                         ReferenceOr::Reference { reference } => {
-                            properties[prop_name] = resolve_schema_with_visited(
+                            properties[prop_name] = resolve_schema_with(
                                 &ReferenceOr::Reference { reference: reference.clone() },
                                 spec,
-                                visited,
+                                resolver,
                             );
                         }
                     }
@@ -265,17 +480,17 @@ fn resolve_type_schema(
                     }
                     openapiv3::AdditionalProperties::Schema(schema) => match schema.as_ref() {
                         ReferenceOr::Item(schema_box) => {
-                            json_schema["additionalProperties"] = resolve_schema_with_visited(
+                            json_schema["additionalProperties"] = resolve_schema_with(
                                 &ReferenceOr::Item((*schema_box).clone()),
                                 spec,
-                                visited,
+                                resolver,
                             );
                         }
                         ReferenceOr::Reference { reference } => {
-                            json_schema["additionalProperties"] = resolve_schema_with_visited(
+                            json_schema["additionalProperties"] = resolve_schema_with(
                                 &ReferenceOr::Reference { reference: reference.clone() },
                                 spec,
-                                visited,
+                                resolver,
                             );
                         }
                     },
@@ -296,17 +511,17 @@ fn resolve_type_schema(
             if let Some(items) = &array_type.items {
                 match items {
                     ReferenceOr::Item(schema_box) => {
-                        json_schema["items"] = resolve_schema_with_visited(
+                        json_schema["items"] = resolve_schema_with(
                             &ReferenceOr::Item(*(*schema_box).clone()),
                             spec,
-                            visited,
+                            resolver,
                         );
                     }
                     ReferenceOr::Reference { reference } => {
-                        json_schema["items"] = resolve_schema_with_visited(
+                        json_schema["items"] = resolve_schema_with(
                             &ReferenceOr::Reference { reference: reference.clone() },
                             spec,
-                            visited,
+                            resolver,
                         );
                     }
                 }
@@ -327,18 +542,58 @@ fn resolve_type_schema(
     }
 }
 
+/// Characters of RFC 3986's reserved set kept as they are in query values of
+/// `allowReserved` parameters. `&` and `#` are always encoded, as they would
+/// end the value or the query.
+const ALLOWED_RESERVED: &[u8] = b":/?[]@!$'()*+,;=";
+
+/// Percent-encodes a value for use in a URL, leaving unreserved characters
+/// and, with `allow_reserved`, reserved ones as they are.
+pub fn encode_uri_component(value: &str, allow_reserved: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for &byte in value.as_bytes() {
+        if byte.is_ascii_alphanumeric()
+            || b"-._~".contains(&byte)
+            || allow_reserved && ALLOWED_RESERVED.contains(&byte)
+        {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+/// Whether a path parameter value holds `.` or `..` segments, which could
+/// escape the operation's path once the upstream server decodes the value.
+fn is_path_traversal(value: &Value) -> bool {
+    let strings = match value {
+        Value::Array(items) => items.iter().filter_map(to_canonical_string).collect(),
+        Value::Object(map) => map
+            .iter()
+            .flat_map(|(k, v)| [Some(k.clone()), to_canonical_string(v)])
+            .flatten()
+            .collect(),
+        value => to_canonical_string(value).into_iter().collect::<Vec<_>>(),
+    };
+    strings.iter().any(|s| s.split(['/', '\\']).any(|segment| matches!(segment, "." | "..")))
+}
+
 // Helper function to serialize path parameters according to OpenAPI
-// style/explode
+// style/explode. Values are percent-encoded, so they always stay within
+// their path segment.
 pub fn serialize_path_param(
     name: &str,
     value: &serde_json::Value,
     style: &openapiv3::PathStyle,
     explode: bool,
 ) -> String {
+    let encode = |value: &serde_json::Value| {
+        to_canonical_string(value).map(|value| encode_uri_component(&value, false))
+    };
     match value {
         serde_json::Value::Array(arr) => {
-            let items =
-                arr.iter().map(|v| to_canonical_string(v).unwrap_or_default()).collect::<Vec<_>>();
+            let items = arr.iter().map(|v| encode(v).unwrap_or_default()).collect::<Vec<_>>();
             match style {
                 openapiv3::PathStyle::Simple => items.join(","),
                 openapiv3::PathStyle::Label => {
@@ -358,7 +613,9 @@ pub fn serialize_path_param(
             }
         }
         serde_json::Value::Object(map) => {
-            let pairs = map.iter().filter_map(|(k, v)| to_canonical_string(v).map(|v| (k, v)));
+            let pairs = map
+                .iter()
+                .filter_map(|(k, v)| encode(v).map(|v| (encode_uri_component(k, false), v)));
             let pairs = if explode {
                 pairs.map(|(k, v)| format!("{k}={v}")).collect::<Vec<_>>()
             } else {
@@ -383,7 +640,7 @@ pub fn serialize_path_param(
             }
         }
         value => {
-            let s = to_canonical_string(value).unwrap_or_default();
+            let s = encode(value).unwrap_or_default();
             match style {
                 openapiv3::PathStyle::Simple => s,
                 openapiv3::PathStyle::Label => {
@@ -398,76 +655,63 @@ pub fn serialize_path_param(
 }
 
 /// Serializes a query parameter according to OpenAPI style/explode rules.
+///
+/// Names and values come back percent-encoded, with style delimiters left
+/// as they are, ready to be joined into a query string.
 pub fn serialize_query_param(
     name: &str,
     value: &serde_json::Value,
     style: &openapiv3::QueryStyle,
     explode: bool,
+    allow_reserved: bool,
 ) -> Vec<(String, String)> {
+    let encode = |value: &serde_json::Value| {
+        to_canonical_string(value).map(|value| encode_uri_component(&value, allow_reserved))
+    };
+    let name = encode_uri_component(name, false);
+    let delimited = |arr: &Vec<serde_json::Value>, delimiter: &str| {
+        if explode {
+            arr.iter().filter_map(encode).map(|v| (name.clone(), v)).collect()
+        } else {
+            let joined = arr.iter().filter_map(encode).collect::<Vec<_>>().join(delimiter);
+            vec![(name.clone(), joined)]
+        }
+    };
     match style {
         openapiv3::QueryStyle::Form => match value {
-            serde_json::Value::Array(arr) => {
-                if explode {
-                    arr.iter()
-                        .filter_map(to_canonical_string)
-                        .map(|v| (name.to_string(), v))
-                        .collect()
-                } else {
-                    let joined =
-                        arr.iter().filter_map(to_canonical_string).collect::<Vec<_>>().join(",");
-                    vec![(name.to_string(), joined)]
-                }
-            }
+            serde_json::Value::Array(arr) => delimited(arr, ","),
             serde_json::Value::Object(map) => {
                 if explode {
                     map.iter()
-                        .filter_map(|(k, v)| to_canonical_string(v).map(|v| (k.clone(), v)))
+                        .filter_map(|(k, v)| encode(v).map(|v| (encode_uri_component(k, false), v)))
                         .collect()
                 } else {
                     let joined = map
                         .iter()
-                        .filter_map(|(k, v)| to_canonical_string(v).map(|v| format!("{k},{v}")))
+                        .filter_map(|(k, v)| {
+                            encode(v).map(|v| format!("{},{v}", encode_uri_component(k, false)))
+                        })
                         .collect::<Vec<_>>()
                         .join(",");
-                    vec![(name.to_string(), joined)]
+                    vec![(name, joined)]
                 }
             }
-            _ => to_canonical_string(value).map(|v| (name.to_string(), v)).into_iter().collect(),
+            _ => encode(value).map(|v| (name, v)).into_iter().collect(),
         },
         openapiv3::QueryStyle::SpaceDelimited => match value {
-            serde_json::Value::Array(arr) => {
-                if explode {
-                    arr.iter()
-                        .filter_map(to_canonical_string)
-                        .map(|v| (name.to_string(), v))
-                        .collect()
-                } else {
-                    let joined =
-                        arr.iter().filter_map(to_canonical_string).collect::<Vec<_>>().join(" ");
-                    vec![(name.to_string(), joined)]
-                }
-            }
+            serde_json::Value::Array(arr) => delimited(arr, "%20"),
             _ => vec![], // Not defined for primitives/objects
         },
         openapiv3::QueryStyle::PipeDelimited => match value {
-            serde_json::Value::Array(arr) => {
-                if explode {
-                    arr.iter()
-                        .filter_map(to_canonical_string)
-                        .map(|v| (name.to_string(), v))
-                        .collect()
-                } else {
-                    let joined =
-                        arr.iter().filter_map(to_canonical_string).collect::<Vec<_>>().join("|");
-                    vec![(name.to_string(), joined)]
-                }
-            }
+            serde_json::Value::Array(arr) => delimited(arr, "|"),
             _ => vec![], // Not defined for primitives/objects
         },
         openapiv3::QueryStyle::DeepObject => match value {
             serde_json::Value::Object(map) if explode => map
                 .iter()
-                .filter_map(|(k, v)| to_canonical_string(v).map(|v| (format!("{name}[{k}]"), v)))
+                .filter_map(|(k, v)| {
+                    encode(v).map(|v| (format!("{name}[{}]", encode_uri_component(k, false)), v))
+                })
                 .collect(),
             _ => vec![], // Only defined for objects with explode=true
         },
@@ -506,46 +750,132 @@ pub fn serialize_header_param(
     }
 }
 
+/// Serializes a cookie parameter into `name=value` pairs. Values are
+/// percent-encoded, as cookie values cannot carry `;`, `,` or whitespace.
+pub fn serialize_cookie_param(
+    name: &str,
+    value: &serde_json::Value,
+    style: &openapiv3::CookieStyle,
+    explode: bool,
+) -> Vec<String> {
+    let encode = |value: &serde_json::Value| {
+        to_canonical_string(value).map(|value| urlencoding::encode(&value).into_owned())
+    };
+    match style {
+        openapiv3::CookieStyle::Form => match value {
+            serde_json::Value::Array(arr) => {
+                let values = arr.iter().filter_map(encode);
+                if explode {
+                    // id=3; id=4
+                    values.map(|v| format!("{name}={v}")).collect()
+                } else {
+                    // id=3,4
+                    vec![format!("{name}={}", values.collect::<Vec<_>>().join(","))]
+                }
+            }
+            serde_json::Value::Object(map) => {
+                if explode {
+                    // role=admin; firstName=Alex
+                    map.iter().filter_map(|(k, v)| encode(v).map(|v| format!("{k}={v}"))).collect()
+                } else {
+                    // id=role,admin,firstName,Alex
+                    let values = map
+                        .iter()
+                        .filter_map(|(k, v)| encode(v).map(|v| vec![k.clone(), v]))
+                        .flatten()
+                        .collect::<Vec<_>>();
+                    vec![format!("{name}={}", values.join(","))]
+                }
+            }
+            _ => encode(value).map(|v| format!("{name}={v}")).into_iter().collect(),
+        },
+    }
+}
+
 pub fn generate_input_schema(operation: &Operation, spec: &OpenAPI) -> Value {
+    generate_operation_input_schema(&[], operation, spec)
+}
+
+/// Generates the input schema of an operation shaped by a schema profile,
+/// including the parameters declared on its enclosing path item.
+pub fn generate_input_schema_with_options(
+    path_parameters: &[ReferenceOr<Parameter>],
+    operation: &Operation,
+    spec: &OpenAPI,
+    options: &SchemaOptions,
+) -> Value {
+    let parameters = operation_parameters(path_parameters, operation, spec);
+    let mut schema = input_schema(&parameters, operation, spec, options);
+    options.apply(&mut schema);
+    schema
+}
+
+/// Generates the input schema of an operation, including the parameters
+/// declared on its enclosing path item.
+pub fn generate_operation_input_schema(
+    path_parameters: &[ReferenceOr<Parameter>],
+    operation: &Operation,
+    spec: &OpenAPI,
+) -> Value {
+    let parameters = operation_parameters(path_parameters, operation, spec);
+    input_schema(&parameters, operation, spec, &SchemaOptions::default())
+}
+
+/// Builds the input schema of an operation, leaving the profile to be applied
+/// by the caller.
+fn input_schema(
+    parameters: &[&Parameter],
+    operation: &Operation,
+    spec: &OpenAPI,
+    options: &SchemaOptions,
+) -> Value {
+    let mut resolver =
+        if options.uses_defs() { Resolver::with_defs() } else { Resolver::default() };
     let mut properties = json!({});
     let mut required = Vec::new();
     let mut header_properties = json!({});
     let mut header_required = Vec::new();
+    let mut cookie_properties = json!({});
+    let mut cookie_required = Vec::new();
+
+    // Process parameters, leaving out those set server-side
+    for param in parameters.iter().filter(|param| extensions::exposed(param)) {
+        let schema_ref = match param.parameter_data_ref().format {
+            openapiv3::ParameterSchemaOrContent::Schema(ref schema_ref) => Some(schema_ref),
+            openapiv3::ParameterSchemaOrContent::Content(ref content) => content
+                .get("application/json")
+                .and_then(|json_content| json_content.schema.as_ref()),
+        };
+        let mut schema = schema_ref.map_or(json!({"type": "string"}), |schema_ref| {
+            resolve_schema_with(schema_ref, spec, &mut resolver)
+        });
+        // Parameters with a default may be left out
+        let optional = extensions::shape_schema(param, &mut schema);
 
-    // Process parameters
-    for param_ref in &operation.parameters {
-        if let ReferenceOr::Item(param) = param_ref {
-            let schema_ref = match param.parameter_data_ref().format {
-                openapiv3::ParameterSchemaOrContent::Schema(ref schema_ref) => Some(schema_ref),
-                openapiv3::ParameterSchemaOrContent::Content(ref content) => {
-                    if let Some(json_content) = content.get("application/json") {
-                        json_content.schema.as_ref()
-                    } else {
-                        None
-                    }
-                }
-            };
-            let schema = schema_ref
-                .map_or(json!({"type": "string"}), |schema_ref| resolve_schema(schema_ref, spec));
-
-            match &param {
-                Parameter::Query { parameter_data, .. } => {
-                    properties[&parameter_data.name] = schema;
-                    if parameter_data.required {
-                        required.push(parameter_data.name.as_str());
-                    }
+        match param {
+            Parameter::Query { parameter_data, .. } => {
+                properties[&parameter_data.name] = schema;
+                if parameter_data.required && !optional {
+                    required.push(parameter_data.name.as_str());
                 }
-                Parameter::Path { parameter_data, .. } => {
-                    properties[&parameter_data.name] = schema;
+            }
+            Parameter::Path { parameter_data, .. } => {
+                properties[&parameter_data.name] = schema;
+                if !optional {
                     required.push(parameter_data.name.as_str());
                 }
-                Parameter::Header { parameter_data, .. } => {
-                    header_properties[&parameter_data.name] = schema;
-                    if parameter_data.required {
-                        header_required.push(parameter_data.name.as_str());
-                    }
+            }
+            Parameter::Header { parameter_data, .. } => {
+                header_properties[&parameter_data.name] = schema;
+                if parameter_data.required && !optional {
+                    header_required.push(parameter_data.name.as_str());
+                }
+            }
+            Parameter::Cookie { parameter_data, .. } => {
+                cookie_properties[&parameter_data.name] = schema;
+                if parameter_data.required && !optional {
+                    cookie_required.push(parameter_data.name.as_str());
                 }
-                _ => {}
             }
         }
     }
@@ -565,66 +895,76 @@ pub fn generate_input_schema(operation: &Operation, spec: &OpenAPI) -> Value {
         properties["headers"] = headers_schema;
     }
 
+    // Likewise for cookie parameters
+    if !cookie_properties.as_object().unwrap().is_empty() {
+        let mut cookies_schema = json!({
+            "type": "object",
+            "properties": cookie_properties
+        });
+
+        if !cookie_required.is_empty() {
+            cookies_schema["required"] = json!(cookie_required);
+            required.push("cookies");
+        }
+
+        properties["cookies"] = cookies_schema;
+    }
+
     // Process request body if present
-    if let Some(ReferenceOr::Item(request_body)) = &operation.request_body {
-        if let Some(json_content) = request_body.content.get("application/json") {
-            if let Some(schema) = &json_content.schema {
-                properties["body"] = resolve_schema(schema, spec);
-                if request_body.required {
-                    required.push("body");
-                }
-            }
+    if let Some(request_body) =
+        operation.request_body.as_ref().and_then(|body| reference::resolve(body, spec))
+        && let Some(format) = body::select(request_body)
+    {
+        properties["body"] = body::schema(&format, spec, &mut resolver);
+        if request_body.required {
+            required.push("body");
         }
     }
 
-    json!({
+    let mut schema = json!({
         "type": "object",
         "properties": properties,
         "required": required,
-    })
+    });
+    if let Some(mut defs) = resolver.into_defs() {
+        defs.values_mut().for_each(|def| remove_flagged_properties(def, "readOnly"));
+        schema["$defs"] = json!(defs);
+    }
+    schema
 }
 
 struct ToolInfo<'id> {
     id: Cow<'id, str>,
     path: &'id str,
-    method: &'id str,
+    path_item: &'id PathItem,
+    method: &'static str,
     operation: &'id Operation,
 }
 
-fn tool_infos<'id>(
-    path: &'id str,
-    item: &'id PathItem,
-    cursor: &mut Option<String>,
-) -> impl Iterator<Item = ToolInfo<'id>> {
-    let operations = vec![
-        ("get", &item.get),
-        ("post", &item.post),
-        ("put", &item.put),
-        ("delete", &item.delete),
-        ("patch", &item.patch),
-        ("head", &item.head),
-        ("options", &item.options),
-    ];
-
-    Gen::new(|co| async move {
-        for (method, operation) in operations {
-            if let Some(op) = operation {
-                let id: Cow<str> = op.operation_id.as_ref().map(Into::into).unwrap_or_else(|| {
-                    format!("{}_{}", method, path.replace('/', "_").trim_start_matches('_')).into()
-                });
-                if let Some(previous_id) = cursor {
-                    if id != previous_id.as_str() {
-                        continue;
-                    }
-                    cursor.take();
-                    continue;
-                }
+const METHODS: [&str; 7] = ["get", "post", "put", "delete", "patch", "head", "options"];
+
+fn operation<'a>(item: &'a PathItem, method: &str) -> Option<&'a Operation> {
+    match method {
+        "get" => item.get.as_ref(),
+        "post" => item.post.as_ref(),
+        "put" => item.put.as_ref(),
+        "delete" => item.delete.as_ref(),
+        "patch" => item.patch.as_ref(),
+        "head" => item.head.as_ref(),
+        "options" => item.options.as_ref(),
+        _ => None,
+    }
+}
 
-                co.yield_(ToolInfo { id, path, method, operation: op }).await;
-            }
-        }
+fn tool_infos<'id>(path: &'id str, item: &'id PathItem) -> impl Iterator<Item = ToolInfo<'id>> {
+    METHODS.into_iter().filter_map(move |method| {
+        let op = operation(item, method)?;
+        let name = extensions::name(op).or(op.operation_id.as_deref());
+        let id: Cow<str> = name.map(Into::into).unwrap_or_else(|| {
+            format!("{}_{}", method, path.replace('/', "_").trim_start_matches('_')).into()
+        });
+        Some(ToolInfo { id, path, path_item: item, method, operation: op })
     })
-    .into_iter()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -633,41 +973,380 @@ pub struct HttpRequest {
     pub params: BTreeMap<String, Value>,
 }
 
+/// Number of tools returned per `tools/list` page by default.
+const DEFAULT_PAGE_SIZE: usize = 10;
+
 #[derive(Clone)]
 pub struct HTTPBridge {
-    spec: Arc<OpenAPI>,
+    index: Arc<RwLock<Arc<ToolIndex>>>,
     base_url: String,
     client: Arc<reqwest::Client>,
+    credentials: Arc<Credentials>,
+    tokens: Arc<TokenSources>,
+    response: ResponseOptions,
+    retry: RetryOptions,
+    /// Shared by all sessions, so limits hold across them.
+    limiter: Arc<Limiter>,
+    pagination: Arc<PaginationOptions>,
+    polling: Arc<PollingOptions>,
+    filter: Arc<ToolFilter>,
+    schema: SchemaOptions,
+    page_size: usize,
+    /// Variable values for the spec's servers, which replace the base URL when
+    /// set.
+    server_variables: Option<Arc<HashMap<String, String>>>,
+    cookies: Option<SessionCookies>,
+    /// Prefix of the names of tools and prompts, set when serving several
+    /// APIs.
+    namespace: Option<String>,
+    /// Other APIs served alongside this one, given new cookies along with it
+    /// for each session.
+    apis: Vec<HTTPBridge>,
+    /// Connected sessions, notified when the tool set changes.
+    peers: Arc<Mutex<Vec<Peer<RoleServer>>>>,
 }
 
 impl HTTPBridge {
     pub fn new(spec: Arc<OpenAPI>, base_url: String, client: Arc<reqwest::Client>) -> Self {
-        Self { spec, base_url, client }
+        let bridge = Self {
+            index: Arc::new(RwLock::new(Arc::new(ToolIndex::new(Arc::clone(&spec), None)))),
+            base_url,
+            client,
+            credentials: Arc::default(),
+            tokens: Arc::default(),
+            response: ResponseOptions::default(),
+            retry: RetryOptions::default(),
+            limiter: Arc::default(),
+            pagination: Arc::default(),
+            polling: Arc::default(),
+            filter: Arc::default(),
+            schema: SchemaOptions::default(),
+            page_size: DEFAULT_PAGE_SIZE,
+            server_variables: None,
+            cookies: None,
+            namespace: None,
+            apis: Vec::new(),
+            peers: Arc::default(),
+        };
+        bridge.reindexed()
     }
 
-    pub fn tools(&self, mut cursor: Option<String>) -> impl Iterator<Item = Tool> {
-        Gen::new(|co| async move {
-            for (path, path_item) in &self.spec.paths.paths {
-                if let ReferenceOr::Item(item) = path_item {
-                    for tool in tool_infos(path, item, &mut cursor) {
-                        co.yield_(self.tool(tool.id, tool.path, tool.method, tool.operation)).await;
+    /// Sets the secrets used to satisfy the spec's security requirements.
+    ///
+    /// Parameters carrying these secrets are removed from the tool schemas, so
+    /// agents never see or supply them.
+    pub fn with_credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Arc::new(credentials);
+        self.reindexed()
+    }
+
+    /// Configures OAuth2 clients, keyed by security scheme name, used to obtain
+    /// and renew access tokens for `oauth2` schemes.
+    pub fn with_oauth2(mut self, clients: HashMap<String, OAuth2Config>) -> Self {
+        self.tokens = Arc::new(
+            clients
+                .into_iter()
+                .map(|(scheme, config)| (scheme, TokenSource::new(config)))
+                .collect(),
+        );
+        self.reindexed()
+    }
+
+    /// Sets how upstream responses are surfaced in tool results.
+    pub fn with_response_options(mut self, response: ResponseOptions) -> Self {
+        self.response = response;
+        self
+    }
+
+    /// Sets how failed upstream calls are retried.
+    pub fn with_retry_options(mut self, retry: RetryOptions) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Caps the rate and concurrency of upstream calls, to this API and the
+    /// ones served alongside it.
+    pub fn with_limits(mut self, limits: LimitOptions) -> Self {
+        self.limiter = Arc::new(Limiter::new(limits));
+        for api in &mut self.apis {
+            api.limiter = Arc::clone(&self.limiter);
+        }
+        self
+    }
+
+    /// Sets which list operations have their pages followed and merged.
+    pub fn with_pagination(mut self, pagination: PaginationOptions) -> Self {
+        self.pagination = Arc::new(pagination);
+        self.reindexed()
+    }
+
+    /// Sets which operations answering `202 Accepted` are polled until they
+    /// complete.
+    pub fn with_polling(mut self, polling: PollingOptions) -> Self {
+        self.polling = Arc::new(polling);
+        self
+    }
+
+    /// Restricts the operations exposed as tools. Filtered operations can be
+    /// neither listed nor called.
+    pub fn with_tool_filter(mut self, filter: ToolFilter) -> Self {
+        self.filter = Arc::new(filter);
+        self.reindexed()
+    }
+
+    /// Sets how tool input schemas are shaped for the connected clients.
+    pub fn with_schema_options(mut self, schema: SchemaOptions) -> Self {
+        self.schema = schema;
+        self.reindexed()
+    }
+
+    /// Sets the number of tools returned per `tools/list` page.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Sends each operation to the first server declared by the operation, its
+    /// path or the spec, in that order, expanding server URL variables with
+    /// `variables` or their defaults. The base URL is used when no servers
+    /// are declared, and to resolve relative server URLs.
+    pub fn with_spec_servers(mut self, variables: HashMap<String, String>) -> Self {
+        self.server_variables = Some(Arc::new(variables));
+        self
+    }
+
+    /// Keeps the cookies set by the API for the rest of each MCP session and
+    /// sends them with its later requests, so tools that log in can be
+    /// followed by tools that need the session. Each session should be served
+    /// with its own [`HTTPBridge::for_session`].
+    pub fn with_session_cookies(mut self, enabled: bool) -> Self {
+        self.cookies = enabled.then(SessionCookies::default);
+        self
+    }
+
+    /// Prefixes the names of tools and prompts with `namespace` and an
+    /// underscore, and publishes the spec's resources under
+    /// `openapi://{namespace}/`, keeping them apart from those of the other
+    /// APIs served alongside.
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self.reindexed()
+    }
+
+    /// Serves the tools, resources and prompts of another API alongside this
+    /// one's. The API keeps its own spec, base URL, credentials and filters,
+    /// and should be given a namespace to avoid name collisions. Its calls
+    /// count against the limits of this one.
+    pub fn with_api(mut self, api: HTTPBridge) -> Self {
+        for api in api.bridges() {
+            let mut api = api.clone();
+            api.apis = Vec::new();
+            api.limiter = Arc::clone(&self.limiter);
+            api.peers = Arc::clone(&self.peers);
+            self.apis.push(api);
+        }
+        self
+    }
+
+    /// Returns a copy of the bridge to serve a new MCP session with, holding
+    /// no cookies yet for this API and the ones served alongside it.
+    pub fn for_session(&self) -> Self {
+        let mut session = self.clone();
+        for cookies in iter::once(&mut session.cookies)
+            .chain(session.apis.iter_mut().map(|api| &mut api.cookies))
+        {
+            *cookies = cookies.as_ref().map(|_| SessionCookies::default());
+        }
+        session
+    }
+
+    /// Returns the APIs served alongside this one. Their specs can be reloaded
+    /// through them.
+    pub fn apis(&self) -> impl Iterator<Item = &HTTPBridge> {
+        self.apis.iter()
+    }
+
+    /// Returns this API followed by the ones served alongside it.
+    fn bridges(&self) -> impl Iterator<Item = &HTTPBridge> {
+        iter::once(self).chain(self.apis.iter())
+    }
+
+    /// Returns the spec tools are currently generated from.
+    pub fn spec(&self) -> Arc<OpenAPI> {
+        Arc::clone(self.index().spec())
+    }
+
+    fn index(&self) -> Arc<ToolIndex> {
+        Arc::clone(&self.index.read().unwrap())
+    }
+
+    /// Rebuilds the tool index after a setting that shapes the tools changed.
+    fn reindexed(mut self) -> Self {
+        let index = self.build_index(self.spec());
+        // Replace rather than write through the lock, which clones share.
+        self.index = Arc::new(RwLock::new(Arc::new(index)));
+        self
+    }
+
+    fn build_index(&self, spec: Arc<OpenAPI>) -> ToolIndex {
+        let mut index = ToolIndex::new(Arc::clone(&spec), self.namespace.clone());
+        for (path, path_item) in &spec.paths.paths {
+            if let ReferenceOr::Item(item) = path_item {
+                for info in tool_infos(path, item) {
+                    // Filters and per-operation settings all go by the listed name
+                    let id = Cow::Owned(index.qualify(&info.id).into_owned());
+                    let info = ToolInfo { id, ..info };
+                    if self.filter.allows(&info) {
+                        let tool = self.tool(&spec, &info);
+                        index.insert(&info, tool, response::schema(info.operation, &spec));
                     }
                 }
             }
-        })
-        .into_iter()
+        }
+        index
+    }
+
+    /// Swaps in a new spec, notifying connected clients when the tool set
+    /// changes. Returns whether it did.
+    ///
+    /// Requests already in flight finish against the spec they started with.
+    pub async fn reload(&self, spec: OpenAPI) -> bool {
+        let index = Arc::new(self.build_index(Arc::new(spec)));
+        let previous = std::mem::replace(&mut *self.index.write().unwrap(), Arc::clone(&index));
+        let changed =
+            !previous.after(None).into_iter().flatten().eq(index.after(None).into_iter().flatten());
+        if changed {
+            self.notify_tool_list_changed().await;
+        }
+        changed
+    }
+
+    /// Polls the spec at `location` every `interval` and reloads it when it
+    /// changes. Specs that fail to load are logged and skipped.
+    pub async fn poll_spec(self, location: String, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            match crate::openapi::load_spec(&location).await {
+                Ok(spec) if spec != *self.spec() => {
+                    let changed = self.reload(spec).await;
+                    info!("Reloaded OpenAPI spec from {} (tools changed: {})", location, changed);
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Failed to reload OpenAPI spec from {}: {}", location, e),
+            }
+        }
+    }
+
+    async fn notify_tool_list_changed(&self) {
+        let mut peers = self.peers.lock().await;
+        let mut connected = Vec::with_capacity(peers.len());
+        for peer in peers.drain(..) {
+            // Sessions that have gone away fail to take the notification.
+            if peer.notify_tool_list_changed().await.is_ok() {
+                connected.push(peer);
+            }
+        }
+        *peers = connected;
+    }
+
+    /// Returns the tools of all APIs following the one named by `cursor`, or
+    /// all of them without a cursor.
+    pub fn tools(&self, cursor: Option<String>) -> impl Iterator<Item = Tool> {
+        let tools = self.all_tools();
+        let start = match cursor {
+            Some(cursor) => {
+                tools.iter().position(|tool| tool.name == cursor).map_or(tools.len(), |i| i + 1)
+            }
+            None => 0,
+        };
+        tools.into_iter().skip(start)
+    }
+
+    fn all_tools(&self) -> Vec<Tool> {
+        let tools = self.bridges().flat_map(|bridge| {
+            let index = bridge.index();
+            index.after(None).into_iter().flatten().cloned().collect::<Vec<_>>()
+        });
+        tools.collect()
     }
 
-    fn tool(&self, id: Cow<str>, path: &str, method: &str, operation: &Operation) -> Tool {
-        let description = operation
-            .summary
-            .clone()
+    /// Returns the page of `items` following the one keyed by `cursor`, along
+    /// with the cursor of the next page.
+    fn page<T>(
+        &self,
+        mut items: Vec<T>,
+        cursor: Option<String>,
+        key: impl Fn(&T) -> &str,
+    ) -> Result<(Vec<T>, Option<String>), rmcp::Error> {
+        if let Some(cursor) = cursor {
+            let Some(position) = items.iter().position(|item| key(item) == cursor) else {
+                return Err(rmcp::Error::invalid_params(
+                    "unknown cursor",
+                    Some(Value::String(cursor)),
+                ));
+            };
+            items.drain(..=position);
+        }
+        let next_cursor = (items.len() > self.page_size).then(|| {
+            items.truncate(self.page_size);
+            key(&items[self.page_size - 1]).to_string()
+        });
+        Ok((items, next_cursor))
+    }
+
+    /// Generates the tool of an operation, along with its input schema before
+    /// the strict profile applied, if it does.
+    fn tool(&self, spec: &OpenAPI, tool: &ToolInfo) -> (Tool, Option<Value>) {
+        let ToolInfo { id, path, path_item, method, operation } = tool;
+        let mut description = extensions::description(&operation.extensions)
+            .map(ToString::to_string)
+            .or_else(|| operation.summary.clone())
             .or_else(|| operation.description.clone())
             .unwrap_or_else(|| format!("{} {}", method.to_uppercase(), path));
+        extensions::note_confirmation(operation, &mut description);
 
-        let input_schema = generate_input_schema(operation, &self.spec);
+        let (parameters, _) = self.operation_inputs(spec, path_item, operation);
+        let mut input_schema = input_schema(&parameters, operation, spec, &self.schema);
+        if self.pagination.get(id, operation).is_some() {
+            pagination::extend_schema(&mut input_schema);
+        }
+        let strict_source =
+            (self.schema.profile == SchemaProfile::Strict).then(|| input_schema.clone());
+        self.schema.apply(&mut input_schema);
 
-        Tool::new(id.into_owned(), description, Arc::new(input_schema.as_object().unwrap().clone()))
+        let input_schema = Arc::new(input_schema.as_object().unwrap().clone());
+        (Tool::new(id.to_string(), description, input_schema), strict_source)
+    }
+
+    /// Returns the parameters agents supply to an operation, together with the
+    /// bridge-held credentials that authorize it.
+    fn operation_inputs<'a>(
+        &'a self,
+        spec: &'a OpenAPI,
+        path_item: &'a PathItem,
+        operation: &'a Operation,
+    ) -> (Vec<&'a Parameter>, Option<Authorization<'a>>) {
+        let mut parameters = operation_parameters(&path_item.parameters, operation, spec);
+        let authorization = Authorization::select(operation, spec, &self.credentials, &self.tokens);
+        if let Some(authorization) = &authorization {
+            parameters.retain(|parameter| !authorization.covers(parameter));
+        }
+        (parameters, authorization)
+    }
+
+    /// Returns whether a tool is marked with `x-brwse-requires-confirmation`,
+    /// for policy middleware to have its calls confirmed before they are let
+    /// through. Listed tools ask for confirmation in their description.
+    pub fn requires_confirmation(&self, tool_name: &str) -> bool {
+        self.bridges().any(|bridge| {
+            let index = bridge.index();
+            index
+                .get(tool_name)
+                .is_some_and(|(tool, _)| extensions::requires_confirmation(tool.operation))
+        })
     }
 
     pub async fn execute_tool(
@@ -675,55 +1354,175 @@ impl HTTPBridge {
         tool_name: &str,
         arguments: Value,
     ) -> Result<CallToolResult, rmcp::Error> {
-        // Find the matching operation in the spec
-        for (path, path_item) in &self.spec.paths.paths {
-            if let ReferenceOr::Item(item) = path_item {
-                for tool_info in tool_infos(path, item, &mut None) {
-                    if tool_info.id == tool_name {
-                        return self
-                            .execute_http_request(
-                                tool_info.path,
-                                tool_info.method,
-                                tool_info.operation,
-                                arguments,
-                            )
-                            .await;
-                    }
-                }
+        self.run_tool(tool_name, arguments, None).await
+    }
+
+    /// Executes a tool, reporting streamed responses to `progress`.
+    async fn run_tool(
+        &self,
+        tool_name: &str,
+        arguments: Value,
+        progress: Option<&Progress>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        for bridge in self.bridges() {
+            let index = bridge.index();
+            if let Some((tool_info, validators)) = index.get(tool_name) {
+                return bridge
+                    .execute_http_request(index.spec(), tool_info, validators, arguments, progress)
+                    .await;
             }
         }
-
         Err(rmcp::Error::internal_error(format!("Tool '{tool_name}' not found",), None))
     }
 
-    async fn execute_http_request(
+    /// Describes the API for the instructions given to clients.
+    fn describe(&self) -> String {
+        let spec = self.spec();
+        let mut description =
+            format!("- {} (version {}) at {}", spec.info.title, spec.info.version, self.base_url);
+        if let Some(namespace) = &self.namespace {
+            let _ = write!(description, ", with tools prefixed by `{namespace}_`");
+        }
+        if let Some(about) = &spec.info.description {
+            let _ = write!(description, ": {}", about.lines().next().unwrap_or_default());
+        }
+        description
+    }
+
+    /// Returns the URL of the server an operation is sent to.
+    fn base_url(
         &self,
-        path: &str,
-        method: &str,
+        spec: &OpenAPI,
+        path_item: &PathItem,
         operation: &Operation,
-        args: Value,
+    ) -> Result<Cow<'_, str>, rmcp::Error> {
+        let Some(variables) = &self.server_variables else {
+            return Ok(Cow::Borrowed(&self.base_url));
+        };
+        let servers = [&operation.servers, &path_item.servers, &spec.servers];
+        let Some(server) = servers.into_iter().find_map(|servers| servers.first()) else {
+            return Ok(Cow::Borrowed(&self.base_url));
+        };
+        let url =
+            server_url(server, variables).map_err(|e| rmcp::Error::internal_error(e, None))?;
+        // Relative server URLs are resolved against the base URL
+        if reqwest::Url::parse(&url).is_err()
+            && let Ok(resolved) =
+                reqwest::Url::parse(&self.base_url).and_then(|base| base.join(&url))
+        {
+            return Ok(Cow::Owned(resolved.into()));
+        }
+        Ok(Cow::Owned(url))
+    }
+
+    /// Sends a request once with its credentials and cookies, retrying once
+    /// with renewed tokens when they are rejected.
+    async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+        authorization: Option<&Authorization<'_>>,
+        cookies: &[String],
+    ) -> Result<reqwest::Result<reqwest::Response>, rmcp::Error> {
+        let Some(authorization) = authorization else {
+            if cookies.is_empty() {
+                return Ok(request.send().await);
+            }
+            return Ok(request.header(reqwest::header::COOKIE, cookies.join("; ")).send().await);
+        };
+
+        // Keep a copy of the request to retry once with renewed tokens.
+        let retry = authorization.is_renewable().then(|| request.try_clone()).flatten();
+        let response = authorization.apply(request, &self.client, cookies).await?.send().await;
+        Ok(match (response, retry) {
+            (Ok(response), Some(retry))
+                if response.status() == reqwest::StatusCode::UNAUTHORIZED =>
+            {
+                authorization.invalidate().await;
+                authorization.apply(retry, &self.client, cookies).await?.send().await
+            }
+            (response, _) => response,
+        })
+    }
+
+    /// Sends a request within the limits of its operation and host, retrying
+    /// transient failures. `streamed` requests have their responses forwarded
+    /// as progress.
+    async fn fetch(
+        &self,
+        id: &str,
+        operation: &Operation,
+        request: reqwest::RequestBuilder,
+        authorization: Option<&Authorization<'_>>,
+        cookies: &[String],
+        streamed: bool,
+    ) -> Result<reqwest::Result<reqwest::Response>, rmcp::Error> {
+        let (client, request) = request.build_split();
+        let request = match request {
+            Ok(request) => request,
+            Err(e) => return Ok(Err(e)),
+        };
+        let host = request.url().host_str().map(ToString::to_string);
+        let request = reqwest::RequestBuilder::from_parts(client, request);
+        let host = host.as_deref();
+        // Each attempt counts against the limits
+        let response = retry::send(&self.retry, request, streamed, move |request| async move {
+            // Responses keep their slots taken until their bodies are read
+            let permits = self.limiter.acquire(id, operation, host).await?;
+            let mut response = self.send(request, authorization, cookies).await?;
+            if let Ok(response) = &mut response {
+                permits.hold(response);
+            }
+            Ok(response)
+        })
+        .await?;
+        if let (Some(jar), Ok(response)) = (&self.cookies, &response) {
+            jar.store(response);
+        }
+        Ok(response)
+    }
+
+    async fn execute_http_request(
+        &self,
+        spec: &OpenAPI,
+        tool: ToolInfo<'_>,
+        validators: &Validators,
+        mut args: Value,
+        progress: Option<&Progress>,
     ) -> Result<CallToolResult, rmcp::Error> {
-        let input_schema = generate_input_schema(operation, &self.spec);
-        let validator = jsonschema::validator_for(&input_schema).map_err(|err| {
-            rmcp::Error::internal_error(
-                format!("failed to create validator: {err}"),
-                Some(input_schema),
-            )
-        })?;
+        let ToolInfo { id, path, path_item, method, operation } = tool;
+        let (parameters, authorization) = self.operation_inputs(spec, path_item, operation);
+        let validator = validators.input.as_ref().map_err(Clone::clone)?;
         if let Err(err) = validator.validate(&args) {
             return Err(rmcp::Error::invalid_params(
                 format!("invalid arguments: {err}"),
                 Some(args.clone()),
             ));
         }
+        if let Some(schema) = &validators.strict_source {
+            profile::prune_nulls(&mut args, schema);
+        }
+        extensions::inject(&parameters, &mut args);
 
-        // Build the URL with path parameters
-        let mut url = format!("{}{path}", self.base_url.trim_end_matches('/'));
+        // Build the URL with path and query parameters
+        let base_url = self.base_url(spec, path_item, operation)?;
+        let mut url = format!("{}{path}", base_url.trim_end_matches('/'));
+        let mut query_params = Vec::new();
 
-        // Replace path parameters with correct serialization
-        for param_ref in &operation.parameters {
-            if let ReferenceOr::Item(Parameter::Path { parameter_data, style, .. }) = param_ref {
-                if let Some(value) = args.get(&parameter_data.name) {
+        for param in &parameters {
+            match param {
+                Parameter::Path { parameter_data, style } => {
+                    let Some(value) = args.get(&parameter_data.name) else {
+                        continue;
+                    };
+                    if is_path_traversal(value) {
+                        return Err(rmcp::Error::invalid_params(
+                            format!(
+                                "path parameter '{}' must not contain '.' or '..' segments",
+                                parameter_data.name
+                            ),
+                            Some(value.clone()),
+                        ));
+                    }
                     let explode = parameter_data.explode.unwrap_or(false);
                     let serialized =
                         serialize_path_param(&parameter_data.name, value, style, explode);
@@ -731,9 +1530,30 @@ impl HTTPBridge {
                     let placeholder = format!("{{{}}}", parameter_data.name);
                     url = url.replace(&placeholder, &serialized);
                 }
+                Parameter::Query { parameter_data, style, allow_reserved, allow_empty_value } => {
+                    let Some(value) = args.get(&parameter_data.name) else {
+                        continue;
+                    };
+                    if value.as_str() == Some("") && allow_empty_value != &Some(true) {
+                        continue;
+                    }
+                    query_params.extend(serialize_query_param(
+                        &parameter_data.name,
+                        value,
+                        style,
+                        parameter_data.explode.unwrap_or(true),
+                        *allow_reserved,
+                    ));
+                }
+                _ => {}
             }
         }
 
+        if !query_params.is_empty() {
+            let query = query_params.iter().map(|(k, v)| format!("{k}={v}"));
+            url = format!("{url}?{}", query.collect::<Vec<_>>().join("&"));
+        }
+
         // Build request
         let mut request = match method {
             "get" => self.client.get(&url),
@@ -748,108 +1568,229 @@ impl HTTPBridge {
             }
         };
 
-        // Add query parameters and headers
-        let mut query_params = Vec::new();
+        if let Some(timeout) = extensions::timeout(operation) {
+            request = request.timeout(timeout);
+        }
+
+        // Add headers and cookies
+        let mut cookies = Vec::new();
 
-        for param_ref in &operation.parameters {
-            if let ReferenceOr::Item(param) = param_ref {
-                match param {
-                    Parameter::Query { parameter_data, style, .. } => {
-                        if let Some(value) = args.get(&parameter_data.name) {
-                            let serialized = serialize_query_param(
-                                &parameter_data.name,
-                                value,
-                                style,
-                                parameter_data.explode.unwrap_or(true),
-                            );
-                            query_params.extend(serialized);
+        for param in &parameters {
+            match param {
+                Parameter::Header { parameter_data, style } => {
+                    if let Some(header_value) =
+                        args.get("headers").and_then(|headers| headers.get(&parameter_data.name))
+                    {
+                        let serialized = serialize_header_param(
+                            header_value,
+                            style,
+                            parameter_data.explode.unwrap_or(false),
+                        );
+                        // Line breaks would smuggle further headers into the request
+                        if serialized.chars().any(|c| c.is_control() && c != '\t') {
+                            return Err(rmcp::Error::invalid_params(
+                                format!(
+                                    "header '{}' must not contain control characters",
+                                    parameter_data.name
+                                ),
+                                Some(header_value.clone()),
+                            ));
                         }
+                        request = request.header(&parameter_data.name, serialized);
                     }
-                    Parameter::Header { parameter_data, style, .. } => {
-                        if let Some(headers_obj) = args.get("headers") {
-                            if let Some(header_value) = headers_obj.get(&parameter_data.name) {
-                                let serialized = serialize_header_param(
-                                    header_value,
-                                    style,
-                                    parameter_data.explode.unwrap_or(false),
-                                );
-                                request = request.header(&parameter_data.name, serialized);
-                            }
-                        }
+                }
+                Parameter::Cookie { parameter_data, style } => {
+                    if let Some(cookie_value) =
+                        args.get("cookies").and_then(|cookies| cookies.get(&parameter_data.name))
+                    {
+                        cookies.extend(serialize_cookie_param(
+                            &parameter_data.name,
+                            cookie_value,
+                            style,
+                            parameter_data.explode.unwrap_or(true),
+                        ));
                     }
-                    _ => {}
                 }
+                _ => {}
             }
         }
 
-        if !query_params.is_empty() {
-            request = request.query(&query_params);
+        // Send back the cookies the API set earlier in this session
+        if let Some(jar) = &self.cookies
+            && let Ok(url) = reqwest::Url::parse(&url)
+            && let Some(stored) = jar.get(&url)
+        {
+            cookies.push(stored);
         }
 
         // Add request body
         if let Some(body_value) = args.get("body") {
-            request = request.json(body_value);
+            let format = operation
+                .request_body
+                .as_ref()
+                .and_then(|body| reference::resolve(body, spec))
+                .and_then(body::select);
+            request = match format {
+                Some(format) => body::encode(request, &format, body_value, spec)?,
+                None => request.json(body_value),
+            };
         }
 
-        match request.send().await {
-            Ok(response) => {
-                let status = response.status().as_u16();
-
-                let body = response.text().await.map_err(|e| {
-                    rmcp::Error::internal_error(
-                        "failed to read response body",
-                        Some(json!({
-                            "status": status,
-                            "error": e.to_string(),
-                        })),
-                    )
-                })?;
-                if !body.is_empty() {
-                    return Ok(CallToolResult::success(vec![Content::text(body)]));
-                }
-
-                let body = Content::json(json!({
-                    "status": status,
-                }))
-                .expect("failed to create JSON content");
+        let (authorization, cookies) = (authorization.as_ref(), cookies.as_slice());
+        let streamed = progress.is_some();
+        let fetch = |request| self.fetch(&id, operation, request, authorization, cookies, streamed);
+
+        if let Some(pagination) = self.pagination.get(&id, operation) {
+            let token = args.get(NEXT_PAGE).and_then(Value::as_str);
+            return pagination::collect(
+                &pagination,
+                &self.pagination,
+                request,
+                token,
+                &self.response,
+                fetch,
+            )
+            .await;
+        }
 
-                Ok(CallToolResult::success(vec![body]))
-            }
-            Err(e) => {
-                Ok(CallToolResult::error(vec![Content::text(format!("HTTP request failed: {e}"))]))
-            }
+        let mut response = match fetch(request).await? {
+            Ok(response) => response,
+            Err(e) => return Ok(response::failed(e)),
+        };
+        if response.status() == reqwest::StatusCode::ACCEPTED
+            && let Some(polling) = self.polling.get(&id, operation)
+        {
+            response = match polling::wait(
+                &polling,
+                &self.polling,
+                response,
+                &self.client,
+                progress,
+                fetch,
+            )
+            .await?
+            {
+                Ok(response) => response,
+                Err(result) => return Ok(result),
+            };
         }
+        let schema = validators.output.as_ref();
+        response::into_result(response, &self.response, schema, progress).await
     }
 }
 
 impl rmcp::ServerHandler for HTTPBridge {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
-            instructions: Some(format!("HTTP API bridge. Base URL: {}", self.base_url)),
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            instructions: Some(format!(
+                "HTTP API bridge serving:\n{}",
+                self.bridges().map(HTTPBridge::describe).collect::<Vec<_>>().join("\n")
+            )),
+            capabilities: ServerCapabilities::builder()
+                .enable_prompts()
+                .enable_resources()
+                .enable_tools()
+                .enable_tool_list_changed()
+                .build(),
             ..Default::default()
         }
     }
 
     async fn list_tools(
         &self,
-        request: Option<rmcp::model::PaginatedRequestParam>,
+        request: Option<PaginatedRequestParam>,
         _context: RequestContext<RoleServer>,
     ) -> Result<ListToolsResult, rmcp::Error> {
-        let tools = self.tools(request.and_then(|c| c.cursor)).take(10).collect::<Vec<_>>();
-        Ok(ListToolsResult { next_cursor: tools.last().map(|t| t.name.to_string()), tools })
+        let cursor = request.and_then(|request| request.cursor);
+        let (tools, next_cursor) = self.page(self.all_tools(), cursor, |tool| &tool.name)?;
+        Ok(ListToolsResult { next_cursor, tools })
     }
 
     async fn call_tool(
         &self,
         request: CallToolRequestParam,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, rmcp::Error> {
         let name = &request.name;
         let arguments = request.arguments.map(Value::Object).unwrap_or_default();
+        let progress = context
+            .meta
+            .get_progress_token()
+            .map(|token| Progress::new(context.peer.clone(), token));
+
+        // Execute tool directly from spec, dropping the upstream request when
+        // the client cancels the call
+        tokio::select! {
+            result = self.run_tool(name, arguments, progress.as_ref()) => result,
+            _ = context.ct.cancelled() => {
+                // Cancelled calls get no response, but the SDK sends one for
+                // whatever is returned, so hold it back until the session ends
+                while !context.peer.is_transport_closed() {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+                Err(rmcp::Error::internal_error("tool call cancelled", None))
+            }
+        }
+    }
+
+    async fn list_resources(
+        &self,
+        request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, rmcp::Error> {
+        let cursor = request.and_then(|request| request.cursor);
+        let resources = self.bridges().flat_map(|bridge| docs::resources(&bridge.index()));
+        let (resources, next_cursor) =
+            self.page(resources.collect(), cursor, |resource| &resource.uri)?;
+        Ok(ListResourcesResult { next_cursor, resources })
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, rmcp::Error> {
+        for bridge in self.bridges() {
+            let index = bridge.index();
+            let spec = index.spec();
+            let contents = docs::read(&index, &request.uri, |tool| {
+                let (mut parameters, _) =
+                    bridge.operation_inputs(spec, tool.path_item, tool.operation);
+                parameters.retain(|parameter| extensions::exposed(parameter));
+                parameters
+            });
+            if let Some(contents) = contents {
+                return Ok(ReadResourceResult { contents: vec![contents] });
+            }
+        }
+        Err(rmcp::Error::resource_not_found(format!("Resource '{}' not found", request.uri), None))
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, rmcp::Error> {
+        let prompts = self.bridges().flat_map(|bridge| docs::prompts(&bridge.index()));
+        Ok(ListPromptsResult { next_cursor: None, prompts: prompts.collect() })
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, rmcp::Error> {
+        let prompt = self.bridges().find_map(|bridge| docs::prompt(&bridge.index(), &request.name));
+        prompt.ok_or_else(|| {
+            rmcp::Error::invalid_params(format!("Prompt '{}' not found", request.name), None)
+        })
+    }
 
-        // Execute tool directly from spec
-        self.execute_tool(name, arguments).await
+    async fn on_initialized(&self, context: NotificationContext<RoleServer>) {
+        let mut peers = self.peers.lock().await;
+        // Forget the sessions that have closed since
+        peers.retain(|peer| !peer.is_transport_closed());
+        peers.push(context.peer);
     }
 }
 
@@ -1357,6 +2298,202 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_resolve_schema_keywords() {
+        let spec: OpenAPI = serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Test API", "version": "1.0.0"},
+            "paths": {},
+            "components": {
+                "schemas": {
+                    "Pet": {
+                        "title": "Pet",
+                        "oneOf": [
+                            {"$ref": "#/components/schemas/Cat"},
+                            {"$ref": "#/components/schemas/Dog"},
+                        ],
+                        "discriminator": {
+                            "propertyName": "kind",
+                            "mapping": {"dog": "#/components/schemas/Dog"},
+                        },
+                        "nullable": true,
+                    },
+                    "Cat": {
+                        "type": "object",
+                        "properties": {
+                            "id": {"type": "integer", "readOnly": true},
+                            "kind": {"type": "string"},
+                            "mood": {
+                                "type": "string",
+                                "enum": ["calm", "grumpy", null],
+                                "nullable": true,
+                            },
+                            "lives": {
+                                "type": "integer",
+                                "minimum": 0,
+                                "exclusiveMinimum": true,
+                                "maximum": 9,
+                            },
+                        },
+                        "required": ["id"],
+                    },
+                    "Dog": {
+                        "allOf": [{"type": "object"}],
+                        "deprecated": true,
+                        "example": {"kind": "dog"},
+                    },
+                },
+            },
+        }))
+        .unwrap();
+
+        let mut result = resolve_schema(
+            &ReferenceOr::Reference { reference: "#/components/schemas/Pet".to_string() },
+            &spec,
+        );
+        remove_flagged_properties(&mut result, "readOnly");
+
+        assert_json_snapshot!(result, @r###"
+        {
+          "oneOf": [
+            {
+              "properties": {
+                "kind": {
+                  "const": "Cat",
+                  "type": "string"
+                },
+                "lives": {
+                  "exclusiveMinimum": 0,
+                  "maximum": 9,
+                  "type": "integer"
+                },
+                "mood": {
+                  "enum": [
+                    "calm",
+                    "grumpy",
+                    null
+                  ],
+                  "type": [
+                    "string",
+                    "null"
+                  ]
+                }
+              },
+              "required": [
+                "kind"
+              ],
+              "type": "object"
+            },
+            {
+              "allOf": [
+                {
+                  "allOf": [
+                    {
+                      "type": "object"
+                    }
+                  ],
+                  "deprecated": true,
+                  "examples": [
+                    {
+                      "kind": "dog"
+                    }
+                  ]
+                },
+                {
+                  "properties": {
+                    "kind": {
+                      "const": "dog"
+                    }
+                  },
+                  "required": [
+                    "kind"
+                  ]
+                }
+              ]
+            },
+            {
+              "type": "null"
+            }
+          ],
+          "title": "Pet"
+        }
+        "###);
+    }
+
+    #[test]
+    fn test_input_schema_with_defs() {
+        let spec: OpenAPI = serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Test API", "version": "1.0.0"},
+            "paths": {},
+            "components": {
+                "schemas": {
+                    "Node": {
+                        "type": "object",
+                        "properties": {
+                            "id": {"type": "string", "readOnly": true},
+                            "children": {
+                                "type": "array",
+                                "items": {"$ref": "#/components/schemas/Node"},
+                            },
+                        },
+                    },
+                },
+            },
+        }))
+        .unwrap();
+        let operation: Operation = serde_json::from_value(json!({
+            "requestBody": {
+                "content": {
+                    "application/json": {"schema": {"$ref": "#/components/schemas/Node"}},
+                },
+            },
+            "responses": {},
+        }))
+        .unwrap();
+        let path_parameters = serde_json::from_value::<Vec<ReferenceOr<Parameter>>>(json!([{
+            "name": "parent",
+            "in": "query",
+            "schema": {"$ref": "#/components/schemas/Node"},
+        }]))
+        .unwrap();
+
+        let options = SchemaOptions { defs: true, ..Default::default() };
+        let schema =
+            generate_input_schema_with_options(&path_parameters, &operation, &spec, &options);
+
+        assert_json_snapshot!(schema, @r###"
+        {
+          "$defs": {
+            "Node": {
+              "properties": {
+                "children": {
+                  "items": {
+                    "$ref": "#/$defs/Node"
+                  },
+                  "type": "array"
+                }
+              },
+              "type": "object"
+            }
+          },
+          "properties": {
+            "body": {
+              "$ref": "#/$defs/Node"
+            },
+            "parent": {
+              "$ref": "#/$defs/Node"
+            }
+          },
+          "required": [],
+          "type": "object"
+        }
+        "###);
+        let validator = jsonschema::validator_for(&schema).unwrap();
+        assert!(validator.is_valid(&json!({"body": {"children": [{"children": []}]}})));
+        assert!(!validator.is_valid(&json!({"body": {"children": [{"children": 1}]}})));
+    }
+
     #[test]
     fn test_input_validation() {
         let spec = create_simple_spec();
@@ -1863,6 +3000,76 @@ mod tests {
         assert!(!call_result.content.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_http_post_with_multipart_body() {
+        use wiremock::{
+            Mock, MockServer, ResponseTemplate,
+            matchers::{body_string_contains, header_regex, method, path},
+        };
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/documents"))
+            .and(header_regex("content-type", "^multipart/form-data; boundary="))
+            .and(body_string_contains("name=\"title\"\r\n\r\nQuarterly report"))
+            .and(body_string_contains("filename=\"report.txt\""))
+            .and(body_string_contains("Content-Type: text/plain\r\n\r\nhello world"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({"id": 1})))
+            .mount(&mock_server)
+            .await;
+
+        let spec: OpenAPI = serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Documents API", "version": "1.0.0"},
+            "paths": {
+                "/documents": {
+                    "post": {
+                        "operationId": "uploadDocument",
+                        "requestBody": {
+                            "required": true,
+                            "content": {
+                                "multipart/form-data": {
+                                    "schema": {
+                                        "type": "object",
+                                        "properties": {
+                                            "title": {"type": "string"},
+                                            "file": {"type": "string", "format": "binary"}
+                                        },
+                                        "required": ["file"]
+                                    }
+                                }
+                            }
+                        },
+                        "responses": {"201": {"description": "Uploaded"}}
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let client = Arc::new(reqwest::Client::new());
+        let server = HTTPBridge::new(Arc::new(spec), mock_server.uri(), client);
+
+        let arguments = json!({
+            "body": {
+                "title": "Quarterly report",
+                "file": {
+                    "filename": "report.txt",
+                    "contentType": "text/plain",
+                    "data": "aGVsbG8gd29ybGQ="
+                }
+            }
+        });
+
+        let call_result = server.execute_tool("uploadDocument", arguments).await.unwrap();
+        assert_ne!(call_result.is_error, Some(true));
+
+        // Files must be provided as objects with base64 data.
+        let result =
+            server.execute_tool("uploadDocument", json!({"body": {"file": "hello world"}})).await;
+        assert!(result.is_err());
+    }
+
     #[tokio::test]
     async fn test_http_request_server_error() {
         use wiremock::{
@@ -1901,7 +3108,7 @@ mod tests {
         assert!(result.is_ok());
 
         let call_result = result.unwrap();
-        assert!(call_result.is_error != Some(true)); // The bridge still succeeds but returns error content
+        assert_eq!(call_result.is_error, Some(true));
 
         // Just verify we got content back
         assert!(!call_result.content.is_empty());
@@ -1922,6 +3129,444 @@ mod tests {
         // error message would be checked differently
     }
 
+    #[tokio::test]
+    async fn test_read_only_tool_filter() {
+        let spec: OpenAPI = serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Test API", "version": "1.0.0"},
+            "paths": {
+                "/users": {
+                    "get": {"operationId": "listUsers", "responses": {}},
+                    "post": {"operationId": "createUser", "responses": {}},
+                }
+            }
+        }))
+        .unwrap();
+        let server = HTTPBridge::new(
+            Arc::new(spec),
+            "http://localhost:3000".to_string(),
+            Arc::new(reqwest::Client::new()),
+        )
+        .with_tool_filter(ToolFilter { read_only: true, ..Default::default() });
+
+        let names = server.tools(None).map(|tool| tool.name.to_string()).collect::<Vec<_>>();
+        assert_eq!(names, ["listUsers"]);
+
+        let error = server.execute_tool("createUser", json!({})).await.unwrap_err();
+        assert_eq!(error.message, "Tool 'createUser' not found");
+    }
+
+    #[tokio::test]
+    async fn test_resources_and_prompts() {
+        use rmcp::{
+            ServiceExt,
+            model::{
+                GetPromptRequestParam, PromptMessageContent, ReadResourceRequestParam,
+                ResourceContents,
+            },
+        };
+
+        let spec: OpenAPI = serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Pet Store", "version": "1.0.0"},
+            "tags": [{"name": "pets", "description": "Everything about pets."}],
+            "paths": {
+                "/pets/{id}": {
+                    "parameters": [{
+                        "name": "id",
+                        "in": "path",
+                        "required": true,
+                        "description": "Pet ID",
+                        "example": 7,
+                        "schema": {"type": "integer"},
+                    }],
+                    "get": {
+                        "operationId": "getPet",
+                        "tags": ["pets"],
+                        "summary": "Get a pet",
+                        "responses": {
+                            "200": {
+                                "description": "The pet",
+                                "content": {"application/json": {
+                                    "schema": {"$ref": "#/components/schemas/Pet"},
+                                    "example": {"id": 7, "name": "Rex"},
+                                }},
+                            },
+                            "404": {"description": "No such pet"},
+                        },
+                    },
+                    "put": {
+                        "operationId": "updatePet",
+                        "tags": ["pets"],
+                        "summary": "Update a pet",
+                        "x-brwse-description": "Replace a pet",
+                        "requestBody": {"content": {"application/json": {
+                            "schema": {"$ref": "#/components/schemas/Pet"},
+                        }}},
+                        "responses": {},
+                    },
+                },
+                "/health": {"get": {"operationId": "health", "responses": {}}},
+            },
+            "components": {"schemas": {"Pet": {
+                "type": "object",
+                "properties": {"id": {"type": "integer"}, "name": {"type": "string"}},
+            }}},
+        }))
+        .unwrap();
+        let bridge = HTTPBridge::new(
+            Arc::new(spec),
+            "http://localhost:3000".to_string(),
+            Arc::new(reqwest::Client::new()),
+        );
+        let (server_io, client_io) = tokio::io::duplex(4096);
+        let (server, client) = tokio::join!(bridge.serve(server_io), ().serve(client_io));
+        let (_server, client) = (server.unwrap(), client.unwrap());
+
+        let resources = client.list_all_resources().await.unwrap();
+        let uris = resources.iter().map(|resource| resource.uri.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            uris,
+            [
+                "openapi://spec",
+                "openapi://schemas/Pet",
+                "openapi://operations/health",
+                "openapi://operations/getPet",
+                "openapi://operations/updatePet",
+            ]
+        );
+
+        let read =
+            |uri: &str| client.read_resource(ReadResourceRequestParam { uri: uri.to_string() });
+        let contents = read("openapi://operations/getPet").await.unwrap().contents;
+        let ResourceContents::TextResourceContents { text, .. } = &contents[0] else {
+            panic!("expected text contents");
+        };
+        assert_snapshot!(text, @r###"
+        # getPet
+
+        `GET /pets/{id}`
+
+        Get a pet
+
+        ## Parameters
+
+        - `id` (in path, required): Pet ID Example: `7`
+
+        ## Responses
+
+        ### 200
+
+        The pet
+
+        Media type: `application/json`
+
+        Example:
+
+        ```
+        {
+          "id": 7,
+          "name": "Rex"
+        }
+        ```
+
+        ### 404
+
+        No such pet
+        "###);
+        assert!(read("openapi://schemas/Cat").await.is_err());
+
+        let prompts = client.list_all_prompts().await.unwrap();
+        assert_eq!(prompts.iter().map(|prompt| prompt.name.as_str()).collect::<Vec<_>>(), ["pets"]);
+        let prompt = client
+            .get_prompt(GetPromptRequestParam { name: "pets".to_string(), arguments: None })
+            .await
+            .unwrap();
+        let PromptMessageContent::Text { text } = &prompt.messages[0].content else {
+            panic!("expected a text message");
+        };
+        assert_snapshot!(text, @r###"
+        You are working with the pets operations of Pet Store (version 1.0.0).
+
+        Everything about pets.
+
+        These tools are available:
+
+        - `getPet`: Get a pet
+        - `updatePet`: Replace a pet
+
+        Before calling a tool, read its documentation at `openapi://operations/<tool>` for its parameters, examples and possible responses. The shapes of the data exchanged are described at `openapi://schemas/<schema>`.
+        "###);
+    }
+
+    #[tokio::test]
+    async fn test_list_tools_pagination() {
+        use rmcp::{ServiceExt, model::PaginatedRequestParam};
+
+        let spec: OpenAPI = serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Test API", "version": "1.0.0"},
+            "paths": {
+                "/users": {
+                    "get": {"operationId": "listUsers", "responses": {}},
+                    "post": {"operationId": "createUser", "responses": {}},
+                },
+                "/users/{id}": {
+                    "delete": {"operationId": "deleteUser", "responses": {}},
+                }
+            }
+        }))
+        .unwrap();
+        let bridge = HTTPBridge::new(
+            Arc::new(spec),
+            "http://localhost:3000".to_string(),
+            Arc::new(reqwest::Client::new()),
+        )
+        .with_page_size(2);
+        let (server_io, client_io) = tokio::io::duplex(4096);
+        let (server, client) = tokio::join!(bridge.serve(server_io), ().serve(client_io));
+        let (_server, client) = (server.unwrap(), client.unwrap());
+
+        let page = |cursor: Option<&str>| {
+            let cursor = cursor.map(str::to_string);
+            client.list_tools(Some(PaginatedRequestParam { cursor }))
+        };
+        let first = page(None).await.unwrap();
+        let names = first.tools.iter().map(|tool| tool.name.as_ref()).collect::<Vec<_>>();
+        assert_eq!(names, ["listUsers", "createUser"]);
+        assert_eq!(first.next_cursor.as_deref(), Some("createUser"));
+
+        let second = page(Some("createUser")).await.unwrap();
+        let names = second.tools.iter().map(|tool| tool.name.as_ref()).collect::<Vec<_>>();
+        assert_eq!(names, ["deleteUser"]);
+        assert_eq!(second.next_cursor, None);
+
+        assert!(page(Some("unknown")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reload_notifies_tool_list_changed() {
+        use rmcp::{ClientHandler, RoleClient, ServiceExt};
+        use tokio::sync::mpsc;
+
+        struct Watcher(mpsc::UnboundedSender<()>);
+
+        impl ClientHandler for Watcher {
+            async fn on_tool_list_changed(&self, _context: NotificationContext<RoleClient>) {
+                self.0.send(()).unwrap();
+            }
+        }
+
+        let spec = |paths: serde_json::Value| -> OpenAPI {
+            serde_json::from_value(json!({
+                "openapi": "3.0.0",
+                "info": {"title": "Test API", "version": "1.0.0"},
+                "paths": paths,
+            }))
+            .unwrap()
+        };
+        let get = json!({"get": {"operationId": "listUsers", "responses": {}}});
+
+        let bridge = HTTPBridge::new(
+            Arc::new(spec(json!({"/users": get}))),
+            "http://localhost:3000".to_string(),
+            Arc::new(reqwest::Client::new()),
+        );
+        let (notifications, mut received) = mpsc::unbounded_channel();
+        let (server_io, client_io) = tokio::io::duplex(4096);
+        let (server, client) =
+            tokio::join!(bridge.clone().serve(server_io), Watcher(notifications).serve(client_io));
+        let (server, client) = (server.unwrap(), client.unwrap());
+
+        // A new spec that generates the same tools is swapped in silently.
+        let mut same = spec(json!({"/users": get}));
+        same.info.version = "1.0.1".to_string();
+        assert!(!bridge.reload(same).await);
+        assert_eq!(bridge.spec().info.version, "1.0.1");
+
+        let changed = spec(json!({
+            "/users": get,
+            "/users/{id}": {"delete": {"operationId": "deleteUser", "responses": {}}},
+        }));
+        assert!(bridge.reload(changed).await);
+        received.recv().await.unwrap();
+        assert!(received.try_recv().is_err());
+
+        let tools = client.list_tools(None).await.unwrap().tools;
+        let names = tools.iter().map(|tool| tool.name.as_ref()).collect::<Vec<_>>();
+        assert_eq!(names, ["listUsers", "deleteUser"]);
+
+        // Closed sessions are forgotten once another one starts
+        client.cancel().await.unwrap();
+        server.waiting().await.unwrap();
+        let (notifications, _received) = mpsc::unbounded_channel();
+        let (server_io, client_io) = tokio::io::duplex(4096);
+        let (server, client) =
+            tokio::join!(bridge.clone().serve(server_io), Watcher(notifications).serve(client_io));
+        let (_server, _client) = (server.unwrap(), client.unwrap());
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while bridge.peers.lock().await.len() != 1 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_streamed_response_progress_and_cancellation() {
+        use rmcp::{
+            ClientHandler, RoleClient, ServiceExt,
+            model::{
+                ClientRequest, Meta, NumberOrString, ProgressNotificationParam, ProgressToken,
+                Request, ServerResult,
+            },
+            service::PeerRequestOptions,
+        };
+        use tokio::{
+            io::{AsyncReadExt, AsyncWriteExt},
+            net::TcpListener,
+            sync::{mpsc, oneshot},
+        };
+        use wiremock::{
+            Mock, MockServer, ResponseTemplate,
+            matchers::{method, path},
+        };
+
+        struct Listener(mpsc::UnboundedSender<ProgressNotificationParam>);
+
+        impl ClientHandler for Listener {
+            async fn on_progress(
+                &self,
+                params: ProgressNotificationParam,
+                _context: NotificationContext<RoleClient>,
+            ) {
+                self.0.send(params).unwrap();
+            }
+        }
+
+        let events =
+            "data: started\n\n: ping\n\nevent: done\ndata: {\"id\": 1}\ndata: {\"id\": 2}\n\n";
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/events"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_raw(events.as_bytes(), "text/event-stream"),
+            )
+            .mount(&mock_server)
+            .await;
+
+        // An upstream that sends one chunk, then waits for the request to be
+        // dropped
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream = format!("http://{}", listener.local_addr().unwrap());
+        let (closed, upstream_closed) = oneshot::channel();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let (mut request, mut buffer) = (Vec::new(), [0; 1024]);
+            while !request.ends_with(b"\r\n\r\n") {
+                let read = socket.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+            }
+            let response = "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\
+                            Transfer-Encoding: chunked\r\n\r\n7\r\nworking\r\n";
+            socket.write_all(response.as_bytes()).await.unwrap();
+            while socket.read(&mut buffer).await.is_ok_and(|read| read > 0) {}
+            closed.send(()).unwrap();
+        });
+
+        // An upstream streaming events for longer than tool calls may take
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let dripping = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let _ = socket.read(&mut [0; 1024]).await.unwrap();
+            let headers = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\
+                           Transfer-Encoding: chunked\r\n\r\n";
+            socket.write_all(headers.as_bytes()).await.unwrap();
+            for line in 1..=4 {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                let event = format!("data: {line}\n\n");
+                let chunk = format!("{:x}\r\n{event}\r\n", event.len());
+                socket.write_all(chunk.as_bytes()).await.unwrap();
+            }
+            socket.write_all(b"0\r\n\r\n").await.unwrap();
+        });
+
+        let spec: OpenAPI = serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Test API", "version": "1.0.0"},
+            "paths": {
+                "/events": {"get": {"operationId": "watchEvents", "responses": {}}},
+                "/jobs": {"get": {
+                    "operationId": "followJob",
+                    "servers": [{"url": upstream}],
+                    "responses": {},
+                }},
+                "/logs": {"get": {
+                    "operationId": "tailLogs",
+                    "servers": [{"url": dripping}],
+                    "responses": {},
+                }},
+            },
+        }))
+        .unwrap();
+        let retry =
+            RetryOptions { timeout: Some(Duration::from_millis(200)), ..Default::default() };
+        let bridge =
+            HTTPBridge::new(Arc::new(spec), mock_server.uri(), Arc::new(reqwest::Client::new()))
+                .with_spec_servers(HashMap::new())
+                .with_retry_options(retry);
+
+        let (notifications, mut received) = mpsc::unbounded_channel();
+        let (server_io, client_io) = tokio::io::duplex(4096);
+        let (server, client) =
+            tokio::join!(bridge.serve(server_io), Listener(notifications).serve(client_io));
+        let (_server, client) = (server.unwrap(), client.unwrap());
+
+        let call = async |name: &str, token: u32| {
+            let mut meta = Meta::new();
+            meta.set_progress_token(ProgressToken(NumberOrString::Number(token)));
+            let request = ClientRequest::CallToolRequest(Request::new(CallToolRequestParam {
+                name: name.to_string().into(),
+                arguments: Some(serde_json::Map::new()),
+            }));
+            let options = PeerRequestOptions { timeout: None, meta: Some(meta) };
+            client.send_cancellable_request(request, options).await.unwrap()
+        };
+
+        let response = call("watchEvents", 1).await.await_response().await.unwrap();
+        let ServerResult::CallToolResult(result) = response else {
+            panic!("unexpected response: {response:?}");
+        };
+        assert_eq!(result.content[0].as_text().unwrap().text, events);
+        let mut messages = Vec::new();
+        while let Ok(notification) = received.try_recv() {
+            assert_eq!(notification.progress_token, ProgressToken(NumberOrString::Number(1)));
+            assert_eq!(notification.progress, messages.len() as u32 + 1);
+            messages.push(notification.message.unwrap());
+        }
+        assert_eq!(messages, ["started", "{\"id\": 1}\n{\"id\": 2}"]);
+
+        // Streams go on past the call timeout while they keep sending
+        let response = call("tailLogs", 3).await.await_response().await.unwrap();
+        let ServerResult::CallToolResult(result) = response else {
+            panic!("unexpected response: {response:?}");
+        };
+        assert_eq!(result.is_error, Some(false));
+        let mut messages = Vec::new();
+        while let Ok(notification) = received.try_recv() {
+            messages.push(notification.message.unwrap());
+        }
+        assert_eq!(messages, ["1", "2", "3", "4"]);
+
+        let handle = call("followJob", 2).await;
+        let notification = received.recv().await.unwrap();
+        assert_eq!(notification.message.as_deref(), Some("working"));
+        handle.cancel(None).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), upstream_closed).await.unwrap().unwrap();
+    }
+
     #[tokio::test]
     async fn test_bearer_token_authentication() {
         use wiremock::{
@@ -2700,7 +4345,7 @@ mod tests {
         assert!(result.is_ok());
 
         let call_result = result.unwrap();
-        assert!(call_result.is_error != Some(true)); // HTTP errors are still considered "successful" tool calls
+        assert_eq!(call_result.is_error, Some(true));
 
         // Just verify we got content back
         assert!(!call_result.content.is_empty());
@@ -2791,14 +4436,20 @@ mod tests {
             &json!(["tag1", "tag2", "tag3"]),
             &QueryStyle::SpaceDelimited,
             false,
+            false,
         );
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].0, "tags");
-        assert_eq!(result[0].1, "tag1 tag2 tag3");
+        assert_eq!(result[0].1, "tag1%20tag2%20tag3");
 
         // Test pipe delimited arrays
-        let result =
-            serialize_query_param("ids", &json!([1, 2, 3]), &QueryStyle::PipeDelimited, false);
+        let result = serialize_query_param(
+            "ids",
+            &json!([1, 2, 3]),
+            &QueryStyle::PipeDelimited,
+            false,
+            false,
+        );
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].0, "ids");
         assert_eq!(result[0].1, "1|2|3");
@@ -2809,12 +4460,72 @@ mod tests {
             &json!({"name": "john", "age": 30}),
             &QueryStyle::DeepObject,
             true,
+            false,
         );
         assert_eq!(result.len(), 2);
         // Results should contain filter[name]=john and filter[age]=30
         let names: Vec<String> = result.iter().map(|(k, _)| k.clone()).collect();
         assert!(names.contains(&"filter[name]".to_string()));
         assert!(names.contains(&"filter[age]".to_string()));
+
+        // Reserved characters are only kept with allowReserved
+        let value = json!("a/b?c=d&e#f g");
+        let result = serialize_query_param("q", &value, &QueryStyle::Form, true, false);
+        assert_eq!(result, [("q".to_string(), "a%2Fb%3Fc%3Dd%26e%23f%20g".to_string())]);
+        let result = serialize_query_param("q", &value, &QueryStyle::Form, true, true);
+        assert_eq!(result, [("q".to_string(), "a/b?c=d%26e%23f%20g".to_string())]);
+    }
+
+    #[test]
+    fn test_path_param_encoding() {
+        let value = json!("a/b?x=1");
+        assert_eq!(serialize_path_param("id", &value, &PathStyle::Simple, false), "a%2Fb%3Fx%3D1");
+        let value = json!(["a,b", "c d"]);
+        assert_eq!(serialize_path_param("id", &value, &PathStyle::Label, true), ".a%2Cb.c%20d");
+
+        assert!(is_path_traversal(&json!("..")));
+        assert!(is_path_traversal(&json!("../admin")));
+        assert!(is_path_traversal(&json!(["a", "b/./c"])));
+        assert!(is_path_traversal(&json!({"a": "..\\b"})));
+        assert!(!is_path_traversal(&json!("..a/b.c")));
+    }
+
+    #[tokio::test]
+    async fn test_unsafe_parameters_rejected() {
+        let spec: OpenAPI = serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Test API", "version": "1.0.0"},
+            "paths": {
+                "/files/{name}": {
+                    "get": {
+                        "operationId": "getFile",
+                        "parameters": [
+                            {
+                                "name": "name",
+                                "in": "path",
+                                "required": true,
+                                "schema": {"type": "string"},
+                            },
+                            {"name": "X-Trace", "in": "header", "schema": {"type": "string"}},
+                        ],
+                        "responses": {},
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        let server = HTTPBridge::new(
+            Arc::new(spec),
+            "http://localhost:3000".to_string(),
+            Arc::new(reqwest::Client::new()),
+        );
+
+        let error = server.execute_tool("getFile", json!({"name": "../admin"})).await.unwrap_err();
+        assert_eq!(error.message, "path parameter 'name' must not contain '.' or '..' segments");
+
+        let arguments = json!({"name": "a", "headers": {"X-Trace": "1\r\nX-Admin: true"}});
+        let error = server.execute_tool("getFile", arguments).await.unwrap_err();
+        assert_eq!(error.message, "header 'X-Trace' must not contain control characters");
     }
 
     #[tokio::test]
@@ -2841,6 +4552,167 @@ mod tests {
         assert!(result.contains("level") && result.contains("5"));
     }
 
+    #[tokio::test]
+    async fn test_operation_servers() {
+        use wiremock::{
+            Mock, MockServer, ResponseTemplate,
+            matchers::{method, path},
+        };
+
+        let mock_server = MockServer::start().await;
+        for route in ["/v2/users", "/v2/teams"] {
+            Mock::given(method("GET"))
+                .and(path(route))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+                .mount(&mock_server)
+                .await;
+        }
+
+        let spec: OpenAPI = serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Test API", "version": "1.0.0"},
+            "servers": [{"url": "http://localhost:1"}],
+            "paths": {
+                "/users": {
+                    "servers": [{
+                        "url": "{origin}/{version}",
+                        "variables": {
+                            "origin": {"default": "http://localhost:1"},
+                            "version": {"default": "v1", "enum": ["v1", "v2"]},
+                        },
+                    }],
+                    "get": {"operationId": "listUsers", "responses": {}},
+                },
+                "/teams": {
+                    "servers": [{"url": "/v2"}],
+                    "get": {"operationId": "listTeams", "responses": {}},
+                },
+            }
+        }))
+        .unwrap();
+        let spec = Arc::new(spec);
+        let server = HTTPBridge::new(
+            Arc::clone(&spec),
+            "http://localhost:1".to_string(),
+            Arc::new(reqwest::Client::new()),
+        );
+        let variables = |version: &str| {
+            HashMap::from([
+                ("origin".to_string(), mock_server.uri()),
+                ("version".to_string(), version.to_string()),
+            ])
+        };
+
+        let result = server.clone().with_spec_servers(variables("v2"));
+        let result = result.execute_tool("listUsers", json!({})).await.unwrap();
+        assert_ne!(result.is_error, Some(true));
+
+        let result = server.with_spec_servers(variables("v3"));
+        let error = result.execute_tool("listUsers", json!({})).await.unwrap_err();
+        assert_eq!(
+            error.message,
+            "invalid value `v3` for server variable `version`, expected one of: v1, v2"
+        );
+
+        let server = HTTPBridge::new(spec, mock_server.uri(), Arc::new(reqwest::Client::new()))
+            .with_spec_servers(HashMap::new());
+        let result = server.execute_tool("listTeams", json!({})).await.unwrap();
+        assert_ne!(result.is_error, Some(true));
+    }
+
+    #[test]
+    fn test_cookie_serialization() {
+        let form = CookieStyle::Form;
+        assert_eq!(serialize_cookie_param("id", &json!("a b;c"), &form, true), ["id=a%20b%3Bc"]);
+        assert_eq!(serialize_cookie_param("id", &json!([3, 4]), &form, true), ["id=3", "id=4"]);
+        assert_eq!(serialize_cookie_param("id", &json!([3, 4]), &form, false), ["id=3,4"]);
+
+        let object = json!({"role": "admin", "firstName": "Alex"});
+        assert_eq!(
+            serialize_cookie_param("id", &object, &form, true),
+            ["firstName=Alex", "role=admin"]
+        );
+        assert_eq!(
+            serialize_cookie_param("id", &object, &form, false),
+            ["id=firstName,Alex,role,admin"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_session_cookies() {
+        use wiremock::{
+            Mock, MockServer, ResponseTemplate,
+            matchers::{header, method, path},
+        };
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/login"))
+            .respond_with(
+                ResponseTemplate::new(204).insert_header("Set-Cookie", "session=abc123; Path=/"),
+            )
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/me"))
+            .and(header("Cookie", "theme=dark; session=abc123"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"name": "Alex"})))
+            .mount(&mock_server)
+            .await;
+
+        let spec: Arc<OpenAPI> = serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Test API", "version": "1.0.0"},
+            "paths": {
+                "/login": {"post": {"operationId": "login", "responses": {}}},
+                "/me": {
+                    "get": {
+                        "operationId": "getMe",
+                        "parameters": [{
+                            "name": "theme",
+                            "in": "cookie",
+                            "required": true,
+                            "schema": {"type": "string"},
+                        }],
+                        "responses": {},
+                    }
+                },
+            }
+        }))
+        .unwrap();
+        let server =
+            HTTPBridge::new(Arc::clone(&spec), mock_server.uri(), Arc::new(reqwest::Client::new()))
+                .with_session_cookies(true);
+
+        let get_me = server.tools(None).find(|tool| tool.name == "getMe").unwrap();
+        assert_eq!(get_me.input_schema["required"], json!(["cookies"]));
+        assert_eq!(get_me.input_schema["properties"]["cookies"]["required"], json!(["theme"]));
+
+        let arguments = json!({"cookies": {"theme": "dark"}});
+        let result = server.execute_tool("getMe", arguments.clone()).await.unwrap();
+        assert_eq!(result.is_error, Some(true));
+
+        server.execute_tool("login", json!({})).await.unwrap();
+        let result = server.execute_tool("getMe", arguments.clone()).await.unwrap();
+        assert_ne!(result.is_error, Some(true));
+
+        // Clones keep the cookies, while each session starts without any
+        let result = server.clone().execute_tool("getMe", arguments.clone()).await.unwrap();
+        assert_ne!(result.is_error, Some(true));
+        let result = server.for_session().execute_tool("getMe", arguments.clone()).await.unwrap();
+        assert_eq!(result.is_error, Some(true));
+
+        // Including for the APIs served alongside
+        let bridge = HTTPBridge::new(spec, mock_server.uri(), Arc::new(reqwest::Client::new()))
+            .with_api(server.with_namespace("app"));
+        let (first, second) = (bridge.for_session(), bridge.for_session());
+        first.execute_tool("app_login", json!({})).await.unwrap();
+        let result = first.execute_tool("app_getMe", arguments.clone()).await.unwrap();
+        assert_ne!(result.is_error, Some(true));
+        let result = second.execute_tool("app_getMe", arguments).await.unwrap();
+        assert_eq!(result.is_error, Some(true));
+    }
+
     #[test]
     fn test_schema_with_all_parameter_types() {
         let operation = Operation {
@@ -2979,4 +4851,650 @@ mod tests {
         }
         "###);
     }
+
+    #[tokio::test]
+    async fn test_pagination() {
+        use base64::{Engine as _, prelude::BASE64_URL_SAFE_NO_PAD};
+        use wiremock::{
+            Mock, MockServer, ResponseTemplate,
+            matchers::{method, path, query_param, query_param_is_missing},
+        };
+
+        let mock_server = MockServer::start().await;
+        let page = |body: Value, next: Option<&str>| {
+            let template = ResponseTemplate::new(200).set_body_json(body);
+            match next {
+                Some(next) => template.insert_header("Link", format!("<{next}>; rel=\"next\"")),
+                None => template,
+            }
+        };
+        Mock::given(method("GET"))
+            .and(path("/users"))
+            .and(query_param_is_missing("page"))
+            .respond_with(page(json!([1, 2]), Some("/users?limit=2&page=2")))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/users"))
+            .and(query_param("page", "2"))
+            .respond_with(page(json!([3, 4]), Some("/users?limit=2&page=3")))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/users"))
+            .and(query_param("page", "3"))
+            .respond_with(page(json!([5]), Some("http://localhost:1/users?page=4")))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/events"))
+            .and(query_param_is_missing("cursor"))
+            .respond_with(page(json!({"data": [{"id": "a"}], "meta": {"cursor": "c2"}}), None))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/events"))
+            .and(query_param("cursor", "c2"))
+            .respond_with(page(json!({"data": [{"id": "b"}], "meta": {"cursor": null}}), None))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/orders"))
+            .and(query_param_is_missing("cursor"))
+            .respond_with(page(json!({"data": [1], "next": "o2"}), None))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/orders"))
+            .and(query_param("cursor", "o2"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("try again later"))
+            .mount(&mock_server)
+            .await;
+
+        let spec: OpenAPI = serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Test API", "version": "1.0.0"},
+            "paths": {
+                "/users": {"get": {"operationId": "listUsers", "responses": {}}},
+                "/events": {"get": {
+                    "operationId": "listEvents",
+                    "responses": {},
+                    "x-brwse-pagination": {
+                        "items": "/data",
+                        "next": "/meta/cursor",
+                        "cursorParam": "cursor",
+                    },
+                }},
+                "/orders": {"get": {
+                    "operationId": "listOrders",
+                    "responses": {},
+                    "x-brwse-pagination": {
+                        "items": "/data",
+                        "next": "/next",
+                        "cursorParam": "cursor",
+                    },
+                }},
+                "/health": {"get": {"operationId": "health", "responses": {}}},
+            }
+        }))
+        .unwrap();
+        let server =
+            HTTPBridge::new(Arc::new(spec), mock_server.uri(), Arc::new(reqwest::Client::new()))
+                .with_pagination(PaginationOptions {
+                    operations: HashMap::from([("listUsers".to_string(), Pagination::default())]),
+                    max_pages: 2,
+                    max_items: None,
+                });
+        let tools = server.tools(None).collect::<Vec<_>>();
+        let paginated =
+            tools.iter().filter(|tool| tool.input_schema["properties"].get(NEXT_PAGE).is_some());
+        assert_eq!(
+            paginated.map(|tool| tool.name.as_ref()).collect::<Vec<_>>(),
+            ["listEvents", "listOrders", "listUsers"]
+        );
+
+        let call = async |name: &str, arguments: Value| {
+            let result = server.execute_tool(name, arguments).await.unwrap();
+            assert_ne!(result.is_error, Some(true));
+            serde_json::from_str::<Value>(&result.content[0].as_text().unwrap().text).unwrap()
+        };
+        let result = call("listUsers", json!({})).await;
+        assert_eq!(result["items"], json!([1, 2, 3, 4]));
+        assert_eq!(result["pages"], 2);
+
+        // The last page links to another host, which is not followed
+        let result = call("listUsers", json!({"nextPage": result["nextPage"]})).await;
+        assert_json_snapshot!(result, @r###"
+        {
+          "items": [
+            5
+          ],
+          "pages": 1
+        }
+        "###);
+
+        let result = call("listEvents", json!({})).await;
+        assert_json_snapshot!(result, @r###"
+        {
+          "items": [
+            {
+              "id": "a"
+            },
+            {
+              "id": "b"
+            }
+          ],
+          "pages": 2
+        }
+        "###);
+
+        // A failing later page keeps the items so far and says what went wrong
+        let mut result = call("listOrders", json!({})).await;
+        let next_page = result.as_object_mut().unwrap().remove(NEXT_PAGE).unwrap();
+        let next_page = BASE64_URL_SAFE_NO_PAD.decode(next_page.as_str().unwrap()).unwrap();
+        assert!(String::from_utf8(next_page).unwrap().ends_with("/orders?cursor=o2"));
+        assert_json_snapshot!(result, @r###"
+        {
+          "error": {
+            "message": "try again later",
+            "status": 503
+          },
+          "items": [
+            1
+          ],
+          "pages": 1
+        }
+        "###);
+
+        let token = BASE64_URL_SAFE_NO_PAD.encode("http://localhost:1/users");
+        let error = server.execute_tool("listUsers", json!({"nextPage": token})).await;
+        assert_eq!(error.unwrap_err().message, "invalid nextPage token");
+    }
+
+    #[tokio::test]
+    async fn test_vendor_extensions() {
+        use wiremock::{
+            Mock, MockServer, ResponseTemplate,
+            matchers::{header, method, path, query_param, query_param_is_missing},
+        };
+
+        let mock_server = MockServer::start().await;
+        for limit in ["20", "5"] {
+            Mock::given(method("GET"))
+                .and(path("/reports"))
+                .and(query_param("region", "eu"))
+                .and(query_param("limit", limit))
+                .and(header("x-tenant", "acme"))
+                .and(query_param_is_missing("debug"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({"limit": limit})))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/exports"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+            .mount(&mock_server)
+            .await;
+
+        let spec: OpenAPI = serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Test API", "version": "1.0.0"},
+            "paths": {
+                "/reports": {"get": {
+                    "operationId": "getReportV2",
+                    "summary": "Get report (v2)",
+                    "x-brwse-name": "get_report",
+                    "x-brwse-description": "Fetches the usage report of the tenant",
+                    "x-brwse-requires-confirmation": true,
+                    "parameters": [
+                        {"in": "query", "name": "region", "schema": {"type": "string"}, "x-brwse-value": "eu"},
+                        {
+                            "in": "query",
+                            "name": "limit",
+                            "required": true,
+                            "schema": {"type": "integer"},
+                            "x-brwse-default": 20,
+                            "x-brwse-description": "Rows to return",
+                        },
+                        {"in": "header", "name": "X-Tenant", "schema": {"type": "string"}, "x-brwse-value": "acme"},
+                        {"in": "query", "name": "debug", "schema": {"type": "string"}, "x-brwse-hidden": true},
+                    ],
+                    "responses": {},
+                }},
+                "/exports": {"get": {
+                    "operationId": "listExports",
+                    "x-brwse-timeout": 0.05,
+                    "responses": {},
+                }},
+                "/internal": {"get": {
+                    "operationId": "internalStats",
+                    "x-brwse-hidden": true,
+                    "responses": {},
+                }},
+            }
+        }))
+        .unwrap();
+        let server =
+            HTTPBridge::new(Arc::new(spec), mock_server.uri(), Arc::new(reqwest::Client::new()));
+
+        let tools = server.tools(None).collect::<Vec<_>>();
+        assert_json_snapshot!(tools[1], @r###"
+        {
+          "name": "get_report",
+          "description": "Fetches the usage report of the tenant\n\nAsk the user to confirm before calling this tool.",
+          "inputSchema": {
+            "properties": {
+              "limit": {
+                "default": 20,
+                "description": "Rows to return",
+                "type": "integer"
+              }
+            },
+            "required": [],
+            "type": "object"
+          }
+        }
+        "###);
+        assert_eq!(
+            tools.iter().map(|tool| tool.name.as_ref()).collect::<Vec<_>>(),
+            ["listExports", "get_report"]
+        );
+        assert_eq!(tools[0].description.as_deref(), Some("GET /exports"));
+        assert!(server.requires_confirmation("get_report"));
+        assert!(!server.requires_confirmation("listExports"));
+
+        // Fixed values override whatever agents send, and hidden parameters are dropped
+        for arguments in [json!({}), json!({"limit": 5, "region": "us", "debug": "1"})] {
+            let result = server.execute_tool("get_report", arguments).await.unwrap();
+            assert_eq!(result.is_error, Some(false));
+        }
+        let result = server.execute_tool("listExports", json!({})).await.unwrap();
+        assert_eq!(result.is_error, Some(true));
+        assert!(server.execute_tool("internalStats", json!({})).await.is_err());
+
+        // The served spec shows neither hidden operations nor the settings of the
+        // bridge
+        let Some(rmcp::model::ResourceContents::TextResourceContents { text, .. }) =
+            docs::read(&server.index(), "openapi://spec", |_| Vec::new())
+        else {
+            panic!("expected the spec");
+        };
+        let served = serde_json::from_str::<Value>(&text).unwrap();
+        assert_json_snapshot!(served["paths"], @r###"
+        {
+          "/exports": {
+            "get": {
+              "operationId": "listExports",
+              "responses": {}
+            }
+          },
+          "/reports": {
+            "get": {
+              "operationId": "getReportV2",
+              "parameters": [
+                {
+                  "in": "query",
+                  "name": "limit",
+                  "required": true,
+                  "schema": {
+                    "type": "integer"
+                  },
+                  "style": "form"
+                }
+              ],
+              "responses": {},
+              "summary": "Get report (v2)"
+            }
+          }
+        }
+        "###);
+    }
+
+    #[tokio::test]
+    async fn test_operation_timeout() {
+        use wiremock::{
+            Mock, MockServer, ResponseTemplate,
+            matchers::{method, path},
+        };
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/exports"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(300)))
+            .mount(&mock_server)
+            .await;
+
+        let spec: OpenAPI = serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Test API", "version": "1.0.0"},
+            "paths": {
+                "/exports": {"get": {
+                    "operationId": "listExports",
+                    "x-brwse-timeout": 5,
+                    "responses": {},
+                }},
+            }
+        }))
+        .unwrap();
+        // Operations taking longer than the client and call timeouts allow
+        let timeout = Duration::from_millis(100);
+        let client = reqwest::Client::builder().timeout(timeout).build().unwrap();
+        let server = HTTPBridge::new(Arc::new(spec), mock_server.uri(), Arc::new(client))
+            .with_retry_options(RetryOptions { timeout: Some(timeout), ..Default::default() });
+
+        let result = server.execute_tool("listExports", json!({})).await.unwrap();
+        assert_eq!(result.is_error, Some(false));
+    }
+
+    #[tokio::test]
+    async fn test_polling() {
+        use wiremock::{
+            Mock, MockServer, ResponseTemplate,
+            matchers::{method, path},
+        };
+
+        let mock_server = MockServer::start().await;
+        let accepted =
+            |location: &str| ResponseTemplate::new(202).insert_header("Location", location);
+        let mount = async |method_name: &str, route: &str, response: ResponseTemplate, times| {
+            let mock = Mock::given(method(method_name)).and(path(route)).respond_with(response);
+            let mock = match times {
+                Some(times) => mock.up_to_n_times(times),
+                None => mock,
+            };
+            mock.mount(&mock_server).await;
+        };
+        for route in ["/exports", "/imports", "/jobs", "/slow", "/plain"] {
+            mount("POST", route, accepted(&format!("{route}/1")), None).await;
+        }
+        // A status document that eventually points at the export
+        let running = ResponseTemplate::new(200).set_body_json(json!({"status": "running"}));
+        mount("GET", "/exports/1", running, Some(1)).await;
+        let succeeded = json!({"status": "Succeeded", "resourceLocation": "/files/1"});
+        mount("GET", "/exports/1", ResponseTemplate::new(200).set_body_json(succeeded), None).await;
+        let file = ResponseTemplate::new(200).set_body_json(json!({"id": 1, "rows": 3}));
+        mount("GET", "/files/1", file, None).await;
+        // A status URL that redirects to the dataset once imported
+        mount("GET", "/imports/1", accepted("/imports/1"), Some(1)).await;
+        let see_other = ResponseTemplate::new(303).insert_header("Location", "/datasets/7");
+        mount("GET", "/imports/1", see_other, None).await;
+        let dataset = ResponseTemplate::new(200).set_body_json(json!({"id": 7}));
+        mount("GET", "/datasets/7", dataset, None).await;
+        let failed = json!({"status": "failed", "error": "disk full"});
+        mount("GET", "/jobs/1", ResponseTemplate::new(200).set_body_json(failed), None).await;
+        mount("GET", "/slow/1", ResponseTemplate::new(202), None).await;
+
+        let post = |operation_id: &str, polling: Option<Value>| {
+            let mut operation = json!({"operationId": operation_id, "responses": {}});
+            if let Some(polling) = polling {
+                operation["x-brwse-polling"] = polling;
+            }
+            json!({"post": operation})
+        };
+        let spec: OpenAPI = serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Test API", "version": "1.0.0"},
+            "paths": {
+                "/exports": post(
+                    "createExport",
+                    Some(json!({"status": "/status", "result": "/resourceLocation"})),
+                ),
+                "/imports": post("createImport", None),
+                "/jobs": post("createJob", Some(json!({"status": "/status"}))),
+                "/slow": post("createSlow", Some(json!(true))),
+                "/plain": post("createPlain", None),
+            }
+        }))
+        .unwrap();
+        let server =
+            HTTPBridge::new(Arc::new(spec), mock_server.uri(), Arc::new(reqwest::Client::new()))
+                .with_polling(PollingOptions {
+                    operations: HashMap::from([("createImport".to_string(), Polling::default())]),
+                    interval: Duration::from_millis(1),
+                    max_interval: Duration::from_millis(5),
+                    timeout: Duration::from_millis(200),
+                });
+
+        let call = async |name: &str| {
+            let result = server.execute_tool(name, json!({})).await.unwrap();
+            (result.is_error, result.content[0].as_text().unwrap().text.clone())
+        };
+        assert_eq!(call("createExport").await, (Some(false), r#"{"id":1,"rows":3}"#.into()));
+        assert_eq!(call("createImport").await, (Some(false), r#"{"id":7}"#.into()));
+        assert_eq!(
+            call("createJob").await,
+            (Some(true), r#"{"error":"disk full","status":"failed"}"#.into())
+        );
+        let (is_error, message) = call("createSlow").await;
+        assert_eq!(is_error, Some(true));
+        assert_eq!(
+            message.replace(&mock_server.uri(), "http://mock"),
+            "Operation did not complete within 200ms, its status is at http://mock/slow/1"
+        );
+        // Operations that did not opt in return the accepted response
+        assert_eq!(call("createPlain").await, (Some(false), r#"{"status":202}"#.into()));
+    }
+
+    #[tokio::test]
+    async fn test_retries_pass_limits() {
+        use wiremock::{
+            Mock, MockServer, ResponseTemplate,
+            matchers::{method, path},
+        };
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(503).insert_header("Retry-After", "0"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let spec: OpenAPI = serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Test API", "version": "1.0.0"},
+            "paths": {"/flaky": {"get": {"operationId": "getFlaky", "responses": {}}}},
+        }))
+        .unwrap();
+        let server =
+            HTTPBridge::new(Arc::new(spec), mock_server.uri(), Arc::new(reqwest::Client::new()))
+                .with_retry_options(RetryOptions { max_retries: 2, ..Default::default() })
+                .with_limits(LimitOptions {
+                    global: Limit { rate: Some(1.0), ..Default::default() },
+                    reject: true,
+                    ..Default::default()
+                });
+
+        // The retry needs a token of its own, which the first attempt used up
+        let error = server.execute_tool("getFlaky", json!({})).await.unwrap_err();
+        assert_eq!(error.message, "global rate limit exceeded");
+        mock_server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn test_multiple_apis() {
+        use rmcp::{ServerHandler, ServiceExt};
+        use wiremock::{
+            Mock, MockServer, ResponseTemplate,
+            matchers::{header, method, path},
+        };
+
+        let mock_server = MockServer::start().await;
+        for (api, token) in [("billing", "b-token"), ("users", "u-token")] {
+            Mock::given(method("GET"))
+                .and(path(format!("/{api}/items")))
+                .and(header("authorization", format!("Bearer {token}")))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!([api])))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+        }
+
+        let api = |title: &str| {
+            let spec: OpenAPI = serde_json::from_value(json!({
+                "openapi": "3.0.0",
+                "info": {
+                    "title": title,
+                    "version": "1.0.0",
+                    "description": "Manages items.\n\nMore.",
+                },
+                "paths": {"/items": {"get": {
+                    "operationId": "listItems",
+                    "tags": ["items"],
+                    "security": [{"bearer": []}],
+                    "responses": {},
+                }}},
+                "components": {
+                    "schemas": {"Item": {"type": "string"}},
+                    "securitySchemes": {"bearer": {"type": "http", "scheme": "bearer"}},
+                },
+            }))
+            .unwrap();
+            let base_url = format!("{}/{}", mock_server.uri(), title.to_lowercase());
+            HTTPBridge::new(Arc::new(spec), base_url, Arc::new(reqwest::Client::new()))
+        };
+        let credentials =
+            |token: &str| Credentials::from([("bearer".to_string(), token.to_string())]);
+        let bridge = api("Billing")
+            .with_namespace("billing")
+            .with_credentials(credentials("b-token"))
+            .with_api(
+                api("Users").with_namespace("users").with_credentials(credentials("u-token")),
+            );
+
+        let tools = bridge.tools(None).map(|tool| tool.name.to_string()).collect::<Vec<_>>();
+        assert_eq!(tools, ["billing_listItems", "users_listItems"]);
+        for (tool, api) in [("users_listItems", "users"), ("billing_listItems", "billing")] {
+            let result = bridge.execute_tool(tool, json!({})).await.unwrap();
+            assert_eq!(result.content[0].as_text().unwrap().text, format!("[\"{api}\"]"));
+        }
+        mock_server.verify().await;
+
+        // Operations are named as listed, namespace included
+        let filter = ToolFilter {
+            include_operations: vec!["users_list*".to_string()],
+            ..Default::default()
+        };
+        let filtered = api("Users").with_namespace("users").with_tool_filter(filter.clone());
+        assert_eq!(filtered.tools(None).count(), 1);
+        assert_eq!(api("Users").with_tool_filter(filter).tools(None).count(), 0);
+
+        // Limits hold across all APIs
+        assert!(bridge.apis().all(|api| Arc::ptr_eq(&api.limiter, &bridge.limiter)));
+        let limited = bridge.clone().with_limits(LimitOptions::default());
+        assert!(limited.apis().all(|api| Arc::ptr_eq(&api.limiter, &limited.limiter)));
+
+        let instructions = bridge.get_info().instructions.unwrap();
+        assert_snapshot!(instructions.replace(&mock_server.uri(), "http://mock"), @r###"
+        HTTP API bridge serving:
+        - Billing (version 1.0.0) at http://mock/billing, with tools prefixed by `billing_`: Manages items.
+        - Users (version 1.0.0) at http://mock/users, with tools prefixed by `users_`: Manages items.
+        "###);
+
+        let (server_io, client_io) = tokio::io::duplex(4096);
+        let (server, client) = tokio::join!(bridge.serve(server_io), ().serve(client_io));
+        let (_server, client) = (server.unwrap(), client.unwrap());
+        let resources = client.list_all_resources().await.unwrap();
+        let uris = resources.iter().map(|resource| resource.uri.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            uris,
+            [
+                "openapi://billing/spec",
+                "openapi://billing/schemas/Item",
+                "openapi://billing/operations/billing_listItems",
+                "openapi://users/spec",
+                "openapi://users/schemas/Item",
+                "openapi://users/operations/users_listItems",
+            ]
+        );
+        let read = client.read_resource(rmcp::model::ReadResourceRequestParam {
+            uri: "openapi://users/operations/users_listItems".to_string(),
+        });
+        assert!(read.await.is_ok());
+
+        let prompts = client.list_all_prompts().await.unwrap();
+        let names = prompts.iter().map(|prompt| prompt.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["billing_items", "users_items"]);
+        let prompt = client
+            .get_prompt(GetPromptRequestParam { name: "users_items".to_string(), arguments: None });
+        assert_eq!(
+            prompt.await.unwrap().description.unwrap(),
+            "How to use the items operations of Users"
+        );
+    }
+    #[tokio::test]
+    async fn test_apis_keep_their_settings() {
+        use wiremock::{
+            Mock, MockServer, ResponseTemplate,
+            matchers::{method, path},
+        };
+
+        let (billing_server, users_server) = (MockServer::start().await, MockServer::start().await);
+        for (server, route) in [(&billing_server, "/items"), (&users_server, "/v2/items")] {
+            Mock::given(method("GET"))
+                .and(path(route))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+                .expect(1)
+                .mount(server)
+                .await;
+        }
+
+        let spec = |servers: Value| {
+            let spec: OpenAPI = serde_json::from_value(json!({
+                "openapi": "3.0.0",
+                "info": {"title": "Test API", "version": "1.0.0"},
+                "servers": servers,
+                "paths": {"/items": {
+                    "get": {"operationId": "listItems", "responses": {}},
+                    "delete": {"operationId": "deleteItems", "responses": {}},
+                }},
+            }))
+            .unwrap();
+            Arc::new(spec)
+        };
+        // Each API goes to its own base URL, relative servers included, and
+        // keeps the filters given by namespaced tool names
+        let users_filter = ToolFilter {
+            include_operations: vec!["users_list*".to_string()],
+            ..Default::default()
+        };
+        let users = HTTPBridge::new(
+            spec(json!([{"url": "/v2"}])),
+            users_server.uri(),
+            Arc::new(reqwest::Client::new()),
+        )
+        .with_spec_servers(HashMap::new())
+        .with_namespace("users")
+        .with_tool_filter(users_filter);
+        let billing_filter =
+            ToolFilter { exclude_operations: vec!["users_*".to_string()], ..Default::default() };
+        let limits = LimitOptions {
+            global: Limit { rate: Some(1.0), ..Default::default() },
+            reject: true,
+            ..Default::default()
+        };
+        let bridge = HTTPBridge::new(
+            spec(json!([])),
+            billing_server.uri(),
+            Arc::new(reqwest::Client::new()),
+        )
+        .with_namespace("billing")
+        .with_tool_filter(billing_filter)
+        .with_api(users)
+        .with_limits(limits);
+
+        let tools = bridge.tools(None).map(|tool| tool.name.to_string()).collect::<Vec<_>>();
+        assert_eq!(tools, ["billing_listItems", "billing_deleteItems", "users_listItems"]);
+        assert!(bridge.execute_tool("users_deleteItems", json!({})).await.is_err());
+
+        let result = bridge.execute_tool("users_listItems", json!({})).await.unwrap();
+        assert_eq!(result.is_error, Some(false));
+        // The call to the other API used up the limit they share
+        let error = bridge.execute_tool("billing_listItems", json!({})).await.unwrap_err();
+        assert_eq!(error.message, "global rate limit exceeded");
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let result = bridge.for_session().execute_tool("billing_listItems", json!({})).await;
+        assert_eq!(result.unwrap().is_error, Some(false));
+    }
 }
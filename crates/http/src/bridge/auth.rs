@@ -0,0 +1,230 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use openapiv3::{APIKeyLocation, OpenAPI, Operation, Parameter, SecurityScheme};
+use reqwest::{
+    RequestBuilder,
+    header::{AUTHORIZATION, COOKIE},
+};
+
+use super::{oauth::TokenSource, reference};
+
+/// Secrets held by the bridge, keyed by security scheme name.
+///
+/// API keys and tokens are sent as is, while HTTP basic credentials are given
+/// as `username:password`.
+pub type Credentials = HashMap<String, String>;
+
+/// Token sources for OAuth2 schemes, keyed by security scheme name.
+pub type TokenSources = HashMap<String, TokenSource>;
+
+enum Secret<'a> {
+    Static(&'a str),
+    OAuth2(&'a TokenSource),
+}
+
+/// The bridge-held credentials chosen to authorize an operation.
+pub struct Authorization<'a> {
+    schemes: Vec<(&'a SecurityScheme, Secret<'a>)>,
+}
+
+impl<'a> Authorization<'a> {
+    /// Picks the first security requirement of an operation for which the
+    /// bridge holds credentials or a token source for every scheme.
+    ///
+    /// Operation-level requirements replace the global ones, and an empty list
+    /// disables authorization altogether.
+    pub fn select(
+        operation: &'a Operation,
+        spec: &'a OpenAPI,
+        credentials: &'a Credentials,
+        tokens: &'a TokenSources,
+    ) -> Option<Self> {
+        let requirements = operation.security.as_ref().or(spec.security.as_ref())?;
+        requirements.iter().filter(|requirement| !requirement.is_empty()).find_map(|requirement| {
+            let schemes = requirement
+                .keys()
+                .map(|name| {
+                    let scheme = spec.components.as_ref()?.security_schemes.get(name)?;
+                    let secret = credentials
+                        .get(name)
+                        .map(|secret| Secret::Static(secret))
+                        .or_else(|| tokens.get(name).map(Secret::OAuth2))?;
+                    Some((reference::resolve(scheme, spec)?, secret))
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some(Self { schemes })
+        })
+    }
+
+    /// Returns whether a declared parameter is supplied by the credentials, in
+    /// which case it is hidden from agents.
+    pub fn covers(&self, parameter: &Parameter) -> bool {
+        let name = &parameter.parameter_data_ref().name;
+        self.schemes.iter().any(|(scheme, _)| match (scheme, parameter) {
+            (SecurityScheme::APIKey { location, name: key, .. }, parameter) => {
+                match (location, parameter) {
+                    (APIKeyLocation::Query, Parameter::Query { .. })
+                    | (APIKeyLocation::Cookie, Parameter::Cookie { .. }) => key == name,
+                    (APIKeyLocation::Header, Parameter::Header { .. }) => {
+                        key.eq_ignore_ascii_case(name)
+                    }
+                    _ => false,
+                }
+            }
+            (_, Parameter::Header { .. }) => name.eq_ignore_ascii_case(AUTHORIZATION.as_str()),
+            _ => false,
+        })
+    }
+
+    /// Returns whether the credentials include OAuth2 access tokens, which
+    /// can be renewed when the upstream rejects them.
+    pub fn is_renewable(&self) -> bool {
+        self.schemes.iter().any(|(_, secret)| matches!(secret, Secret::OAuth2(_)))
+    }
+
+    /// Drops the cached access tokens so the next request fetches new ones.
+    pub async fn invalidate(&self) {
+        for (_, secret) in &self.schemes {
+            if let Secret::OAuth2(source) = secret {
+                source.invalidate().await;
+            }
+        }
+    }
+
+    /// Adds the credentials to a request, obtaining OAuth2 access tokens as
+    /// needed. API keys sent as cookies share a single `Cookie` header with
+    /// the request's other `cookies`.
+    pub async fn apply(
+        &self,
+        mut request: RequestBuilder,
+        client: &reqwest::Client,
+        cookies: &[String],
+    ) -> Result<RequestBuilder, rmcp::Error> {
+        let mut cookies = cookies.to_vec();
+        for (scheme, secret) in &self.schemes {
+            let secret = match secret {
+                Secret::Static(secret) => Cow::Borrowed(*secret),
+                Secret::OAuth2(source) => Cow::Owned(source.access_token(client, scheme).await?),
+            };
+            let secret = secret.as_ref();
+            request = match scheme {
+                SecurityScheme::APIKey { location: APIKeyLocation::Query, name, .. } => {
+                    request.query(&[(name, secret)])
+                }
+                SecurityScheme::APIKey { location: APIKeyLocation::Header, name, .. } => {
+                    request.header(name, secret)
+                }
+                SecurityScheme::APIKey { location: APIKeyLocation::Cookie, name, .. } => {
+                    cookies.push(format!("{name}={secret}"));
+                    request
+                }
+                SecurityScheme::HTTP { scheme, .. } if scheme.eq_ignore_ascii_case("basic") => {
+                    let (username, password) = secret.split_once(':').unwrap_or((secret, ""));
+                    request.basic_auth(username, Some(password))
+                }
+                SecurityScheme::HTTP { scheme, .. } if scheme.eq_ignore_ascii_case("bearer") => {
+                    request.bearer_auth(secret)
+                }
+                SecurityScheme::HTTP { scheme, .. } => {
+                    request.header(AUTHORIZATION, format!("{scheme} {secret}"))
+                }
+                // Secrets for these schemes are access tokens, whether configured
+                // directly or obtained from a token source.
+                SecurityScheme::OAuth2 { .. } | SecurityScheme::OpenIDConnect { .. } => {
+                    request.bearer_auth(secret)
+                }
+            };
+        }
+
+        if !cookies.is_empty() {
+            request = request.header(COOKIE, cookies.join("; "));
+        }
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn spec() -> OpenAPI {
+        serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Test", "version": "1.0.0"},
+            "paths": {},
+            "security": [{"apiKey": []}],
+            "components": {
+                "securitySchemes": {
+                    "apiKey": {"type": "apiKey", "in": "header", "name": "X-API-Key"},
+                    "basic": {"type": "http", "scheme": "basic"},
+                    "session": {"type": "apiKey", "in": "cookie", "name": "sid"},
+                    "token": {"$ref": "#/components/securitySchemes/bearer"},
+                    "bearer": {"type": "http", "scheme": "bearer"},
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    fn credentials(entries: &[(&str, &str)]) -> Credentials {
+        entries.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    fn schemes(authorization: Option<Authorization<'_>>) -> Vec<&str> {
+        authorization
+            .map(|authorization| {
+                authorization
+                    .schemes
+                    .iter()
+                    .map(|(_, secret)| match secret {
+                        Secret::Static(secret) => *secret,
+                        Secret::OAuth2(_) => "<oauth2>",
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_select_requirement() {
+        let spec = spec();
+        let tokens = TokenSources::new();
+        let operation = Operation {
+            security: Some(vec![
+                [("basic".to_string(), vec![]), ("session".to_string(), vec![])].into(),
+                [("token".to_string(), vec![])].into(),
+            ]),
+            ..Default::default()
+        };
+
+        // Global requirements apply unless the operation declares its own.
+        let creds = credentials(&[("apiKey", "key"), ("token", "tok")]);
+        assert_eq!(
+            schemes(Authorization::select(&Operation::default(), &spec, &creds, &tokens)),
+            ["key"]
+        );
+
+        // Every scheme of a requirement must be configured.
+        let creds = credentials(&[("basic", "user:pass"), ("token", "tok")]);
+        assert_eq!(schemes(Authorization::select(&operation, &spec, &creds, &tokens)), ["tok"]);
+
+        let creds = credentials(&[("basic", "user:pass"), ("session", "s1"), ("token", "tok")]);
+        assert_eq!(
+            schemes(Authorization::select(&operation, &spec, &creds, &tokens)),
+            ["user:pass", "s1"]
+        );
+
+        // An empty list opts the operation out of authorization.
+        let public = Operation { security: Some(vec![]), ..Default::default() };
+        assert!(Authorization::select(&public, &spec, &creds, &tokens).is_none());
+
+        // Token sources satisfy requirements like configured secrets do.
+        let creds = credentials(&[("basic", "user:pass")]);
+        let tokens = [("session".to_string(), TokenSource::new(Default::default()))].into();
+        let authorization = Authorization::select(&operation, &spec, &creds, &tokens);
+        assert!(authorization.as_ref().is_some_and(Authorization::is_renewable));
+        assert_eq!(schemes(authorization), ["user:pass", "<oauth2>"]);
+    }
+}
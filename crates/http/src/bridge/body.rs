@@ -0,0 +1,496 @@
+use std::collections::HashSet;
+
+use base64::{Engine as _, prelude::BASE64_STANDARD};
+use openapiv3::{MediaType, OpenAPI, RequestBody};
+use reqwest::{
+    RequestBuilder,
+    header::CONTENT_TYPE,
+    multipart::{Form, Part},
+};
+use serde_json::{Value, json};
+
+use super::{
+    Resolver, remove_flagged_properties, resolve_schema, resolve_schema_with, to_canonical_string,
+};
+
+/// How a request body is encoded on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyEncoding {
+    Json,
+    Form,
+    Multipart,
+    Text,
+    Binary,
+}
+
+impl BodyEncoding {
+    fn of(media_type: &str) -> Self {
+        let essence = essence(media_type);
+        match essence.as_str() {
+            "application/json" => Self::Json,
+            "application/x-www-form-urlencoded" => Self::Form,
+            "multipart/form-data" => Self::Multipart,
+            _ if essence.ends_with("+json") => Self::Json,
+            _ if essence.starts_with("text/") => Self::Text,
+            _ => Self::Binary,
+        }
+    }
+}
+
+/// The media type chosen to send a request body with.
+pub struct BodyFormat<'a> {
+    pub media_type: &'a str,
+    pub encoding: BodyEncoding,
+    pub media: &'a MediaType,
+}
+
+/// Picks the media type used to send a request body.
+///
+/// JSON is preferred because it carries the most structure for agents;
+/// otherwise the first media type declared in the spec is used.
+pub fn select(request_body: &RequestBody) -> Option<BodyFormat<'_>> {
+    let formats = request_body.content.iter().map(|(media_type, media)| BodyFormat {
+        media_type,
+        encoding: BodyEncoding::of(media_type),
+        media,
+    });
+    let mut first = None;
+    for format in formats {
+        if format.encoding == BodyEncoding::Json {
+            return Some(format);
+        }
+        first.get_or_insert(format);
+    }
+    first
+}
+
+/// Generates the JSON schema of the `body` argument for a media type.
+pub fn schema(format: &BodyFormat, spec: &OpenAPI, resolver: &mut Resolver) -> Value {
+    // Read-only properties are set by the server and never sent.
+    let resolved = format.media.schema.as_ref().map(|schema| {
+        // Form fields are inspected below, so keep those schemas inline.
+        let mut schema = match format.encoding {
+            BodyEncoding::Form | BodyEncoding::Multipart => resolve_schema(schema, spec),
+            _ => resolve_schema_with(schema, spec, resolver),
+        };
+        remove_flagged_properties(&mut schema, "readOnly");
+        schema
+    });
+    match format.encoding {
+        BodyEncoding::Json | BodyEncoding::Form => resolved.unwrap_or_else(|| json!({})),
+        BodyEncoding::Multipart => {
+            let mut schema = resolved.unwrap_or_else(|| json!({"type": "object"}));
+            if let Some(properties) = schema.get_mut("properties").and_then(Value::as_object_mut) {
+                for property in properties.values_mut() {
+                    if is_binary(property) {
+                        *property = file_schema(property);
+                    } else if let Some(items) = property.get_mut("items")
+                        && is_binary(items)
+                    {
+                        *items = file_schema(items);
+                    }
+                }
+            }
+            schema
+        }
+        BodyEncoding::Text => {
+            let mut schema = json!({"type": "string"});
+            if let Some(description) = resolved.as_ref().and_then(|s| s.get("description")) {
+                schema["description"] = description.clone();
+            }
+            schema
+        }
+        BodyEncoding::Binary => json!({
+            "type": "string",
+            "contentEncoding": "base64",
+            "description": format!("Base64-encoded {} content", format.media_type),
+        }),
+    }
+}
+
+/// Encodes the `body` argument into the request according to its media type.
+pub fn encode(
+    request: RequestBuilder,
+    format: &BodyFormat,
+    body: &Value,
+    spec: &OpenAPI,
+) -> Result<RequestBuilder, rmcp::Error> {
+    match format.encoding {
+        BodyEncoding::Json => {
+            let request = if essence(format.media_type) == "application/json" {
+                request
+            } else {
+                request.header(CONTENT_TYPE, format.media_type)
+            };
+            Ok(request.json(body))
+        }
+        BodyEncoding::Form => {
+            let Value::Object(fields) = body else {
+                return Err(rmcp::Error::invalid_params(
+                    "form request bodies must be objects",
+                    Some(body.clone()),
+                ));
+            };
+            let mut pairs = Vec::new();
+            for (name, value) in fields {
+                let explode = format.media.encoding.get(name).is_none_or(|e| e.explode);
+                match value {
+                    Value::Array(items) if explode => {
+                        pairs.extend(items.iter().map(|item| (name.clone(), form_value(item))));
+                    }
+                    Value::Array(items) => {
+                        let joined = items.iter().map(form_value).collect::<Vec<_>>().join(",");
+                        pairs.push((name.clone(), joined));
+                    }
+                    value => pairs.push((name.clone(), form_value(value))),
+                }
+            }
+            Ok(request.form(&pairs))
+        }
+        BodyEncoding::Multipart => {
+            let Value::Object(fields) = body else {
+                return Err(rmcp::Error::invalid_params(
+                    "multipart request bodies must be objects",
+                    Some(body.clone()),
+                ));
+            };
+            let files = binary_properties(format, spec);
+            let mut form = Form::new();
+            for (name, value) in fields {
+                let content_type =
+                    format.media.encoding.get(name).and_then(|e| e.content_type.as_deref());
+                match value {
+                    Value::Array(items) if files.contains(name.as_str()) => {
+                        for item in items {
+                            form = form.part(name.clone(), file_part(name, item, content_type)?);
+                        }
+                    }
+                    value if files.contains(name.as_str()) => {
+                        form = form.part(name.clone(), file_part(name, value, content_type)?);
+                    }
+                    Value::Object(_) | Value::Array(_) => {
+                        let part = Part::text(value.to_string())
+                            .mime_str(content_type.unwrap_or("application/json"))
+                            .map_err(|e| invalid_content_type(name, e))?;
+                        form = form.part(name.clone(), part);
+                    }
+                    value => form = form.text(name.clone(), form_value(value)),
+                }
+            }
+            Ok(request.multipart(form))
+        }
+        BodyEncoding::Text => {
+            let text = match body {
+                Value::String(text) => text.clone(),
+                value => value.to_string(),
+            };
+            Ok(request.header(CONTENT_TYPE, format.media_type).body(text))
+        }
+        BodyEncoding::Binary => {
+            let Some(data) = body.as_str() else {
+                return Err(rmcp::Error::invalid_params(
+                    "binary request bodies must be base64-encoded strings",
+                    Some(body.clone()),
+                ));
+            };
+            let bytes = decode_base64("body", data)?;
+            let content_type = if format.media_type.contains('*') {
+                "application/octet-stream"
+            } else {
+                format.media_type
+            };
+            Ok(request.header(CONTENT_TYPE, content_type).body(bytes))
+        }
+    }
+}
+
+/// Returns the media type without parameters, lowercased.
+fn essence(media_type: &str) -> String {
+    media_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase()
+}
+
+fn is_binary(schema: &Value) -> bool {
+    schema.get("type") == Some(&json!("string")) && schema.get("format") == Some(&json!("binary"))
+}
+
+fn file_schema(schema: &Value) -> Value {
+    let mut file = json!({
+        "type": "object",
+        "properties": {
+            "filename": {"type": "string"},
+            "contentType": {"type": "string"},
+            "data": {
+                "type": "string",
+                "contentEncoding": "base64",
+                "description": "Base64-encoded file contents",
+            },
+        },
+        "required": ["data"],
+    });
+    if let Some(description) = schema.get("description") {
+        file["description"] = description.clone();
+    }
+    file
+}
+
+/// Returns the multipart properties that carry files.
+fn binary_properties(format: &BodyFormat, spec: &OpenAPI) -> HashSet<String> {
+    let resolved = format.media.schema.as_ref().map(|schema| resolve_schema(schema, spec));
+    let Some(properties) =
+        resolved.as_ref().and_then(|s| s.get("properties")).and_then(Value::as_object)
+    else {
+        return HashSet::new();
+    };
+    properties
+        .iter()
+        .filter(|(_, schema)| is_binary(schema) || schema.get("items").is_some_and(is_binary))
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+fn file_part(name: &str, value: &Value, content_type: Option<&str>) -> Result<Part, rmcp::Error> {
+    let Some(data) = value.get("data").and_then(Value::as_str) else {
+        return Err(rmcp::Error::invalid_params(
+            format!("file part '{name}' must be an object with base64 'data'"),
+            Some(value.clone()),
+        ));
+    };
+    let mut part = Part::bytes(decode_base64(name, data)?);
+    if let Some(filename) = value.get("filename").and_then(Value::as_str) {
+        part = part.file_name(filename.to_string());
+    }
+    let content_type = value
+        .get("contentType")
+        .and_then(Value::as_str)
+        .or(content_type)
+        .unwrap_or("application/octet-stream");
+    part.mime_str(content_type).map_err(|e| invalid_content_type(name, e))
+}
+
+fn form_value(value: &Value) -> String {
+    to_canonical_string(value).unwrap_or_else(|| value.to_string())
+}
+
+fn decode_base64(name: &str, data: &str) -> Result<Vec<u8>, rmcp::Error> {
+    BASE64_STANDARD.decode(data).map_err(|e| {
+        rmcp::Error::invalid_params(format!("'{name}' is not valid base64: {e}"), None)
+    })
+}
+
+fn invalid_content_type(name: &str, error: reqwest::Error) -> rmcp::Error {
+    rmcp::Error::invalid_params(format!("invalid content type for '{name}': {error}"), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+    use insta::assert_json_snapshot;
+
+    use super::*;
+
+    fn request_body(content: Value) -> RequestBody {
+        serde_json::from_value(json!({ "content": content })).unwrap()
+    }
+
+    fn body_bytes(request: RequestBuilder) -> Vec<u8> {
+        let request = request.build().unwrap();
+        request.body().and_then(|b| b.as_bytes()).unwrap().to_vec()
+    }
+
+    #[test]
+    fn test_select_prefers_json() {
+        let body = request_body(json!({
+            "application/atom+xml": {},
+            "application/vnd.api+json": {},
+            "text/plain": {},
+        }));
+        let format = select(&body).unwrap();
+        assert_eq!(format.media_type, "application/vnd.api+json");
+        assert_eq!(format.encoding, BodyEncoding::Json);
+
+        let body = request_body(json!({
+            "application/x-www-form-urlencoded": {},
+            "multipart/form-data": {},
+        }));
+        assert_eq!(select(&body).unwrap().encoding, BodyEncoding::Form);
+
+        assert!(select(&RequestBody { content: IndexMap::new(), ..Default::default() }).is_none());
+    }
+
+    #[test]
+    fn test_media_type_encodings() {
+        assert_eq!(BodyEncoding::of("Application/JSON; charset=utf-8"), BodyEncoding::Json);
+        assert_eq!(BodyEncoding::of("text/csv"), BodyEncoding::Text);
+        assert_eq!(BodyEncoding::of("image/png"), BodyEncoding::Binary);
+        assert_eq!(BodyEncoding::of("*/*"), BodyEncoding::Binary);
+    }
+
+    #[test]
+    fn test_multipart_schema_replaces_binary_properties() {
+        let body = request_body(json!({
+            "multipart/form-data": {
+                "schema": {
+                    "type": "object",
+                    "properties": {
+                        "title": {"type": "string"},
+                        "avatar": {"type": "string", "format": "binary", "description": "Avatar image"},
+                        "attachments": {"type": "array", "items": {"type": "string", "format": "binary"}}
+                    },
+                    "required": ["avatar"]
+                }
+            }
+        }));
+        let format = select(&body).unwrap();
+
+        let resolved = schema(&format, &OpenAPI::default(), &mut Resolver::default());
+        assert_json_snapshot!(resolved, @r###"
+        {
+          "properties": {
+            "attachments": {
+              "items": {
+                "properties": {
+                  "contentType": {
+                    "type": "string"
+                  },
+                  "data": {
+                    "contentEncoding": "base64",
+                    "description": "Base64-encoded file contents",
+                    "type": "string"
+                  },
+                  "filename": {
+                    "type": "string"
+                  }
+                },
+                "required": [
+                  "data"
+                ],
+                "type": "object"
+              },
+              "type": "array"
+            },
+            "avatar": {
+              "description": "Avatar image",
+              "properties": {
+                "contentType": {
+                  "type": "string"
+                },
+                "data": {
+                  "contentEncoding": "base64",
+                  "description": "Base64-encoded file contents",
+                  "type": "string"
+                },
+                "filename": {
+                  "type": "string"
+                }
+              },
+              "required": [
+                "data"
+              ],
+              "type": "object"
+            },
+            "title": {
+              "type": "string"
+            }
+          },
+          "required": [
+            "avatar"
+          ],
+          "type": "object"
+        }
+        "###);
+    }
+
+    #[test]
+    fn test_text_and_binary_schemas() {
+        let spec = OpenAPI::default();
+        let body = request_body(json!({
+            "text/plain": {"schema": {"type": "string", "description": "Raw notes"}}
+        }));
+        let resolved = schema(&select(&body).unwrap(), &spec, &mut Resolver::default());
+        assert_json_snapshot!(resolved, @r###"
+        {
+          "description": "Raw notes",
+          "type": "string"
+        }
+        "###);
+
+        let body = request_body(json!({ "application/octet-stream": {} }));
+        let resolved = schema(&select(&body).unwrap(), &spec, &mut Resolver::default());
+        assert_json_snapshot!(resolved, @r###"
+        {
+          "contentEncoding": "base64",
+          "description": "Base64-encoded application/octet-stream content",
+          "type": "string"
+        }
+        "###);
+    }
+
+    #[test]
+    fn test_encode_form() {
+        let body = request_body(json!({
+            "application/x-www-form-urlencoded": {
+                "encoding": {"scope": {"explode": false}}
+            }
+        }));
+        let format = select(&body).unwrap();
+        let client = reqwest::Client::new();
+        let request = encode(
+            client.post("http://localhost/token"),
+            &format,
+            &json!({
+                "grant_type": "client_credentials",
+                "scope": ["read", "write"],
+                "audience": ["a b", "c"],
+                "ttl": 60
+            }),
+            &OpenAPI::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(body_bytes(request)).unwrap(),
+            "audience=a+b&audience=c&grant_type=client_credentials&scope=read%2Cwrite&ttl=60"
+        );
+    }
+
+    #[test]
+    fn test_encode_text_and_binary() {
+        let client = reqwest::Client::new();
+        let spec = OpenAPI::default();
+
+        let body = request_body(json!({ "text/csv": {} }));
+        let request = encode(
+            client.post("http://localhost/import"),
+            &select(&body).unwrap(),
+            &json!("id,name\n1,bridge"),
+            &spec,
+        )
+        .unwrap();
+        let request = request.build().unwrap();
+        assert_eq!(request.headers()[CONTENT_TYPE], "text/csv");
+        assert_eq!(request.body().and_then(|b| b.as_bytes()), Some(&b"id,name\n1,bridge"[..]));
+
+        let body = request_body(json!({ "image/*": {} }));
+        let request = encode(
+            client.put("http://localhost/avatar"),
+            &select(&body).unwrap(),
+            &json!("iVBORw0K"),
+            &spec,
+        )
+        .unwrap();
+        let request = request.build().unwrap();
+        assert_eq!(request.headers()[CONTENT_TYPE], "application/octet-stream");
+        assert_eq!(
+            request.body().and_then(|b| b.as_bytes()),
+            Some(&[0x89, b'P', b'N', b'G', 0x0d, 0x0a][..])
+        );
+
+        let invalid = encode(
+            client.put("http://localhost/avatar"),
+            &select(&body).unwrap(),
+            &json!("not base64!"),
+            &spec,
+        );
+        assert!(invalid.is_err());
+    }
+}
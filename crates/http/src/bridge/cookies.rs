@@ -0,0 +1,29 @@
+use std::sync::Arc;
+
+use reqwest::{
+    Response, Url,
+    cookie::{CookieStore, Jar},
+    header::SET_COOKIE,
+};
+
+/// Cookies set by the upstream API during one MCP session, sent back on the
+/// session's later requests so login-then-call flows work.
+///
+/// Clones share the jar. Each session is served with a bridge holding a new
+/// one, so sessions never see each other's cookies.
+#[derive(Clone, Default)]
+pub struct SessionCookies(Arc<Jar>);
+
+impl SessionCookies {
+    /// Returns the `name=value` pairs to send to `url`.
+    pub fn get(&self, url: &Url) -> Option<String> {
+        let header = self.0.cookies(url)?;
+        header.to_str().ok().map(str::to_string)
+    }
+
+    /// Stores the cookies set by a response.
+    pub fn store(&self, response: &Response) {
+        let mut cookies = response.headers().get_all(SET_COOKIE).iter();
+        self.0.set_cookies(&mut cookies, response.url());
+    }
+}
@@ -0,0 +1,269 @@
+use std::{collections::HashSet, fmt::Write as _};
+
+use openapiv3::{MediaType, OpenAPI, Parameter, ReferenceOr};
+use rmcp::model::{
+    AnnotateAble, GetPromptResult, Prompt, PromptMessage, PromptMessageRole, RawResource, Resource,
+    ResourceContents,
+};
+use serde_json::Value;
+
+use super::{METHODS, ToolInfo, extensions, index::ToolIndex, reference, resolve_schema};
+
+const SCHEME: &str = "openapi://";
+
+/// Returns the URI the documents of an index are published under, nested
+/// under its namespace if any.
+fn root(index: &ToolIndex) -> String {
+    match index.namespace() {
+        Some(namespace) => format!("{SCHEME}{namespace}/"),
+        None => SCHEME.to_string(),
+    }
+}
+
+/// Lists the documents published as resources: the spec itself, each of its
+/// component schemas and the documentation of each tool.
+pub fn resources(index: &ToolIndex) -> Vec<Resource> {
+    let spec = index.spec();
+    let root = root(index);
+    let resource = |uri: String, name: String, description: String, mime_type: &str| {
+        let mut resource = RawResource::new(uri, name);
+        resource.description = Some(description);
+        resource.mime_type = Some(mime_type.to_string());
+        resource.no_annotation()
+    };
+
+    let mut resources = vec![resource(
+        format!("{root}spec"),
+        spec.info.title.clone(),
+        "The OpenAPI document of the API".to_string(),
+        "application/json",
+    )];
+    for name in spec.components.iter().flat_map(|components| components.schemas.keys()) {
+        resources.push(resource(
+            format!("{root}schemas/{name}"),
+            name.clone(),
+            format!("JSON Schema of {name} objects"),
+            "application/schema+json",
+        ));
+    }
+    for tool in index.operations() {
+        resources.push(resource(
+            format!("{root}operations/{}", tool.id),
+            tool.id.to_string(),
+            format!("Parameters, examples and responses of the {} tool", tool.id),
+            "text/markdown",
+        ));
+    }
+    resources
+}
+
+/// Reads a resource listed by [`resources`]. `parameters` returns the
+/// parameters agents supply to a tool.
+pub fn read<'a>(
+    index: &'a ToolIndex,
+    uri: &str,
+    parameters: impl FnOnce(&ToolInfo<'a>) -> Vec<&'a Parameter>,
+) -> Option<ResourceContents> {
+    let spec = index.spec();
+    let path = uri.strip_prefix(&root(index))?;
+    let (text, mime_type) = if path == "spec" {
+        (serde_json::to_string_pretty(&exposed_spec(index)).ok()?, "application/json")
+    } else if let Some(name) = path.strip_prefix("schemas/") {
+        spec.components.as_ref()?.schemas.get(name)?;
+        let reference = format!("#/components/schemas/{name}");
+        let schema = resolve_schema(&ReferenceOr::Reference { reference }, spec);
+        (serde_json::to_string_pretty(&schema).ok()?, "application/schema+json")
+    } else {
+        let name = path.strip_prefix("operations/")?;
+        let tool = index.operations().find(|tool| tool.id == name)?;
+        (operation(&tool, &parameters(&tool), spec), "text/markdown")
+    };
+    Some(ResourceContents::TextResourceContents {
+        uri: uri.to_string(),
+        mime_type: Some(mime_type.to_string()),
+        text,
+    })
+}
+
+/// Returns the spec as agents may see it: with only the operations the index
+/// exposes, and without the parameters or extensions kept from agents.
+fn exposed_spec(index: &ToolIndex) -> Value {
+    let mut document = serde_json::to_value(index.spec().as_ref()).unwrap_or_default();
+    let exposed = index.operations().map(|tool| (tool.path, tool.method)).collect::<HashSet<_>>();
+    if let Some(Value::Object(paths)) = document.get_mut("paths") {
+        paths.retain(|path, item| {
+            retain_exposed_parameters(item);
+            let Value::Object(item) = item else {
+                return false;
+            };
+            item.retain(|key, operation| {
+                if !METHODS.contains(&key.as_str()) && key != "trace" {
+                    return true;
+                }
+                retain_exposed_parameters(operation);
+                exposed.contains(&(path.as_str(), key.as_str()))
+            });
+            item.keys().any(|key| METHODS.contains(&key.as_str()))
+        });
+    }
+    extensions::strip(&mut document);
+    document
+}
+
+/// Drops the parameters kept from agents off a path item or operation.
+/// References to shared parameters are kept, stripped of extensions later.
+fn retain_exposed_parameters(object: &mut Value) {
+    if let Some(Value::Array(parameters)) = object.get_mut("parameters") {
+        parameters.retain(|parameter| {
+            serde_json::from_value::<Parameter>(parameter.clone())
+                .map_or(true, |parameter| extensions::exposed(&parameter))
+        });
+    }
+}
+
+/// Documents a tool for agents in Markdown.
+fn operation(tool: &ToolInfo, parameters: &[&Parameter], spec: &OpenAPI) -> String {
+    let ToolInfo { id, path, method, operation, .. } = tool;
+    let mut doc = format!("# {id}\n\n`{} {path}`\n", method.to_uppercase());
+    if operation.deprecated {
+        doc.push_str("\n**Deprecated.**\n");
+    }
+    let texts = match extensions::description(&operation.extensions) {
+        Some(text) => vec![text],
+        None => [&operation.summary, &operation.description]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect(),
+    };
+    for text in texts {
+        let _ = write!(doc, "\n{text}\n");
+    }
+
+    if !parameters.is_empty() {
+        doc.push_str("\n## Parameters\n\n");
+        for parameter in parameters {
+            let data = parameter.parameter_data_ref();
+            let required = if data.required { ", required" } else { "" };
+            let _ =
+                write!(doc, "- `{}` (in {}{required})", data.name, reference::location(parameter));
+            let description =
+                extensions::description(&data.extensions).or(data.description.as_deref());
+            if let Some(description) = description {
+                let _ = write!(doc, ": {description}");
+            }
+            if let Some(example) = &data.example {
+                let _ = write!(doc, " Example: `{example}`");
+            }
+            doc.push('\n');
+        }
+    }
+
+    if let Some(body) =
+        operation.request_body.as_ref().and_then(|body| reference::resolve(body, spec))
+    {
+        doc.push_str("\n## Request body\n");
+        if let Some(description) = &body.description {
+            let _ = write!(doc, "\n{description}\n");
+        }
+        media_types(&mut doc, &body.content);
+    }
+
+    doc.push_str("\n## Responses\n");
+    let responses = &operation.responses;
+    let statuses =
+        responses.responses.iter().map(|(status, response)| (status.to_string(), response));
+    for (status, response) in
+        statuses.chain(responses.default.iter().map(|r| ("default".to_string(), r)))
+    {
+        let Some(response) = reference::resolve(response, spec) else {
+            continue;
+        };
+        let _ = write!(doc, "\n### {status}\n\n{}\n", response.description);
+        media_types(&mut doc, &response.content);
+    }
+    doc
+}
+
+fn media_types(doc: &mut String, content: &indexmap::IndexMap<String, MediaType>) {
+    for (media_type, media) in content {
+        let _ = write!(doc, "\nMedia type: `{media_type}`\n");
+        let example = media.example.as_ref().or_else(|| {
+            media.examples.values().find_map(|example| match example {
+                ReferenceOr::Item(example) => example.value.as_ref(),
+                ReferenceOr::Reference { .. } => None,
+            })
+        });
+        if let Some(example) = example {
+            let example = match example {
+                Value::String(text) => text.clone(),
+                value => serde_json::to_string_pretty(value).unwrap_or_default(),
+            };
+            let _ = write!(doc, "\nExample:\n\n```\n{example}\n```\n");
+        }
+    }
+}
+
+/// Lists a primer prompt for each tag of the exposed operations.
+pub fn prompts(index: &ToolIndex) -> Vec<Prompt> {
+    tags(index)
+        .into_iter()
+        .map(|tag| {
+            let description =
+                format!("How to use the {tag} operations of {}", index.spec().info.title);
+            Prompt::new(index.qualify(&tag), Some(description), None)
+        })
+        .collect()
+}
+
+/// Returns the primer named `name`, for the operations with its tag.
+pub fn prompt(index: &ToolIndex, name: &str) -> Option<GetPromptResult> {
+    let spec = index.spec();
+    let name = tags(index).into_iter().find(|tag| index.qualify(tag) == name)?;
+    let tools =
+        index.operations().filter(|tool| tool.operation.tags.contains(&name)).collect::<Vec<_>>();
+
+    let mut text = format!(
+        "You are working with the {name} operations of {} (version {}).\n",
+        spec.info.title, spec.info.version
+    );
+    let tag = spec.tags.iter().find(|tag| tag.name == name);
+    if let Some(description) = tag.and_then(|tag| tag.description.as_ref()) {
+        let _ = write!(text, "\n{description}\n");
+    }
+    text.push_str("\nThese tools are available:\n\n");
+    for tool in &tools {
+        let ToolInfo { id, path, method, operation, .. } = tool;
+        let summary = extensions::description(&operation.extensions)
+            .or(operation.summary.as_deref())
+            .or(operation.description.as_deref());
+        let summary =
+            summary.map_or_else(|| format!("{} {path}", method.to_uppercase()), str::to_string);
+        let _ = writeln!(text, "- `{id}`: {summary}");
+    }
+    let root = root(index);
+    let _ = write!(
+        text,
+        "\nBefore calling a tool, read its documentation at `{root}operations/<tool>` for its \
+         parameters, examples and possible responses. The shapes of the data exchanged are \
+         described at `{root}schemas/<schema>`."
+    );
+
+    Some(GetPromptResult {
+        description: Some(format!("How to use the {name} operations of {}", spec.info.title)),
+        messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+    })
+}
+
+/// Returns the tags of the exposed operations, in order of appearance.
+fn tags(index: &ToolIndex) -> Vec<String> {
+    let mut tags = Vec::<String>::new();
+    for tool in index.operations() {
+        for tag in &tool.operation.tags {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+    }
+    tags
+}
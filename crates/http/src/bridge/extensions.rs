@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use indexmap::IndexMap;
+use openapiv3::{Operation, Parameter};
+use serde_json::Value;
+
+/// Shared by the names of all extensions the bridge reads.
+const PREFIX: &str = "x-brwse-";
+/// Names the tool of an operation in place of its `operationId`.
+const NAME: &str = "x-brwse-name";
+/// Replaces the description of a tool or parameter.
+const DESCRIPTION: &str = "x-brwse-description";
+/// Keeps an operation or parameter from agents.
+const HIDDEN: &str = "x-brwse-hidden";
+/// Value always sent for a parameter, which agents cannot set.
+const VALUE: &str = "x-brwse-value";
+/// Value sent for a parameter agents leave out.
+const DEFAULT: &str = "x-brwse-default";
+/// Seconds the calls of an operation may take, retries included, replacing
+/// the timeouts of the client and of tool calls.
+const TIMEOUT: &str = "x-brwse-timeout";
+/// Marks operations that should be confirmed by a person before they run.
+const REQUIRES_CONFIRMATION: &str = "x-brwse-requires-confirmation";
+
+/// Returns the tool name set for an operation, if any.
+pub(super) fn name(operation: &Operation) -> Option<&str> {
+    operation.extensions.get(NAME)?.as_str()
+}
+
+/// Returns the description set for an operation or parameter, if any.
+pub(super) fn description(extensions: &IndexMap<String, Value>) -> Option<&str> {
+    extensions.get(DESCRIPTION)?.as_str()
+}
+
+pub(super) fn hidden(extensions: &IndexMap<String, Value>) -> bool {
+    flag(extensions, HIDDEN)
+}
+
+pub(super) fn timeout(operation: &Operation) -> Option<Duration> {
+    let seconds = operation.extensions.get(TIMEOUT)?.as_f64()?;
+    Duration::try_from_secs_f64(seconds).ok().filter(|timeout| !timeout.is_zero())
+}
+
+pub(super) fn requires_confirmation(operation: &Operation) -> bool {
+    flag(&operation.extensions, REQUIRES_CONFIRMATION)
+}
+
+/// Whether agents supply a parameter, which they do unless it is hidden or
+/// has a fixed value.
+pub(super) fn exposed(parameter: &Parameter) -> bool {
+    let extensions = &parameter.parameter_data_ref().extensions;
+    !hidden(extensions) && !extensions.contains_key(VALUE)
+}
+
+/// Applies the description and default set for a parameter to its schema.
+/// Returns whether agents may leave the parameter out thanks to a default.
+pub(super) fn shape_schema(parameter: &Parameter, schema: &mut Value) -> bool {
+    let extensions = &parameter.parameter_data_ref().extensions;
+    if let Some(description) = description(extensions) {
+        schema["description"] = Value::String(description.to_string());
+    }
+    let default = extensions.get(DEFAULT);
+    if let Some(default) = default {
+        schema["default"] = default.clone();
+    }
+    default.is_some()
+}
+
+/// Notes in the description of a tool that its operation should be
+/// confirmed, so clients listing tools can ask before calling it.
+pub(super) fn note_confirmation(operation: &Operation, description: &mut String) {
+    if requires_confirmation(operation) {
+        description.push_str("\n\nAsk the user to confirm before calling this tool.");
+    }
+}
+
+/// Sets the fixed values of parameters in the arguments of a call, along
+/// with the defaults of those agents left out. Arguments agents sent for
+/// parameters kept from them are dropped.
+pub(super) fn inject(parameters: &[&Parameter], args: &mut Value) {
+    for parameter in parameters {
+        let data = parameter.parameter_data_ref();
+        if !exposed(parameter) {
+            let arguments = match parameter {
+                Parameter::Header { .. } => args.get_mut("headers"),
+                Parameter::Cookie { .. } => args.get_mut("cookies"),
+                Parameter::Path { .. } | Parameter::Query { .. } => Some(&mut *args),
+            };
+            if let Some(Value::Object(arguments)) = arguments {
+                arguments.remove(&data.name);
+            }
+        }
+        let (value, fixed) = match (data.extensions.get(VALUE), data.extensions.get(DEFAULT)) {
+            (Some(value), _) => (value, true),
+            (None, Some(default)) => (default, false),
+            (None, None) => continue,
+        };
+        let arguments = match parameter {
+            Parameter::Header { .. } => &mut args["headers"],
+            Parameter::Cookie { .. } => &mut args["cookies"],
+            Parameter::Path { .. } | Parameter::Query { .. } => &mut *args,
+        };
+        if fixed || arguments.get(&data.name).is_none_or(Value::is_null) {
+            arguments[&data.name] = value.clone();
+        }
+    }
+}
+
+/// Removes the bridge's extensions from a document at any depth, as they
+/// configure the bridge and may hold values agents must not read.
+pub(super) fn strip(document: &mut Value) {
+    match document {
+        Value::Object(object) => {
+            object.retain(|key, _| !key.starts_with(PREFIX));
+            object.values_mut().for_each(strip);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip),
+        _ => {}
+    }
+}
+
+fn flag(extensions: &IndexMap<String, Value>, name: &str) -> bool {
+    extensions.get(name).and_then(Value::as_bool).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn parameter(mut parameter: Value) -> Parameter {
+        parameter["schema"] = json!({"type": "string"});
+        serde_json::from_value(parameter).unwrap()
+    }
+
+    #[test]
+    fn test_inject() {
+        let parameters = [
+            parameter(json!({"in": "query", "name": "region", "x-brwse-value": "eu"})),
+            parameter(json!({"in": "query", "name": "limit", "x-brwse-default": 20})),
+            parameter(json!({"in": "header", "name": "X-Tenant", "x-brwse-value": "acme"})),
+            parameter(json!({"in": "query", "name": "sort"})),
+            parameter(json!({"in": "query", "name": "debug", "x-brwse-hidden": true})),
+            parameter(json!({"in": "header", "name": "X-Debug", "x-brwse-hidden": true})),
+        ];
+        assert!(!exposed(&parameters[0]) && exposed(&parameters[1]));
+
+        let mut args = json!({
+            "region": "us",
+            "limit": 5,
+            "debug": true,
+            "headers": {"X-Trace": "1", "X-Debug": "1"},
+        });
+        inject(&parameters.iter().collect::<Vec<_>>(), &mut args);
+        assert_eq!(
+            args,
+            json!({"region": "eu", "limit": 5, "headers": {"X-Trace": "1", "X-Tenant": "acme"}})
+        );
+
+        let mut args = json!({});
+        inject(&parameters.iter().collect::<Vec<_>>(), &mut args);
+        assert_eq!(args, json!({"region": "eu", "limit": 20, "headers": {"X-Tenant": "acme"}}));
+    }
+}
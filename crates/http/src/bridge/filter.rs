@@ -0,0 +1,152 @@
+use super::{ToolInfo, extensions};
+
+/// Methods that leave upstream state untouched, the only ones exposed in
+/// read-only mode.
+const SAFE_METHODS: [&str; 3] = ["get", "head", "options"];
+
+/// Selects which operations of a spec are exposed as tools.
+///
+/// Empty include lists admit everything; exclusions take precedence over
+/// inclusions. Operation patterns are globs (`*` matches any run of
+/// characters, `?` a single one) matched against both the tool name, with
+/// its namespace prefix, and the path, e.g. `list*` or `/admin/*`. Operations
+/// marked with the `x-brwse-hidden` extension are always filtered out.
+#[derive(Debug, Clone, Default)]
+pub struct ToolFilter {
+    pub include_tags: Vec<String>,
+    pub exclude_tags: Vec<String>,
+    pub include_operations: Vec<String>,
+    pub exclude_operations: Vec<String>,
+    pub include_methods: Vec<String>,
+    pub exclude_methods: Vec<String>,
+    pub exclude_deprecated: bool,
+    /// Only expose `GET`, `HEAD` and `OPTIONS` operations.
+    pub read_only: bool,
+}
+
+impl ToolFilter {
+    pub(super) fn allows(&self, tool: &ToolInfo) -> bool {
+        let ToolInfo { id, path, method, operation, .. } = tool;
+
+        // Operations hidden by the spec are never exposed
+        if extensions::hidden(&operation.extensions) {
+            return false;
+        }
+
+        if self.read_only && !SAFE_METHODS.contains(method) {
+            return false;
+        }
+        if self.exclude_deprecated && operation.deprecated {
+            return false;
+        }
+
+        let has_method =
+            |methods: &[String]| methods.iter().any(|m| m.eq_ignore_ascii_case(method));
+        if has_method(&self.exclude_methods)
+            || !self.include_methods.is_empty() && !has_method(&self.include_methods)
+        {
+            return false;
+        }
+
+        let has_tag = |tags: &[String]| operation.tags.iter().any(|tag| tags.contains(tag));
+        if has_tag(&self.exclude_tags)
+            || !self.include_tags.is_empty() && !has_tag(&self.include_tags)
+        {
+            return false;
+        }
+
+        let matches = |patterns: &[String]| {
+            patterns.iter().any(|pattern| glob_match(pattern, id) || glob_match(pattern, path))
+        };
+        !matches(&self.exclude_operations)
+            && (self.include_operations.is_empty() || matches(&self.include_operations))
+    }
+}
+
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let (pattern, text) = (pattern.as_bytes(), text.as_bytes());
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text position it currently absorbs up to.
+    let mut star = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use openapiv3::{Operation, PathItem};
+
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("list*", "listUsers"));
+        assert!(glob_match("*Users", "listUsers"));
+        assert!(glob_match("/users/*/posts", "/users/{id}/posts"));
+        assert!(glob_match("get?ser", "getUser"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("list*", "getUsers"));
+        assert!(!glob_match("get?ser", "getUsers"));
+        assert!(!glob_match("", "a"));
+    }
+
+    #[test]
+    fn test_allows() {
+        let path_item = PathItem::default();
+        let admin = Operation { tags: vec!["admin".to_string()], ..Default::default() };
+        let deprecated = Operation { deprecated: true, ..Default::default() };
+        let tool = |id: &'static str, method: &'static str, operation| ToolInfo {
+            id: Cow::Borrowed(id),
+            path: "/users",
+            path_item: &path_item,
+            method,
+            operation,
+        };
+        let list_users = tool("listUsers", "get", &deprecated);
+        let delete_user = tool("deleteUser", "delete", &admin);
+
+        let filter = ToolFilter::default();
+        assert!(filter.allows(&list_users) && filter.allows(&delete_user));
+
+        let filter = ToolFilter { read_only: true, ..Default::default() };
+        assert!(filter.allows(&list_users) && !filter.allows(&delete_user));
+
+        let filter = ToolFilter { exclude_deprecated: true, ..Default::default() };
+        assert!(!filter.allows(&list_users) && filter.allows(&delete_user));
+
+        let filter =
+            ToolFilter { include_methods: vec!["DELETE".to_string()], ..Default::default() };
+        assert!(!filter.allows(&list_users) && filter.allows(&delete_user));
+
+        let filter = ToolFilter { include_tags: vec!["admin".to_string()], ..Default::default() };
+        assert!(!filter.allows(&list_users) && filter.allows(&delete_user));
+
+        let filter = ToolFilter {
+            include_operations: vec!["/users".to_string()],
+            exclude_operations: vec!["delete*".to_string()],
+            ..Default::default()
+        };
+        assert!(filter.allows(&list_users) && !filter.allows(&delete_user));
+    }
+}
@@ -0,0 +1,112 @@
+use std::{borrow::Cow, sync::Arc};
+
+use indexmap::IndexMap;
+use jsonschema::Validator;
+use openapiv3::{OpenAPI, ReferenceOr};
+use rmcp::model::Tool;
+use serde_json::Value;
+
+use super::{ToolInfo, operation};
+
+/// The tools generated from a spec, keyed by name in spec order, with the
+/// validators for their arguments compiled up front.
+pub(super) struct ToolIndex {
+    spec: Arc<OpenAPI>,
+    namespace: Option<String>,
+    tools: IndexMap<String, IndexedTool>,
+}
+
+struct IndexedTool {
+    tool: Tool,
+    path: String,
+    method: &'static str,
+    validators: Validators,
+}
+
+pub(super) struct Validators {
+    /// Checks the arguments of calls.
+    pub input: Result<Validator, rmcp::Error>,
+    /// Checks successful responses, when the operation declares their schema.
+    pub output: Option<Validator>,
+    /// The input schema before the strict profile made optional properties
+    /// nullable, telling which nulls in arguments stand for omitted ones.
+    pub strict_source: Option<Value>,
+}
+
+impl ToolIndex {
+    pub fn new(spec: Arc<OpenAPI>, namespace: Option<String>) -> Self {
+        Self { spec, namespace, tools: IndexMap::new() }
+    }
+
+    pub fn spec(&self) -> &Arc<OpenAPI> {
+        &self.spec
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    /// Prefixes the name of a tool or prompt with the namespace, if any.
+    pub fn qualify<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        match &self.namespace {
+            Some(namespace) => Cow::Owned(format!("{namespace}_{name}")),
+            None => Cow::Borrowed(name),
+        }
+    }
+
+    /// Adds the tool generated for an operation, along with the schema of its
+    /// result. Later operations that reuse a tool name are shadowed by the
+    /// first one.
+    pub fn insert(
+        &mut self,
+        info: &ToolInfo,
+        (tool, strict_source): (Tool, Option<Value>),
+        output_schema: Option<Value>,
+    ) {
+        if self.tools.contains_key(info.id.as_ref()) {
+            return;
+        }
+        let schema = Value::Object(tool.input_schema.as_ref().clone());
+        let input = jsonschema::validator_for(&schema).map_err(|err| {
+            rmcp::Error::internal_error(format!("failed to create validator: {err}"), Some(schema))
+        });
+        let output = output_schema.and_then(|schema| jsonschema::validator_for(&schema).ok());
+        let validators = Validators { input, output, strict_source };
+        let (path, method) = (info.path.to_string(), info.method);
+        self.tools.insert(info.id.to_string(), IndexedTool { tool, path, method, validators });
+    }
+
+    /// Looks up a tool by name, returning its operation and validators.
+    pub fn get(&self, name: &str) -> Option<(ToolInfo<'_>, &Validators)> {
+        let (id, entry) = self.tools.get_key_value(name)?;
+        Some((self.info(id, entry)?, &entry.validators))
+    }
+
+    /// Returns the operations of all tools, in order.
+    pub fn operations(&self) -> impl Iterator<Item = ToolInfo<'_>> {
+        self.tools.iter().filter_map(|(id, entry)| self.info(id, entry))
+    }
+
+    fn info<'a>(&'a self, id: &'a str, entry: &'a IndexedTool) -> Option<ToolInfo<'a>> {
+        let ReferenceOr::Item(path_item) = self.spec.paths.paths.get(&entry.path)? else {
+            return None;
+        };
+        Some(ToolInfo {
+            id: Cow::Borrowed(id),
+            path: &entry.path,
+            path_item,
+            method: entry.method,
+            operation: operation(path_item, entry.method)?,
+        })
+    }
+
+    /// Returns the tools following the one named by `cursor`, or all of them
+    /// without a cursor. Returns `None` for unknown cursors.
+    pub fn after(&self, cursor: Option<&str>) -> Option<impl Iterator<Item = &Tool>> {
+        let start = match cursor {
+            Some(cursor) => self.tools.get_index_of(cursor)? + 1,
+            None => 0,
+        };
+        Some(self.tools.values().skip(start).map(|entry| &entry.tool))
+    }
+}
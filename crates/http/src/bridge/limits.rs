@@ -0,0 +1,332 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use openapiv3::Operation;
+use reqwest::Response;
+use rmcp::model::ErrorCode;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Spec extension overriding the limits of an operation.
+const EXTENSION: &str = "x-brwse-rate-limit";
+
+/// Error code of calls rejected for exceeding a limit, from the range JSON-RPC
+/// leaves to implementations.
+const RATE_LIMITED: ErrorCode = ErrorCode(-32029);
+
+/// Caps on the calls made to an upstream. Caps left unset or set to zero
+/// do not apply.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Limit {
+    /// Calls per second, sustained.
+    pub rate: Option<f64>,
+    /// Calls that may be made at once after a quiet period. Defaults to the
+    /// rate, rounded up.
+    pub burst: Option<u32>,
+    /// Calls that may be in flight at the same time, until their responses
+    /// are read.
+    pub max_in_flight: Option<usize>,
+}
+
+/// Limits on the calls made to upstreams, applied across all sessions.
+///
+/// A call must pass the limits of its operation, of its host and the global
+/// ones. Operations may set their own limits with the `x-brwse-rate-limit`
+/// extension, e.g. `{rate: 5, burst: 10, maxInFlight: 2}`; limits configured
+/// here for the operation take precedence.
+#[derive(Debug, Clone, Default)]
+pub struct LimitOptions {
+    pub global: Limit,
+    /// Limits keyed by host name.
+    pub hosts: HashMap<String, Limit>,
+    /// Limits keyed by tool name, namespace prefix included, so they stay
+    /// apart when several APIs share the limiter.
+    pub operations: HashMap<String, Limit>,
+    /// Fail calls exceeding a limit rather than queuing them.
+    pub reject: bool,
+}
+
+/// Gates by key, along with the limit each was made for.
+type Gates = Mutex<HashMap<String, (Limit, Arc<Gate>)>>;
+
+/// Tracks usage against the configured limits.
+#[derive(Default)]
+pub(super) struct Limiter {
+    options: LimitOptions,
+    global: Gate,
+    hosts: Gates,
+    operations: Gates,
+}
+
+/// Held for the duration of a call, freeing its in-flight slots when dropped.
+pub(super) struct Permits {
+    _held: Vec<OwnedSemaphorePermit>,
+}
+
+impl Permits {
+    /// Keeps the in-flight slots of a call until its response is dropped,
+    /// once its body has been read.
+    pub fn hold(self, response: &mut Response) {
+        response.extensions_mut().insert(Arc::new(self));
+    }
+
+    /// Takes the slots held by a response, for the caller to keep while it
+    /// reads the body by value.
+    pub fn take(response: &mut Response) -> Option<Arc<Self>> {
+        response.extensions_mut().remove()
+    }
+}
+
+impl Limiter {
+    pub fn new(options: LimitOptions) -> Self {
+        Self { global: Gate::new(&options.global), options, ..Default::default() }
+    }
+
+    /// Waits until a call to `operation` at `host` is allowed, or fails when
+    /// limits are configured to reject.
+    pub async fn acquire(
+        &self,
+        name: &str,
+        operation: &Operation,
+        host: Option<&str>,
+    ) -> Result<Permits, rmcp::Error> {
+        let operation = self.operation(name, operation);
+        let host = host.and_then(|host| {
+            let limit = self.options.hosts.get(host)?;
+            Some(gate(&self.hosts, host, limit))
+        });
+
+        let mut permits = Vec::new();
+        let mut passed = Vec::new();
+        let gates = [("operation", operation.as_deref()), ("host", host.as_deref())];
+        for (scope, gate) in gates.into_iter().chain([("global", Some(&self.global))]) {
+            let Some(gate) = gate else {
+                continue;
+            };
+            match gate.pass(self.options.reject).await {
+                Ok(permit) => {
+                    permits.extend(permit);
+                    passed.push(gate);
+                }
+                Err(retry_after) => {
+                    // The call is not made, so the limits it passed keep their tokens
+                    passed.into_iter().for_each(Gate::refund);
+                    let limit = if retry_after.is_some() { "rate" } else { "concurrency" };
+                    return Err(rmcp::Error::new(
+                        RATE_LIMITED,
+                        format!("{scope} {limit} limit exceeded"),
+                        retry_after.map(|delay| json!({"retryAfterMs": delay.as_millis()})),
+                    ));
+                }
+            }
+        }
+        Ok(Permits { _held: permits })
+    }
+
+    fn operation(&self, name: &str, operation: &Operation) -> Option<Arc<Gate>> {
+        if let Some(limit) = self.options.operations.get(name) {
+            return Some(gate(&self.operations, name, limit));
+        }
+        let limit = operation.extensions.get(EXTENSION)?;
+        let limit = serde_json::from_value::<Limit>(limit.clone()).ok()?;
+        Some(gate(&self.operations, name, &limit))
+    }
+}
+
+/// Returns the gate of `key`, made anew when its limit changed, as it does
+/// when specs are reloaded.
+fn gate(gates: &Gates, key: &str, limit: &Limit) -> Arc<Gate> {
+    let mut gates = gates.lock().unwrap();
+    match gates.get(key) {
+        Some((current, gate)) if current == limit => Arc::clone(gate),
+        _ => {
+            let gate = Arc::new(Gate::new(limit));
+            gates.insert(key.to_string(), (limit.clone(), Arc::clone(&gate)));
+            gate
+        }
+    }
+}
+
+#[derive(Default)]
+struct Gate {
+    bucket: Option<Mutex<Bucket>>,
+    in_flight: Option<Arc<Semaphore>>,
+}
+
+impl Gate {
+    fn new(limit: &Limit) -> Self {
+        let bucket = limit.rate.filter(|rate| *rate > 0.0).map(|rate| {
+            let burst = limit.burst.map_or(rate.ceil(), f64::from).max(1.0);
+            Mutex::new(Bucket { rate, burst, tokens: burst, updated: Instant::now() })
+        });
+        let in_flight = limit.max_in_flight.filter(|max| *max > 0);
+        let in_flight = in_flight.map(|max| Arc::new(Semaphore::new(max)));
+        Self { bucket, in_flight }
+    }
+
+    /// Takes a token and an in-flight slot, waiting for them unless
+    /// `reject`ing. Rejections carry the delay after which a token is free,
+    /// when that is what is missing.
+    async fn pass(&self, reject: bool) -> Result<Option<OwnedSemaphorePermit>, Option<Duration>> {
+        if let Some(bucket) = &self.bucket {
+            let delay = bucket.lock().unwrap().take(reject)?;
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+        }
+        let Some(in_flight) = &self.in_flight else {
+            return Ok(None);
+        };
+        let in_flight = Arc::clone(in_flight);
+        let permit = if reject {
+            in_flight.try_acquire_owned().map_err(|_| {
+                self.refund();
+                None
+            })?
+        } else {
+            in_flight.acquire_owned().await.map_err(|_| None)?
+        };
+        Ok(Some(permit))
+    }
+
+    /// Gives back the token taken by a call that was rejected after all.
+    fn refund(&self) {
+        if let Some(bucket) = &self.bucket {
+            let mut bucket = bucket.lock().unwrap();
+            bucket.tokens = (bucket.tokens + 1.0).min(bucket.burst);
+        }
+    }
+}
+
+/// A token bucket refilled at `rate` tokens per second up to `burst`.
+struct Bucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    /// Takes a token, returning how long to wait until it is available. When
+    /// queuing, the token is reserved right away so later calls line up
+    /// behind it.
+    fn take(&mut self, reject: bool) -> Result<Duration, Option<Duration>> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.updated = now;
+
+        let delay = Duration::from_secs_f64(((1.0 - self.tokens) / self.rate).max(0.0));
+        if reject && !delay.is_zero() {
+            return Err(Some(delay));
+        }
+        self.tokens -= 1.0;
+        Ok(delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket() {
+        let mut bucket = Bucket { rate: 10.0, burst: 2.0, tokens: 2.0, updated: Instant::now() };
+        assert_eq!(bucket.take(true), Ok(Duration::ZERO));
+        assert_eq!(bucket.take(true), Ok(Duration::ZERO));
+        let delay = bucket.take(true).unwrap_err().unwrap();
+        assert!(delay > Duration::from_millis(50) && delay <= Duration::from_millis(100));
+
+        // Queued calls reserve their token and wait their turn
+        let first = bucket.take(false).unwrap();
+        let second = bucket.take(false).unwrap();
+        assert!(second > first && second <= Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn test_limiter() {
+        let options = LimitOptions {
+            hosts: HashMap::from([(
+                "api.example.com".to_string(),
+                Limit { max_in_flight: Some(1), ..Default::default() },
+            )]),
+            reject: true,
+            ..Default::default()
+        };
+        let limiter = Limiter::new(options);
+        let operation: Operation = serde_json::from_value(json!({
+            "responses": {},
+            "x-brwse-rate-limit": {"rate": 1, "maxInFlight": 5},
+        }))
+        .unwrap();
+
+        let unlimited = Operation::default();
+        let host = Some("api.example.com");
+
+        let permits = limiter.acquire("listUsers", &operation, host).await;
+        let error = limiter.acquire("getUser", &unlimited, host).await.err().unwrap();
+        assert_eq!(error.message, "host concurrency limit exceeded");
+        assert!(error.data.is_none());
+        drop(permits);
+
+        let error = limiter.acquire("listUsers", &operation, None).await.err().unwrap();
+        assert_eq!(error.message, "operation rate limit exceeded");
+        assert!(error.data.unwrap()["retryAfterMs"].as_u64().unwrap() > 0);
+        let permits = limiter.acquire("getUser", &unlimited, host).await;
+        assert!(permits.is_ok());
+
+        // Calls rejected by the host do not use up the token of their operation
+        let error = limiter.acquire("listOrders", &operation, host).await.err().unwrap();
+        assert_eq!(error.message, "host concurrency limit exceeded");
+        drop(permits);
+        assert!(limiter.acquire("listOrders", &operation, host).await.is_ok());
+
+        // Limits changed by a reloaded spec apply right away
+        let error = limiter.acquire("listUsers", &operation, None).await.err().unwrap();
+        assert_eq!(error.message, "operation rate limit exceeded");
+        let reloaded: Operation = serde_json::from_value(json!({
+            "responses": {},
+            "x-brwse-rate-limit": {"rate": 100},
+        }))
+        .unwrap();
+        assert!(limiter.acquire("listUsers", &reloaded, None).await.is_ok());
+        assert!(limiter.acquire("listUsers", &unlimited, None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_responses_hold_permits() {
+        use wiremock::{Mock, MockServer, ResponseTemplate, matchers::any};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(any()).respond_with(ResponseTemplate::new(200)).mount(&mock_server).await;
+        let limit = Limit { max_in_flight: Some(1), ..Default::default() };
+        let options = LimitOptions { global: limit, reject: true, ..Default::default() };
+        let limiter = Limiter::new(options);
+        let operation = Operation::default();
+
+        let permits = limiter.acquire("listUsers", &operation, None).await.unwrap();
+        let mut response = reqwest::get(mock_server.uri()).await.unwrap();
+        permits.hold(&mut response);
+        assert!(limiter.acquire("listUsers", &operation, None).await.is_err());
+
+        // Until the body is read
+        let permits = Permits::take(&mut response);
+        response.bytes().await.unwrap();
+        assert!(limiter.acquire("listUsers", &operation, None).await.is_err());
+        drop(permits);
+        assert!(limiter.acquire("listUsers", &operation, None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_zero_is_unlimited() {
+        let limit = Limit { rate: Some(0.0), burst: None, max_in_flight: Some(0) };
+        let limiter = Limiter::new(LimitOptions { global: limit, ..Default::default() });
+        let permits = limiter.acquire("listUsers", &Operation::default(), None).await;
+        assert!(permits.is_ok());
+    }
+}
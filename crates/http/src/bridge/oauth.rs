@@ -0,0 +1,280 @@
+use std::time::{Duration, Instant};
+
+use openapiv3::SecurityScheme;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+/// Tokens expiring within this window are refreshed before they are used.
+const REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+/// Client settings used to obtain access tokens for an `oauth2` security
+/// scheme.
+#[derive(Debug, Clone, Default)]
+pub struct OAuth2Config {
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    /// A long-lived refresh token, used instead of the client credentials
+    /// grant when present.
+    pub refresh_token: Option<String>,
+    /// Overrides the token URL declared in the spec's flows.
+    pub token_url: Option<String>,
+    pub scopes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+    refresh_token: Option<String>,
+}
+
+struct AccessToken {
+    value: String,
+    expires_at: Option<Instant>,
+}
+
+impl AccessToken {
+    fn is_fresh(&self) -> bool {
+        self.expires_at.is_none_or(|expires_at| Instant::now() + REFRESH_MARGIN < expires_at)
+    }
+}
+
+#[derive(Default)]
+struct State {
+    access_token: Option<AccessToken>,
+    refresh_token: Option<String>,
+}
+
+/// Obtains and caches access tokens for a single security scheme.
+pub struct TokenSource {
+    config: OAuth2Config,
+    state: Mutex<State>,
+}
+
+impl TokenSource {
+    pub fn new(config: OAuth2Config) -> Self {
+        let state = State { access_token: None, refresh_token: config.refresh_token.clone() };
+        Self { config, state: Mutex::new(state) }
+    }
+
+    /// Returns a cached access token, fetching a new one when none is cached or
+    /// the cached one is about to expire.
+    pub async fn access_token(
+        &self,
+        client: &reqwest::Client,
+        scheme: &SecurityScheme,
+    ) -> Result<String, rmcp::Error> {
+        let mut state = self.state.lock().await;
+        if let Some(token) = &state.access_token
+            && token.is_fresh()
+        {
+            return Ok(token.value.clone());
+        }
+
+        let token_url =
+            self.config.token_url.clone().or_else(|| token_url(scheme)).ok_or_else(|| {
+                rmcp::Error::internal_error("no token URL configured for OAuth2 scheme", None)
+            })?;
+        let token_url = token_url.as_str();
+
+        let response = match state.refresh_token.clone() {
+            Some(refresh_token) => {
+                let grant = [("grant_type", "refresh_token"), ("refresh_token", &refresh_token)];
+                match self.request(client, token_url, &grant).await {
+                    Ok(response) => response,
+                    Err(e) if self.config.client_secret.is_some() => {
+                        warn!("Refreshing OAuth2 token failed, using client credentials: {}", e);
+                        self.client_credentials(client, token_url).await?
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            None => self.client_credentials(client, token_url).await?,
+        };
+
+        if response.refresh_token.is_some() {
+            state.refresh_token = response.refresh_token;
+        }
+        let token = state.access_token.insert(AccessToken {
+            value: response.access_token,
+            expires_at: response.expires_in.map(|secs| Instant::now() + Duration::from_secs(secs)),
+        });
+        Ok(token.value.clone())
+    }
+
+    /// Drops the cached access token, e.g. after the upstream rejected it.
+    pub async fn invalidate(&self) {
+        self.state.lock().await.access_token = None;
+    }
+
+    async fn client_credentials(
+        &self,
+        client: &reqwest::Client,
+        token_url: &str,
+    ) -> Result<TokenResponse, rmcp::Error> {
+        let scope = self.config.scopes.join(" ");
+        let mut grant = vec![("grant_type", "client_credentials")];
+        if !scope.is_empty() {
+            grant.push(("scope", &scope));
+        }
+        self.request(client, token_url, &grant).await
+    }
+
+    async fn request(
+        &self,
+        client: &reqwest::Client,
+        token_url: &str,
+        grant: &[(&str, &str)],
+    ) -> Result<TokenResponse, rmcp::Error> {
+        let mut request = client.post(token_url);
+        request = match &self.config.client_secret {
+            Some(secret) => request.basic_auth(&self.config.client_id, Some(secret)).form(grant),
+            None => {
+                let mut form = grant.to_vec();
+                form.push(("client_id", &self.config.client_id));
+                request.form(&form)
+            }
+        };
+
+        let response = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| token_error(token_url, e))?;
+        response.json().await.map_err(|e| token_error(token_url, e))
+    }
+}
+
+/// Returns the token URL declared by the flows of an `oauth2` scheme.
+fn token_url(scheme: &SecurityScheme) -> Option<String> {
+    let SecurityScheme::OAuth2 { flows, .. } = scheme else {
+        return None;
+    };
+    // The flow objects keep their fields private, so read them back through
+    // their serialized form.
+    let flows = serde_json::to_value(flows).ok()?;
+    ["clientCredentials", "authorizationCode", "password"]
+        .into_iter()
+        .find_map(|flow| flows[flow]["tokenUrl"].as_str().map(str::to_string))
+}
+
+fn token_error(token_url: &str, error: reqwest::Error) -> rmcp::Error {
+    rmcp::Error::internal_error(
+        "failed to obtain OAuth2 access token",
+        Some(json!({
+            "tokenUrl": token_url,
+            "error": error.to_string(),
+        })),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_string_contains, header, method, path},
+    };
+
+    use super::*;
+
+    fn scheme(token_url: &str) -> SecurityScheme {
+        serde_json::from_value(json!({
+            "type": "oauth2",
+            "flows": {
+                "clientCredentials": {"tokenUrl": token_url, "scopes": {}}
+            }
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_client_credentials_token_is_cached() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(header("Authorization", "Basic aWQ6c2VjcmV0")) // id:secret
+            .and(body_string_contains("grant_type=client_credentials"))
+            .and(body_string_contains("scope=read+write"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"access_token": "abc", "expires_in": 3600})),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let source = TokenSource::new(OAuth2Config {
+            client_id: "id".to_string(),
+            client_secret: Some("secret".to_string()),
+            scopes: vec!["read".to_string(), "write".to_string()],
+            ..Default::default()
+        });
+        let client = reqwest::Client::new();
+        let scheme = scheme(&format!("{}/token", mock_server.uri()));
+
+        assert_eq!(source.access_token(&client, &scheme).await.unwrap(), "abc");
+        assert_eq!(source.access_token(&client, &scheme).await.unwrap(), "abc");
+    }
+
+    #[tokio::test]
+    async fn test_expiring_token_is_refreshed() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_string_contains("grant_type=refresh_token"))
+            .and(body_string_contains("refresh_token=r1"))
+            .and(body_string_contains("client_id=public"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "access_token": "first",
+                "expires_in": 10,
+                "refresh_token": "r2",
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_string_contains("refresh_token=r2"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({"access_token": "second"})),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let source = TokenSource::new(OAuth2Config {
+            client_id: "public".to_string(),
+            refresh_token: Some("r1".to_string()),
+            token_url: Some(format!("{}/token", mock_server.uri())),
+            ..Default::default()
+        });
+        let client = reqwest::Client::new();
+        let scheme = scheme("https://ignored.example.com/token");
+
+        // The first token expires within the refresh margin, so it is replaced
+        // using the rotated refresh token.
+        assert_eq!(source.access_token(&client, &scheme).await.unwrap(), "first");
+        assert_eq!(source.access_token(&client, &scheme).await.unwrap(), "second");
+        assert_eq!(source.access_token(&client, &scheme).await.unwrap(), "second");
+    }
+
+    #[tokio::test]
+    async fn test_token_endpoint_error() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&mock_server)
+            .await;
+
+        let source = TokenSource::new(OAuth2Config {
+            client_id: "id".to_string(),
+            client_secret: Some("wrong".to_string()),
+            ..Default::default()
+        });
+        let scheme = scheme(&format!("{}/token", mock_server.uri()));
+
+        let error = source.access_token(&reqwest::Client::new(), &scheme).await.unwrap_err();
+        assert_eq!(error.message, "failed to obtain OAuth2 access token");
+    }
+}
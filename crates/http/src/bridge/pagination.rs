@@ -0,0 +1,382 @@
+use std::{collections::HashMap, future::Future};
+
+use base64::{Engine as _, prelude::BASE64_URL_SAFE_NO_PAD};
+use openapiv3::Operation;
+use reqwest::{
+    RequestBuilder, Response, Url,
+    header::{HeaderMap, LINK},
+};
+use rmcp::model::{CallToolResult, Content};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tracing::warn;
+
+use super::{
+    limits::Permits,
+    response::{self, ResponseOptions, failed},
+};
+
+/// Spec extension enabling pagination following for an operation.
+const EXTENSION: &str = "x-brwse-pagination";
+
+/// Argument and result field carrying the token to resume from.
+pub(super) const NEXT_PAGE: &str = "nextPage";
+
+/// How the pages of a list operation link to each other.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pagination {
+    /// JSON pointer to the items of a page, e.g. `/data`. Pages must be
+    /// arrays themselves when unset.
+    pub items: Option<String>,
+    /// JSON pointer to the URL or cursor of the next page, e.g.
+    /// `/meta/nextCursor`. The `Link` header with `rel="next"` is followed
+    /// when unset.
+    pub next: Option<String>,
+    /// Query parameter the cursor found at `next` is sent in. Without it, the
+    /// value found there is taken to be a URL.
+    pub cursor_param: Option<String>,
+    /// Overrides [`PaginationOptions::max_pages`].
+    pub max_pages: Option<usize>,
+    /// Overrides [`PaginationOptions::max_items`].
+    pub max_items: Option<usize>,
+}
+
+/// Controls which operations have their pages followed, and how far.
+///
+/// Operations opt in here or with the `x-brwse-pagination` extension, e.g.
+/// `{items: "/data", next: "/meta/cursor", cursorParam: "cursor"}`. Their
+/// tools return the items of the pages fetched in one array, along with a
+/// `nextPage` token agents pass back to continue where they stopped.
+///
+/// [`ResponseOptions`] only shape a failing first page, reported as is. The
+/// merged items are not one upstream response, so they get no envelope and
+/// are not validated against the response schema.
+#[derive(Debug, Clone)]
+pub struct PaginationOptions {
+    /// Operations to paginate, by tool name, whether or not their spec has
+    /// the extension.
+    pub operations: HashMap<String, Pagination>,
+    /// Pages fetched per call at most.
+    pub max_pages: usize,
+    /// Items returned per call at most. A page going over is cut short, and
+    /// its other items are returned by the call resuming from it.
+    pub max_items: Option<usize>,
+}
+
+impl Default for PaginationOptions {
+    fn default() -> Self {
+        Self { operations: HashMap::new(), max_pages: 5, max_items: None }
+    }
+}
+
+impl PaginationOptions {
+    /// Returns how the pages of an operation are followed, if they are.
+    pub(super) fn get(&self, name: &str, operation: &Operation) -> Option<Pagination> {
+        if let Some(pagination) = self.operations.get(name) {
+            return Some(pagination.clone());
+        }
+        let pagination = operation.extensions.get(EXTENSION)?;
+        serde_json::from_value(pagination.clone()).ok()
+    }
+}
+
+/// Adds the argument resuming a previous call to an input schema.
+pub(super) fn extend_schema(schema: &mut Value) {
+    schema["properties"][NEXT_PAGE] = json!({
+        "type": "string",
+        "description": "Token returned by a previous call to fetch the pages after it",
+    });
+}
+
+/// Fetches the pages of a list operation with `fetch`, starting with
+/// `request` or from the page a `token` points at, and merges their items.
+///
+/// Pages are followed within the first page's URL only, so neither upstreams
+/// nor tokens can send the request and its credentials elsewhere. A failing
+/// first page is reported as usual; later failures end the call early with
+/// the items so far, the `error` of the failed page and a token to retry
+/// from.
+pub(super) async fn collect<F, Fut>(
+    pagination: &Pagination,
+    options: &PaginationOptions,
+    request: RequestBuilder,
+    token: Option<&str>,
+    response_options: &ResponseOptions,
+    fetch: F,
+) -> Result<CallToolResult, rmcp::Error>
+where
+    F: Fn(RequestBuilder) -> Fut,
+    Fut: Future<Output = Result<reqwest::Result<Response>, rmcp::Error>>,
+{
+    let (client, template) = request.build_split();
+    let template = match template {
+        Ok(template) => template,
+        Err(e) => return Ok(failed(e)),
+    };
+    let first = template.url().clone();
+    let (mut url, mut skip) = match token {
+        Some(token) => resume(token, &first)?,
+        None => (first.clone(), 0),
+    };
+
+    let max_pages = pagination.max_pages.unwrap_or(options.max_pages).max(1);
+    let max_items = pagination.max_items.or(options.max_items);
+    let mut items = Vec::new();
+    let mut pages = 0;
+    let mut error = None;
+    let next = loop {
+        let Some(mut request) = template.try_clone() else {
+            // Streamed bodies cannot be sent again
+            let message = "Requests with streamed bodies cannot be paginated";
+            return Ok(CallToolResult::error(vec![Content::text(message)]));
+        };
+        *request.url_mut() = url.clone();
+        let (status, page) =
+            match fetch(RequestBuilder::from_parts(client.clone(), request)).await? {
+                Ok(response) => {
+                    (Some(response.status()), page(pagination, response, response_options).await?)
+                }
+                Err(e) => (None, Err(failed(e))),
+            };
+        let page = match page {
+            Ok(page) => page,
+            Err(result) if pages == 0 => return Ok(result),
+            Err(result) => {
+                let message = result.content.iter().filter_map(|content| content.as_text());
+                let message = message.map(|text| text.text.as_str()).collect::<Vec<_>>();
+                let status = status.map(|status| status.as_u16());
+                error = Some(json!({"status": status, "message": message.join("\n")}));
+                break Some(url);
+            }
+        };
+
+        pages += 1;
+        let mut page_items = page.items;
+        // Items returned by the call that stopped within this page
+        let skipped = std::mem::take(&mut skip).min(page_items.len());
+        page_items.drain(..skipped);
+        if let Some(max) = max_items
+            && items.len() + page_items.len() > max
+        {
+            let taken = max.saturating_sub(items.len());
+            items.extend(page_items.drain(..taken));
+            url.set_fragment(Some(&(skipped + taken).to_string()));
+            break Some(url);
+        }
+        items.extend(page_items);
+        let Some(next) = page.next else {
+            break None;
+        };
+        if !same_resource(&next, &first) {
+            warn!("Not following next page {} outside of {}", next, first);
+            break None;
+        }
+        if pages >= max_pages || max_items.is_some_and(|max| items.len() >= max) {
+            break Some(next);
+        }
+        url = next;
+    };
+
+    let mut result = json!({"items": items, "pages": pages});
+    if let Some(next) = next {
+        result[NEXT_PAGE] = json!(BASE64_URL_SAFE_NO_PAD.encode(next.as_str()));
+    }
+    if let Some(error) = error {
+        result["error"] = error;
+    }
+    Ok(CallToolResult::success(vec![response::json_content(result)?]))
+}
+
+struct Page {
+    items: Vec<Value>,
+    next: Option<Url>,
+}
+
+/// Reads the items of a page and the URL of the next one. Pages that cannot
+/// be read are returned as the tool result to report.
+async fn page(
+    pagination: &Pagination,
+    mut response: Response,
+    options: &ResponseOptions,
+) -> Result<Result<Page, CallToolResult>, rmcp::Error> {
+    if !response.status().is_success() {
+        return response::into_result(response, options, None, None).await.map(Err);
+    }
+    let url = response.url().clone();
+    let link = next_link(response.headers(), &url);
+    let _permits = Permits::take(&mut response);
+    let body = match response.json::<Value>().await {
+        Ok(body) => body,
+        Err(e) => return Ok(Err(failed(e))),
+    };
+
+    let pointer = pagination.items.as_deref().unwrap_or_default();
+    let Some(Value::Array(items)) = body.pointer(pointer) else {
+        let message = format!("Response has no array of items at `{pointer}`");
+        return Ok(Err(CallToolResult::error(vec![Content::text(message)])));
+    };
+    let next = match &pagination.next {
+        None => link,
+        Some(pointer) => match body.pointer(pointer) {
+            Some(Value::String(next)) if !next.is_empty() => next_url(pagination, &url, next),
+            Some(Value::Number(next)) => next_url(pagination, &url, &next.to_string()),
+            _ => None,
+        },
+    };
+    Ok(Ok(Page { items: items.clone(), next }))
+}
+
+/// Resolves the next page from the value found at the `next` pointer.
+fn next_url(pagination: &Pagination, url: &Url, next: &str) -> Option<Url> {
+    let Some(param) = &pagination.cursor_param else {
+        return url.join(next).ok();
+    };
+    let query = url.query_pairs().filter(|(name, _)| name != param);
+    let query =
+        query.map(|(name, value)| (name.into_owned(), value.into_owned())).collect::<Vec<_>>();
+    let mut url = url.clone();
+    url.query_pairs_mut().clear().extend_pairs(query).append_pair(param, next);
+    Some(url)
+}
+
+/// Finds the `Link` header target with `rel="next"`, resolved against the
+/// `url` of the page.
+fn next_link(headers: &HeaderMap, url: &Url) -> Option<Url> {
+    let links = headers.get_all(LINK).iter().filter_map(|value| value.to_str().ok());
+    links.flat_map(split_links).find_map(|link| {
+        let (target, params) = link.trim().strip_prefix('<')?.split_once('>')?;
+        let is_next = params.split(';').any(|param| {
+            param.trim().strip_prefix("rel=").is_some_and(|rel| {
+                rel.trim_matches('"').split_whitespace().any(|rel| rel.eq_ignore_ascii_case("next"))
+            })
+        });
+        if is_next { url.join(target).ok() } else { None }
+    })
+}
+
+/// Splits a `Link` header into its links, at the commas outside of targets
+/// and quoted parameter values.
+fn split_links(value: &str) -> Vec<&str> {
+    let (mut links, mut start) = (Vec::new(), 0);
+    let (mut in_target, mut in_quotes) = (false, false);
+    for (i, c) in value.char_indices() {
+        match c {
+            '<' if !in_quotes => in_target = true,
+            '>' if !in_quotes => in_target = false,
+            '"' if !in_target => in_quotes = !in_quotes,
+            ',' if !in_target && !in_quotes => {
+                links.push(&value[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    links.push(&value[start..]);
+    links
+}
+
+/// Decodes a `nextPage` token, checking that it points at the operation.
+/// Returns the URL of the page to resume from and the number of its items
+/// already returned, which the token carries in the URL fragment.
+fn resume(token: &str, first: &Url) -> Result<(Url, usize), rmcp::Error> {
+    let url = BASE64_URL_SAFE_NO_PAD
+        .decode(token)
+        .ok()
+        .and_then(|url| String::from_utf8(url).ok())
+        .and_then(|url| Url::parse(&url).ok())
+        .filter(|url| same_resource(url, first))
+        .map(|mut url| {
+            let skip = url.fragment().and_then(|skip| skip.parse().ok()).unwrap_or(0);
+            url.set_fragment(None);
+            (url, skip)
+        });
+    url.ok_or_else(|| {
+        rmcp::Error::invalid_params(
+            format!("invalid {NEXT_PAGE} token"),
+            Some(Value::String(token.to_string())),
+        )
+    })
+}
+
+fn same_resource(url: &Url, first: &Url) -> bool {
+    url.origin() == first.origin() && url.path() == first.path()
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path, query_param},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_next_link() {
+        let url = Url::parse("https://api.test/items?page=1").unwrap();
+        let mut headers = HeaderMap::new();
+        let link = "<https://api.test/items?page=0>; rel=\"prev\"; title=\"back, to start\", \
+                    </items?page=2&ids=1,2>; rel=\"next last\"";
+        headers.insert(LINK, HeaderValue::from_static(link));
+        let next = next_link(&headers, &url).unwrap();
+        assert_eq!(next.as_str(), "https://api.test/items?page=2&ids=1,2");
+
+        headers.insert(LINK, HeaderValue::from_static("<https://api.test/items?page=0>; rel=prev"));
+        assert_eq!(next_link(&headers, &url), None);
+    }
+
+    #[test]
+    fn test_next_url() {
+        let url = Url::parse("https://api.test/items?limit=10&cursor=a").unwrap();
+        let by_url = Pagination::default();
+        let next = next_url(&by_url, &url, "/items?page=2").unwrap();
+        assert_eq!(next.as_str(), "https://api.test/items?page=2");
+
+        let by_cursor = Pagination { cursor_param: Some("cursor".to_string()), ..by_url };
+        let next = next_url(&by_cursor, &url, "b c").unwrap();
+        assert_eq!(next.as_str(), "https://api.test/items?limit=10&cursor=b+c");
+    }
+
+    #[tokio::test]
+    async fn test_max_items() {
+        let mock_server = MockServer::start().await;
+        for (page, items) in [("1", json!([1, 2, 3])), ("2", json!([4, 5, 6]))] {
+            let next = format!("</items?page={}>; rel=\"next\"", page.parse::<u8>().unwrap() + 1);
+            Mock::given(method("GET"))
+                .and(path("/items"))
+                .and(query_param("page", page))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_json(items).insert_header("Link", next),
+                )
+                .mount(&mock_server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/items"))
+            .and(query_param("page", "3"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .mount(&mock_server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let options = PaginationOptions { max_items: Some(4), ..Default::default() };
+        let list = async |token: Option<&str>| {
+            let request = client.get(format!("{}/items?page=1", mock_server.uri()));
+            let fetch = |request: RequestBuilder| async { Ok(request.send().await) };
+            let (pagination, response) = (Pagination::default(), ResponseOptions::default());
+            let result = collect(&pagination, &options, request, token, &response, fetch);
+            let result = result.await.unwrap();
+            serde_json::from_str::<Value>(&result.content[0].as_text().unwrap().text).unwrap()
+        };
+
+        // The second page is cut short, and the rest of it comes next
+        let result = list(None).await;
+        assert_eq!(result["items"], json!([1, 2, 3, 4]));
+        let result = list(Some(result[NEXT_PAGE].as_str().unwrap())).await;
+        assert_eq!(result["items"], json!([5, 6]));
+        assert_eq!(result["pages"], 2);
+        assert!(result.get(NEXT_PAGE).is_none());
+    }
+}
@@ -0,0 +1,366 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use openapiv3::Operation;
+use reqwest::{Client, RequestBuilder, Response, StatusCode, Url, header::LOCATION};
+use rmcp::model::{CallToolResult, Content};
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::warn;
+
+use super::{
+    limits::Permits,
+    progress::Progress,
+    response::{self, failed},
+    retry,
+};
+
+/// Spec extension enabling polling for an operation, either `true` or a
+/// [`Polling`] object.
+const EXTENSION: &str = "x-brwse-polling";
+
+/// How the status of an operation accepted for later processing is read.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Polling {
+    /// JSON pointer to the state in status documents, e.g. `/status`. When
+    /// unset, the status URL is polled until it stops answering `202` and
+    /// what it answers then is the result.
+    pub status: Option<String>,
+    /// States of operations that completed.
+    pub succeeded: Vec<String>,
+    /// States of operations that failed.
+    pub failed: Vec<String>,
+    /// JSON pointer to the URL of the resource a completed operation made,
+    /// e.g. `/resourceLocation`. The status document is the result when
+    /// unset.
+    pub result: Option<String>,
+}
+
+impl Default for Polling {
+    fn default() -> Self {
+        let states = |states: &[&str]| states.iter().map(ToString::to_string).collect();
+        Self {
+            status: None,
+            succeeded: states(&["succeeded", "completed", "done"]),
+            failed: states(&["failed", "canceled", "cancelled"]),
+            result: None,
+        }
+    }
+}
+
+/// Controls which operations answering `202 Accepted` are waited for, and
+/// for how long.
+///
+/// Operations opt in here or with the `x-brwse-polling` extension, e.g.
+/// `{status: "/status", result: "/resourceLocation"}`. Their tools poll the
+/// `Location` of the accepted response until the operation completes, and
+/// return the resource it made. Clients that sent a progress token are told
+/// of each poll.
+#[derive(Debug, Clone)]
+pub struct PollingOptions {
+    /// How to poll operations by tool name. An entry here is used instead
+    /// of the operation's extension.
+    pub operations: HashMap<String, Polling>,
+    /// Delay before the first poll, doubled for each one after it.
+    pub interval: Duration,
+    /// Upper bound of the delay between polls. `Retry-After` may ask for
+    /// longer.
+    pub max_interval: Duration,
+    /// Time to wait for an operation before giving up on it.
+    pub timeout: Duration,
+}
+
+impl Default for PollingOptions {
+    fn default() -> Self {
+        Self {
+            operations: HashMap::new(),
+            interval: Duration::from_secs(1),
+            max_interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(300),
+        }
+    }
+}
+
+impl PollingOptions {
+    /// Returns how the status of an operation is polled, if it is.
+    pub(super) fn get(&self, name: &str, operation: &Operation) -> Option<Polling> {
+        if let Some(polling) = self.operations.get(name) {
+            return Some(polling.clone());
+        }
+        match operation.extensions.get(EXTENSION)? {
+            Value::Bool(enabled) => enabled.then(Polling::default),
+            polling => serde_json::from_value(polling.clone()).ok(),
+        }
+    }
+}
+
+/// Polls the status URL of an `accepted` response with `fetch` until the
+/// operation completes, fails or times out.
+///
+/// Returns the final response to report, or the result to report when there
+/// is none. Status and result URLs are only followed within the origin of the
+/// request, so upstreams cannot send its credentials elsewhere.
+pub(super) async fn wait<F, Fut>(
+    polling: &Polling,
+    options: &PollingOptions,
+    accepted: Response,
+    client: &Client,
+    progress: Option<&Progress>,
+    fetch: F,
+) -> Result<Result<Response, CallToolResult>, rmcp::Error>
+where
+    F: Fn(RequestBuilder) -> Fut,
+    Fut: Future<Output = Result<reqwest::Result<Response>, rmcp::Error>>,
+{
+    let origin = accepted.url().clone();
+    let Some(mut url) = location(&accepted, &origin) else {
+        return Ok(Ok(accepted));
+    };
+    let deadline = Instant::now() + options.timeout;
+    let mut interval = options.interval;
+    let mut delay = retry::retry_after(&accepted).unwrap_or(interval);
+    loop {
+        if Instant::now() + delay > deadline {
+            let message = format!(
+                "Operation did not complete within {:?}, its status is at {url}",
+                options.timeout
+            );
+            return Ok(Err(CallToolResult::error(vec![Content::text(message)])));
+        }
+        tokio::time::sleep(delay).await;
+
+        let response = match fetch(client.get(url.clone())).await? {
+            Ok(response) => response,
+            Err(e) => return Ok(Err(failed(e))),
+        };
+        let retry_after = retry::retry_after(&response);
+        let state = match response.status() {
+            StatusCode::ACCEPTED => {
+                if let Some(next) = location(&response, &origin) {
+                    url = next;
+                }
+                None
+            }
+            // Status URLs redirecting elsewhere lead to the result
+            status if status.is_success() && polling.status.is_some() && *response.url() == url => {
+                match status_document(polling, response, &origin, client, &fetch).await? {
+                    Poll::Running(state) => Some(state),
+                    Poll::Done(response) => return Ok(Ok(response)),
+                    Poll::Report(result) => return Ok(Err(result)),
+                }
+            }
+            _ => return Ok(Ok(response)),
+        };
+
+        interval = (interval * 2).min(options.max_interval);
+        delay = retry_after.unwrap_or(interval);
+        if let Some(progress) = progress {
+            let state = state.as_deref().unwrap_or("pending");
+            progress.notify(format!("Operation {state}, checking again in {delay:?}")).await;
+        }
+    }
+}
+
+/// Where an operation stands according to its status document.
+enum Poll {
+    /// Still running, in the given state.
+    Running(String),
+    /// Completed, with the resource it made.
+    Done(Response),
+    /// Ended with the given result, or cannot be followed any further.
+    Report(CallToolResult),
+}
+
+/// Reads the state of an operation from its status document, fetching the
+/// resource of completed operations.
+async fn status_document<F, Fut>(
+    polling: &Polling,
+    mut response: Response,
+    origin: &Url,
+    client: &Client,
+    fetch: &F,
+) -> Result<Poll, rmcp::Error>
+where
+    F: Fn(RequestBuilder) -> Fut,
+    Fut: Future<Output = Result<reqwest::Result<Response>, rmcp::Error>>,
+{
+    let url = response.url().clone();
+    let _permits = Permits::take(&mut response);
+    let document = match response.json::<Value>().await {
+        Ok(document) => document,
+        Err(e) => return Ok(Poll::Report(failed(e))),
+    };
+    let pointer = polling.status.as_deref().unwrap_or_default();
+    let state = match document.pointer(pointer) {
+        Some(Value::String(state)) => state.clone(),
+        _ => {
+            let message = format!("Status document has no state at `{pointer}`");
+            return Ok(Poll::Report(CallToolResult::error(vec![Content::text(message)])));
+        }
+    };
+    let is = |states: &[String]| states.iter().any(|known| known.eq_ignore_ascii_case(&state));
+
+    if is(&polling.failed) {
+        return Ok(Poll::Report(CallToolResult::error(vec![response::json_content(document)?])));
+    }
+    if !is(&polling.succeeded) {
+        return Ok(Poll::Running(state));
+    }
+    let result = polling.result.as_deref().and_then(|pointer| document.pointer(pointer));
+    if let Some(Value::String(result)) = result {
+        match url.join(result) {
+            Ok(result) if result.origin() == origin.origin() => {
+                return match fetch(client.get(result)).await? {
+                    Ok(response) => Ok(Poll::Done(response)),
+                    Err(e) => Ok(Poll::Report(failed(e))),
+                };
+            }
+            _ => warn!("Not fetching result {} outside of {}", result, origin),
+        }
+    }
+    Ok(Poll::Report(CallToolResult::success(vec![response::json_content(document)?])))
+}
+
+/// Resolves the `Location` header of a response, if it is within `origin`.
+fn location(response: &Response, origin: &Url) -> Option<Url> {
+    let location = response.headers().get(LOCATION)?.to_str().ok()?;
+    let url = response.url().join(location).ok()?;
+    if url.origin() != origin.origin() {
+        warn!("Not polling {} outside of {}", url, origin);
+        return None;
+    }
+    Some(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path},
+    };
+
+    use super::*;
+
+    fn options(timeout: Duration) -> PollingOptions {
+        PollingOptions {
+            interval: Duration::from_millis(10),
+            max_interval: Duration::from_millis(10),
+            timeout,
+            ..Default::default()
+        }
+    }
+
+    fn polling() -> Polling {
+        Polling {
+            status: Some("/status".to_string()),
+            result: Some("/resourceLocation".to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Starts an operation at `/jobs`, accepted with `accepted`, and waits for
+    /// it.
+    async fn run(
+        mock_server: &MockServer,
+        accepted: ResponseTemplate,
+        polling: &Polling,
+        options: &PollingOptions,
+    ) -> Result<Response, CallToolResult> {
+        Mock::given(method("POST"))
+            .and(path("/jobs"))
+            .respond_with(accepted)
+            .mount(mock_server)
+            .await;
+        let client = Client::new();
+        let accepted = client.post(format!("{}/jobs", mock_server.uri())).send().await.unwrap();
+        let fetch = |request: RequestBuilder| async { Ok(request.send().await) };
+        wait(polling, options, accepted, &client, None, fetch).await.unwrap()
+    }
+
+    fn text(result: &CallToolResult) -> &str {
+        &result.content[0].as_text().unwrap().text
+    }
+
+    #[tokio::test]
+    async fn test_stays_within_origin() {
+        let (mock_server, elsewhere) = (MockServer::start().await, MockServer::start().await);
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&elsewhere)
+            .await;
+
+        // Status URLs elsewhere are not polled
+        let accepted = ResponseTemplate::new(202)
+            .insert_header("Location", format!("{}/jobs/1", elsewhere.uri()));
+        let options = options(Duration::from_secs(5));
+        let response = run(&mock_server, accepted, &polling(), &options).await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+        // Nor are results elsewhere fetched, the status document is reported
+        let mock_server = MockServer::start().await;
+        let file = format!("{}/files/1", elsewhere.uri());
+        let document = json!({"status": "succeeded", "resourceLocation": file});
+        Mock::given(method("GET"))
+            .and(path("/jobs/1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&document))
+            .mount(&mock_server)
+            .await;
+        let accepted = ResponseTemplate::new(202).insert_header("Location", "/jobs/1");
+        let result = run(&mock_server, accepted, &polling(), &options).await.unwrap_err();
+        assert_eq!(result.is_error, Some(false));
+        assert_eq!(serde_json::from_str::<Value>(text(&result)).unwrap(), document);
+    }
+
+    #[tokio::test]
+    async fn test_failed_operation() {
+        let mock_server = MockServer::start().await;
+        let document = json!({"status": "Cancelled", "reason": "quota"});
+        Mock::given(method("GET"))
+            .and(path("/jobs/1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&document))
+            .mount(&mock_server)
+            .await;
+
+        let accepted = ResponseTemplate::new(202).insert_header("Location", "/jobs/1");
+        let options = options(Duration::from_secs(5));
+        let result = run(&mock_server, accepted, &polling(), &options).await.unwrap_err();
+        assert_eq!(result.is_error, Some(true));
+        assert_eq!(serde_json::from_str::<Value>(text(&result)).unwrap(), document);
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/jobs/1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"status": "running"})))
+            .mount(&mock_server)
+            .await;
+
+        let accepted = ResponseTemplate::new(202).insert_header("Location", "/jobs/1");
+        let (polling, options) = (polling(), options(Duration::from_millis(100)));
+        let result = run(&mock_server, accepted, &polling, &options).await.unwrap_err();
+        let message = format!(
+            "Operation did not complete within 100ms, its status is at {}/jobs/1",
+            mock_server.uri()
+        );
+        assert_eq!(text(&result), message);
+
+        // Nor is a status polled after the deadline when asked to wait past it
+        let mock_server = MockServer::start().await;
+        let accepted = ResponseTemplate::new(202)
+            .insert_header("Location", "/jobs/1")
+            .insert_header("Retry-After", "60");
+        let options = self::options(Duration::from_secs(5));
+        let started = Instant::now();
+        let result = run(&mock_server, accepted, &polling, &options).await.unwrap_err();
+        assert!(text(&result).starts_with("Operation did not complete"));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,534 @@
+use std::str::FromStr;
+
+use serde_json::{Map, Value, json};
+
+use super::make_nullable;
+
+/// How tool input schemas are shaped for the clients consuming them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SchemaProfile {
+    /// A faithful translation of the spec's schemas.
+    #[default]
+    Full,
+    /// Closes every object with `additionalProperties: false` and requires all
+    /// of its properties, optional ones becoming nullable, as the structured
+    /// output modes of LLM providers demand. Null arguments are treated as
+    /// absent.
+    Strict,
+    /// Merges `allOf`, flattens `oneOf`/`anyOf`, drops `format` and collapses
+    /// nesting beyond [`SchemaOptions::max_depth`], for clients with limited
+    /// JSON Schema support.
+    Simplified,
+}
+
+impl FromStr for SchemaProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(Self::Full),
+            "strict" => Ok(Self::Strict),
+            "simplified" => Ok(Self::Simplified),
+            _ => Err(format!("unknown schema profile `{s}`, expected full, strict or simplified")),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SchemaOptions {
+    pub profile: SchemaProfile,
+    /// Depth beyond which the simplified profile collapses nested schemas to
+    /// their bare type.
+    pub max_depth: usize,
+    /// Emits each component schema once under `$defs` and references it,
+    /// rather than inlining every reference. Ignored by the simplified
+    /// profile, which flattens schemas anyway.
+    pub defs: bool,
+}
+
+impl Default for SchemaOptions {
+    fn default() -> Self {
+        Self { profile: SchemaProfile::Full, max_depth: 5, defs: false }
+    }
+}
+
+impl SchemaOptions {
+    pub(super) fn uses_defs(&self) -> bool {
+        self.defs && self.profile != SchemaProfile::Simplified
+    }
+
+    /// Reshapes a resolved input schema according to the profile.
+    pub(super) fn apply(&self, schema: &mut Value) {
+        match self.profile {
+            SchemaProfile::Full => {}
+            SchemaProfile::Strict => strict(schema),
+            SchemaProfile::Simplified => simplify(schema, 0, self.max_depth),
+        }
+    }
+}
+
+/// Calls `f` on each schema directly nested in `schema`.
+fn for_each_subschema(schema: &mut Value, f: &mut impl FnMut(&mut Value)) {
+    let Some(object) = schema.as_object_mut() else {
+        return;
+    };
+    for (keyword, value) in object.iter_mut() {
+        match (keyword.as_str(), value) {
+            ("properties" | "$defs", Value::Object(schemas)) => {
+                schemas.values_mut().for_each(&mut *f)
+            }
+            ("allOf" | "anyOf" | "oneOf", Value::Array(schemas)) => {
+                schemas.iter_mut().for_each(&mut *f)
+            }
+            ("items" | "additionalProperties" | "not", value) if value.is_object() => f(value),
+            _ => {}
+        }
+    }
+}
+
+fn strict(schema: &mut Value) {
+    // Closed objects cannot be combined, so merge `allOf` parts first. Parts
+    // that are references keep the object open.
+    if let Some(object) = schema.as_object_mut()
+        && let Some(Value::Array(parts)) = object.remove("allOf")
+    {
+        let (references, parts): (Vec<_>, Vec<_>) =
+            parts.into_iter().partition(|part| part.get("$ref").is_some());
+        for part in parts {
+            if let Value::Object(part) = part {
+                merge_all(object, part);
+            }
+        }
+        if !references.is_empty() {
+            object.insert("allOf".to_string(), Value::Array(references));
+        }
+    }
+    for_each_subschema(schema, &mut strict);
+    if schema.get("allOf").is_some() {
+        return;
+    }
+
+    let required = schema.get("required").and_then(Value::as_array).cloned().unwrap_or_default();
+    let Some(Value::Object(properties)) = schema.get_mut("properties") else {
+        return;
+    };
+    for (name, property) in properties.iter_mut() {
+        if !required.iter().any(|r| r == name) {
+            make_nullable(property);
+        }
+    }
+    let names = properties.keys().cloned().collect::<Vec<_>>();
+    schema["required"] = json!(names);
+    schema["additionalProperties"] = json!(false);
+}
+
+/// Drops the null members of objects, at any depth, that stand in for the
+/// optional properties the strict profile made nullable. `schema` is the
+/// input schema before the profile applied, so nulls that the spec allows
+/// are kept.
+pub(super) fn prune_nulls(value: &mut Value, schema: &Value) {
+    prune(value, schema, schema);
+}
+
+fn prune(value: &mut Value, schema: &Value, root: &Value) {
+    let schema = dereference(schema, root);
+    match value {
+        Value::Object(object) => {
+            for part in parts(schema, root) {
+                let Some(Value::Object(properties)) = part.get("properties") else {
+                    continue;
+                };
+                let required = part.get("required").and_then(Value::as_array);
+                for (name, property) in properties {
+                    let optional =
+                        !required.is_some_and(|required| required.contains(&json!(name)));
+                    match object.get_mut(name) {
+                        Some(Value::Null) if optional && !accepts_null(property, root) => {
+                            object.remove(name);
+                        }
+                        Some(member) => prune(member, property, root),
+                        None => {}
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(schema) = schema.get("items") {
+                items.iter_mut().for_each(|item| prune(item, schema, root));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Resolves a `$defs` reference against the root schema.
+fn dereference<'a>(schema: &'a Value, root: &'a Value) -> &'a Value {
+    let name = schema.get("$ref").and_then(Value::as_str).and_then(|r| r.strip_prefix("#/$defs/"));
+    name.and_then(|name| root.get("$defs")?.get(name)).unwrap_or(schema)
+}
+
+/// Returns a schema along with the parts it combines, which may each declare
+/// properties.
+fn parts<'a>(schema: &'a Value, root: &'a Value) -> Vec<&'a Value> {
+    let mut parts = vec![schema];
+    for keyword in ["allOf", "anyOf", "oneOf"] {
+        for part in schema.get(keyword).and_then(Value::as_array).into_iter().flatten() {
+            parts.extend(self::parts(dereference(part, root), root));
+        }
+    }
+    parts
+}
+
+fn accepts_null(schema: &Value, root: &Value) -> bool {
+    let schema = dereference(schema, root);
+    let variants = schema.get("anyOf").or_else(|| schema.get("oneOf"));
+    if let Some(Value::Array(variants)) = variants {
+        return variants.iter().any(|variant| accepts_null(variant, root));
+    }
+    match (schema.get("type"), schema.get("const"), schema.get("enum")) {
+        (Some(Value::Array(types)), _, _) => types.contains(&json!("null")),
+        (Some(type_name), _, _) => type_name == "null",
+        (None, Some(value), _) => value.is_null(),
+        (None, None, Some(Value::Array(values))) => values.contains(&Value::Null),
+        _ => schema.get("allOf").is_none(),
+    }
+}
+
+fn simplify(schema: &mut Value, depth: usize, max_depth: usize) {
+    let Some(object) = schema.as_object_mut() else {
+        return;
+    };
+    object.remove("format");
+
+    if let Some(Value::Array(parts)) = object.remove("allOf") {
+        for mut part in parts {
+            simplify(&mut part, depth, max_depth);
+            if let Value::Object(part) = part {
+                merge_all(object, part);
+            }
+        }
+    }
+    for keyword in ["oneOf", "anyOf"] {
+        if let Some(Value::Array(variants)) = object.remove(keyword) {
+            let mut merged: Option<Map<String, Value>> = None;
+            for mut variant in variants {
+                simplify(&mut variant, depth, max_depth);
+                if let Value::Object(variant) = variant {
+                    match &mut merged {
+                        Some(merged) => merge_any(merged, variant),
+                        None => merged = Some(variant),
+                    }
+                }
+            }
+            merge_all(object, merged.unwrap_or_default());
+        }
+    }
+
+    if depth >= max_depth {
+        for keyword in ["properties", "required", "items", "additionalProperties", "not"] {
+            object.remove(keyword);
+        }
+        return;
+    }
+    for_each_subschema(schema, &mut |subschema| simplify(subschema, depth + 1, max_depth));
+}
+
+/// Merges a schema that must also hold into `target`.
+fn merge_all(target: &mut Map<String, Value>, source: Map<String, Value>) {
+    for (keyword, value) in source {
+        match (keyword.as_str(), target.get_mut(&keyword), value) {
+            ("properties", Some(Value::Object(properties)), Value::Object(more)) => {
+                for (name, property) in more {
+                    properties.entry(name).or_insert(property);
+                }
+            }
+            ("required", Some(Value::Array(required)), Value::Array(more)) => {
+                for name in more {
+                    if !required.contains(&name) {
+                        required.push(name);
+                    }
+                }
+            }
+            (_, Some(_), _) => {}
+            (_, None, value) => {
+                target.insert(keyword, value);
+            }
+        }
+    }
+}
+
+/// Merges an alternative schema into `target`, loosening it to accept either.
+fn merge_any(target: &mut Map<String, Value>, mut source: Map<String, Value>) {
+    match (target.get("type").cloned(), source.get("type")) {
+        (Some(a), Some(b)) if a != *b => {
+            let mut types = Vec::new();
+            for t in [a, b.clone()].into_iter().flat_map(|t| match t {
+                Value::Array(items) => items,
+                t => vec![t],
+            }) {
+                if !types.contains(&t) {
+                    types.push(t);
+                }
+            }
+            target.insert("type".to_string(), json!(types));
+        }
+        (Some(_), None) => {
+            target.remove("type");
+        }
+        _ => {}
+    }
+
+    // Only properties required by every alternative stay required.
+    let required = source.remove("required");
+    match (target.get_mut("required"), required) {
+        (Some(Value::Array(names)), Some(Value::Array(more))) => names.retain(|n| more.contains(n)),
+        (Some(_), _) => {
+            target.remove("required");
+        }
+        _ => {}
+    }
+
+    if let Some(Value::Object(more)) = source.remove("properties") {
+        let properties = target.entry("properties").or_insert_with(|| json!({}));
+        if let Value::Object(properties) = properties {
+            for (name, property) in more {
+                match properties.get_mut(&name) {
+                    Some(existing) => merge_values(existing, &property),
+                    None => {
+                        properties.insert(name, property);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Combines the allowed values of two alternatives for the same property,
+/// e.g. the discriminator values pinned on each variant.
+fn merge_values(target: &mut Value, source: &Value) {
+    let values = |schema: &Value| match (schema.get("const"), schema.get("enum")) {
+        (Some(value), _) => Some(vec![value.clone()]),
+        (_, Some(Value::Array(values))) => Some(values.clone()),
+        _ => None,
+    };
+    let Some(object) = target.as_object_mut() else {
+        return;
+    };
+    match (values(&Value::Object(object.clone())), values(source)) {
+        (Some(mut all), Some(more)) => {
+            for value in more {
+                if !all.contains(&value) {
+                    all.push(value);
+                }
+            }
+            object.remove("const");
+            object.insert("enum".to_string(), json!(all));
+        }
+        // One alternative leaves the property unconstrained.
+        (Some(_), None) | (None, Some(_)) => {
+            object.remove("const");
+            object.remove("enum");
+        }
+        (None, None) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_json_snapshot;
+
+    use super::*;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "id": {"type": "string", "format": "uuid"},
+                "pet": {
+                    "oneOf": [
+                        {
+                            "type": "object",
+                            "properties": {
+                                "kind": {"const": "cat"},
+                                "lives": {"type": "integer"},
+                            },
+                            "required": ["kind", "lives"],
+                        },
+                        {
+                            "allOf": [
+                                {"type": "object", "properties": {"kind": {"const": "dog"}}},
+                                {"properties": {"owner": {
+                                    "type": "object",
+                                    "properties": {"address": {
+                                        "type": "object",
+                                        "properties": {"city": {"type": "string"}},
+                                    }},
+                                }}, "required": ["kind"]},
+                            ],
+                        },
+                    ],
+                },
+            },
+            "required": ["pet"],
+        })
+    }
+
+    #[test]
+    fn test_strict_profile() {
+        let mut schema = schema();
+        SchemaOptions { profile: SchemaProfile::Strict, ..Default::default() }.apply(&mut schema);
+
+        assert_json_snapshot!(schema, @r###"
+        {
+          "additionalProperties": false,
+          "properties": {
+            "id": {
+              "format": "uuid",
+              "type": [
+                "string",
+                "null"
+              ]
+            },
+            "pet": {
+              "oneOf": [
+                {
+                  "additionalProperties": false,
+                  "properties": {
+                    "kind": {
+                      "const": "cat"
+                    },
+                    "lives": {
+                      "type": "integer"
+                    }
+                  },
+                  "required": [
+                    "kind",
+                    "lives"
+                  ],
+                  "type": "object"
+                },
+                {
+                  "additionalProperties": false,
+                  "properties": {
+                    "kind": {
+                      "const": "dog"
+                    },
+                    "owner": {
+                      "additionalProperties": false,
+                      "properties": {
+                        "address": {
+                          "additionalProperties": false,
+                          "properties": {
+                            "city": {
+                              "type": [
+                                "string",
+                                "null"
+                              ]
+                            }
+                          },
+                          "required": [
+                            "city"
+                          ],
+                          "type": [
+                            "object",
+                            "null"
+                          ]
+                        }
+                      },
+                      "required": [
+                        "address"
+                      ],
+                      "type": [
+                        "object",
+                        "null"
+                      ]
+                    }
+                  },
+                  "required": [
+                    "kind",
+                    "owner"
+                  ],
+                  "type": "object"
+                }
+              ]
+            }
+          },
+          "required": [
+            "id",
+            "pet"
+          ],
+          "type": "object"
+        }
+        "###);
+    }
+
+    #[test]
+    fn test_simplified_profile() {
+        let mut schema = schema();
+        SchemaOptions { profile: SchemaProfile::Simplified, max_depth: 3, ..Default::default() }
+            .apply(&mut schema);
+
+        assert_json_snapshot!(schema, @r###"
+        {
+          "properties": {
+            "id": {
+              "type": "string"
+            },
+            "pet": {
+              "properties": {
+                "kind": {
+                  "enum": [
+                    "cat",
+                    "dog"
+                  ]
+                },
+                "lives": {
+                  "type": "integer"
+                },
+                "owner": {
+                  "properties": {
+                    "address": {
+                      "type": "object"
+                    }
+                  },
+                  "type": "object"
+                }
+              },
+              "required": [
+                "kind"
+              ],
+              "type": "object"
+            }
+          },
+          "required": [
+            "pet"
+          ],
+          "type": "object"
+        }
+        "###);
+    }
+
+    #[test]
+    fn test_prune_nulls() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "a": {"type": "string"},
+                "b": {"$ref": "#/$defs/B"},
+                "e": {"type": "array", "items": {"properties": {"f": {"type": "integer"}}}},
+                "g": {"type": ["string", "null"]},
+            },
+            "required": ["a"],
+            "$defs": {"B": {"properties": {"c": {"type": "string"}, "d": {"type": "integer"}}}},
+        });
+        let mut args = json!({
+            "a": null,
+            "b": {"c": null, "d": 1},
+            "e": [{"f": null}],
+            "g": null,
+        });
+        prune_nulls(&mut args, &schema);
+        // Nulls the spec allows, or that the profile cannot have introduced, are kept
+        assert_eq!(args, json!({"a": null, "b": {"d": 1}, "e": [{}], "g": null}));
+    }
+}
@@ -0,0 +1,151 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use rmcp::{
+    Peer, RoleServer,
+    model::{ProgressNotificationParam, ProgressToken},
+};
+use tracing::warn;
+
+const EVENT_STREAM: &str = "text/event-stream";
+
+/// Reports the progress of a tool call to the client that asked for it with
+/// a progress token.
+pub(super) struct Progress {
+    peer: Peer<RoleServer>,
+    token: ProgressToken,
+    sent: AtomicU32,
+}
+
+impl Progress {
+    pub fn new(peer: Peer<RoleServer>, token: ProgressToken) -> Self {
+        Self { peer, token, sent: AtomicU32::new(0) }
+    }
+
+    /// Sends a notification, counting up from the ones sent before.
+    pub async fn notify(&self, message: String) {
+        let progress = self.sent.fetch_add(1, Ordering::Relaxed) + 1;
+        let param = ProgressNotificationParam {
+            progress_token: self.token.clone(),
+            progress,
+            total: None,
+            message: Some(message),
+        };
+        if let Err(e) = self.peer.notify_progress(param).await {
+            warn!("Failed to send progress notification: {}", e);
+        }
+    }
+}
+
+/// Reads the body of a response, forwarding it to `progress` as it arrives
+/// when it is streamed: server-sent events one event at a time, and other
+/// bodies of unknown length one chunk at a time.
+///
+/// The whole body is returned once the upstream ends it, or as much of it as
+/// was received along with the error that cut it short.
+pub(super) async fn read_body(
+    mut response: reqwest::Response,
+    media_type: Option<&str>,
+    textual: bool,
+    progress: Option<&Progress>,
+) -> (Vec<u8>, Option<reqwest::Error>) {
+    let events = media_type == Some(EVENT_STREAM);
+    let progress = progress.filter(|_| events || response.content_length().is_none());
+
+    let mut body = Vec::new();
+    let mut pending = Vec::new();
+    loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => return (body, None),
+            Err(e) => return (body, Some(e)),
+        };
+        body.extend_from_slice(&chunk);
+        let Some(progress) = progress else {
+            continue;
+        };
+        if events {
+            pending.extend_from_slice(&chunk);
+            while let Some(event) = next_event(&mut pending) {
+                if let Some(data) = event_data(&event) {
+                    progress.notify(data).await;
+                }
+            }
+        } else if textual {
+            pending.extend_from_slice(&chunk);
+            let text = next_text(&mut pending);
+            if !text.is_empty() {
+                progress.notify(text).await;
+            }
+        } else {
+            progress.notify(format!("{} bytes received", body.len())).await;
+        }
+    }
+}
+
+/// Takes the text off `pending`, leaving a character cut off at its end for
+/// the next chunk to complete.
+fn next_text(pending: &mut Vec<u8>) -> String {
+    let end = match std::str::from_utf8(pending) {
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        _ => pending.len(),
+    };
+    let text = String::from_utf8_lossy(&pending[..end]).into_owned();
+    pending.drain(..end);
+    text
+}
+
+/// Takes the first complete event, ended by a blank line, off `pending`.
+fn next_event(pending: &mut Vec<u8>) -> Option<String> {
+    let (end, separator) = [&b"\r\n\r\n"[..], b"\n\n", b"\r\r"]
+        .into_iter()
+        .filter_map(|separator| {
+            let end = pending.windows(separator.len()).position(|window| window == separator)?;
+            Some((end, separator.len()))
+        })
+        .min()?;
+    let event = String::from_utf8_lossy(&pending[..end]).into_owned();
+    pending.drain(..end + separator);
+    Some(event)
+}
+
+/// Joins the `data` fields of an event. Events without data, such as
+/// keep-alive comments, are skipped.
+fn event_data(event: &str) -> Option<String> {
+    let data = event
+        .lines()
+        .filter_map(|line| line.strip_prefix("data"))
+        .filter_map(|value| match value.strip_prefix(':') {
+            Some(value) => Some(value.strip_prefix(' ').unwrap_or(value)),
+            None => value.is_empty().then_some(""),
+        })
+        .collect::<Vec<_>>();
+    (!data.is_empty()).then(|| data.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events() {
+        let mut pending =
+            b"data: one\r\n\r\n: ping\n\nevent: update\ndata: two\ndata:three\n\ndata: fo".to_vec();
+        let events = std::iter::from_fn(|| next_event(&mut pending)).collect::<Vec<_>>();
+        let data = events.iter().map(|event| event_data(event)).collect::<Vec<_>>();
+        assert_eq!(data, [Some("one".to_string()), None, Some("two\nthree".to_string())]);
+        assert_eq!(pending, b"data: fo");
+    }
+
+    #[test]
+    fn test_text() {
+        let text = "naïve café";
+        let mut pending = text.as_bytes()[..3].to_vec();
+        assert_eq!(next_text(&mut pending), "na");
+        pending.extend_from_slice(&text.as_bytes()[3..]);
+        assert_eq!(next_text(&mut pending), "ïve café");
+        assert!(pending.is_empty());
+
+        let mut pending = b"ok \xff".to_vec();
+        assert_eq!(next_text(&mut pending), "ok \u{fffd}");
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::HashSet;
+
+use indexmap::IndexMap;
+use openapiv3::{
+    Components, OpenAPI, Operation, Parameter, ReferenceOr, RequestBody, Response, SecurityScheme,
+};
+
+/// A reusable object that can be referenced from `#/components/...`.
+pub trait Component: Sized {
+    /// The JSON pointer prefix under which components of this kind live.
+    const PREFIX: &'static str;
+
+    fn components(components: &Components) -> &IndexMap<String, ReferenceOr<Self>>;
+}
+
+impl Component for Parameter {
+    const PREFIX: &'static str = "#/components/parameters/";
+
+    fn components(components: &Components) -> &IndexMap<String, ReferenceOr<Self>> {
+        &components.parameters
+    }
+}
+
+impl Component for RequestBody {
+    const PREFIX: &'static str = "#/components/requestBodies/";
+
+    fn components(components: &Components) -> &IndexMap<String, ReferenceOr<Self>> {
+        &components.request_bodies
+    }
+}
+
+impl Component for Response {
+    const PREFIX: &'static str = "#/components/responses/";
+
+    fn components(components: &Components) -> &IndexMap<String, ReferenceOr<Self>> {
+        &components.responses
+    }
+}
+
+impl Component for SecurityScheme {
+    const PREFIX: &'static str = "#/components/securitySchemes/";
+
+    fn components(components: &Components) -> &IndexMap<String, ReferenceOr<Self>> {
+        &components.security_schemes
+    }
+}
+
+/// Resolves a possibly referenced component, following chains of references.
+///
+/// Returns `None` for references that point outside of the spec's components
+/// or that loop back onto themselves.
+pub fn resolve<'a, T: Component>(item: &'a ReferenceOr<T>, spec: &'a OpenAPI) -> Option<&'a T> {
+    let mut visited = HashSet::new();
+    let mut current = item;
+    loop {
+        match current {
+            ReferenceOr::Item(item) => return Some(item),
+            ReferenceOr::Reference { reference } => {
+                if !visited.insert(reference.as_str()) {
+                    return None;
+                }
+                let name = reference.strip_prefix(T::PREFIX)?;
+                current = T::components(spec.components.as_ref()?).get(name)?;
+            }
+        }
+    }
+}
+
+/// Returns the value of the `in` field of a parameter.
+pub fn location(parameter: &Parameter) -> &'static str {
+    match parameter {
+        Parameter::Query { .. } => "query",
+        Parameter::Header { .. } => "header",
+        Parameter::Path { .. } => "path",
+        Parameter::Cookie { .. } => "cookie",
+    }
+}
+
+/// Collects the parameters that apply to an operation.
+///
+/// Path-level parameters come first. An operation-level parameter with the
+/// same name and location overrides the path-level one in place, as the
+/// OpenAPI specification requires. Header names match in any case.
+pub fn operation_parameters<'a>(
+    path_parameters: &'a [ReferenceOr<Parameter>],
+    operation: &'a Operation,
+    spec: &'a OpenAPI,
+) -> Vec<&'a Parameter> {
+    let mut parameters: Vec<&Parameter> =
+        path_parameters.iter().filter_map(|p| resolve(p, spec)).collect();
+
+    for parameter in operation.parameters.iter().filter_map(|p| resolve(p, spec)) {
+        let name = &parameter.parameter_data_ref().name;
+        let existing = parameters.iter_mut().find(|existing| {
+            let existing_name = &existing.parameter_data_ref().name;
+            location(existing) == location(parameter)
+                && match parameter {
+                    Parameter::Header { .. } => existing_name.eq_ignore_ascii_case(name),
+                    _ => existing_name == name,
+                }
+        });
+        match existing {
+            Some(existing) => *existing = parameter,
+            None => parameters.push(parameter),
+        }
+    }
+
+    parameters
+}
+
+#[cfg(test)]
+mod tests {
+    use openapiv3::*;
+
+    use super::*;
+
+    fn query(name: &str, description: &str) -> Parameter {
+        Parameter::Query {
+            parameter_data: ParameterData {
+                name: name.to_string(),
+                description: Some(description.to_string()),
+                required: false,
+                deprecated: None,
+                format: ParameterSchemaOrContent::Schema(ReferenceOr::Item(Schema {
+                    schema_data: SchemaData::default(),
+                    schema_kind: SchemaKind::Type(Type::String(StringType::default())),
+                })),
+                example: None,
+                examples: IndexMap::new(),
+                explode: None,
+                extensions: IndexMap::new(),
+            },
+            style: QueryStyle::Form,
+            allow_reserved: false,
+            allow_empty_value: None,
+        }
+    }
+
+    fn spec_with_parameters(parameters: Vec<(&str, ReferenceOr<Parameter>)>) -> OpenAPI {
+        OpenAPI {
+            components: Some(Components {
+                parameters: parameters.into_iter().map(|(k, v)| (k.to_string(), v)).collect(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_resolve_chained_reference() {
+        let spec = spec_with_parameters(vec![
+            ("Limit", ReferenceOr::Item(query("limit", "page size"))),
+            ("PageSize", ReferenceOr::ref_("#/components/parameters/Limit")),
+        ]);
+
+        let reference = ReferenceOr::<Parameter>::ref_("#/components/parameters/PageSize");
+        let resolved = resolve(&reference, &spec);
+        assert_eq!(resolved.map(|p| p.parameter_data_ref().name.as_str()), Some("limit"));
+    }
+
+    #[test]
+    fn test_resolve_missing_and_circular_references() {
+        let spec = spec_with_parameters(vec![
+            ("A", ReferenceOr::ref_("#/components/parameters/B")),
+            ("B", ReferenceOr::ref_("#/components/parameters/A")),
+        ]);
+
+        assert!(
+            resolve(&ReferenceOr::<Parameter>::ref_("#/components/parameters/A"), &spec).is_none()
+        );
+        assert!(
+            resolve(&ReferenceOr::<Parameter>::ref_("#/components/parameters/Nope"), &spec)
+                .is_none()
+        );
+        assert!(
+            resolve(&ReferenceOr::<Parameter>::ref_("#/components/schemas/Limit"), &spec).is_none()
+        );
+    }
+
+    #[test]
+    fn test_operation_parameters_override_path_level() {
+        let spec = spec_with_parameters(vec![(
+            "Filter",
+            ReferenceOr::Item(query("filter", "from component")),
+        )]);
+        let path_parameters = vec![
+            ReferenceOr::Item(query("limit", "from path")),
+            ReferenceOr::ref_("#/components/parameters/Filter"),
+        ];
+        let operation = Operation {
+            parameters: vec![
+                ReferenceOr::Item(query("limit", "from operation")),
+                ReferenceOr::Item(query("offset", "from operation")),
+            ],
+            ..Default::default()
+        };
+
+        let parameters = operation_parameters(&path_parameters, &operation, &spec)
+            .into_iter()
+            .map(|p| {
+                let data = p.parameter_data_ref();
+                (data.name.as_str(), data.description.as_deref().unwrap_or_default())
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            parameters,
+            vec![
+                ("limit", "from operation"),
+                ("filter", "from component"),
+                ("offset", "from operation"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_header_overrides_ignore_case() {
+        let header = |name: &str, description: &str| -> ReferenceOr<Parameter> {
+            serde_json::from_value(serde_json::json!({
+                "in": "header",
+                "name": name,
+                "description": description,
+                "schema": {"type": "string"},
+            }))
+            .unwrap()
+        };
+        let path_parameters = vec![header("X-Request-Id", "from path")];
+        let operation = Operation {
+            parameters: vec![header("x-request-id", "from operation")],
+            ..Default::default()
+        };
+
+        let spec = OpenAPI::default();
+        let parameters = operation_parameters(&path_parameters, &operation, &spec);
+        assert_eq!(parameters.len(), 1);
+        let data = parameters[0].parameter_data_ref();
+        assert_eq!(data.description.as_deref(), Some("from operation"));
+    }
+}
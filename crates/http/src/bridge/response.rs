@@ -0,0 +1,421 @@
+use base64::{Engine as _, prelude::BASE64_STANDARD};
+use jsonschema::Validator;
+use openapiv3::{OpenAPI, Operation, StatusCode};
+use rmcp::model::{CallToolResult, Content, ResourceContents};
+use serde_json::{Map, Value, json};
+
+use super::{
+    progress::{self, Progress},
+    reference, remove_flagged_properties, resolve_schema,
+};
+
+/// Controls how upstream responses are returned to agents.
+#[derive(Debug, Clone, Default)]
+pub struct ResponseOptions {
+    /// Prepend a JSON envelope carrying the status code and selected headers.
+    pub envelope: bool,
+    /// Response headers to copy into the envelope, matched case-insensitively.
+    pub headers: Vec<String>,
+    /// Check JSON bodies of successful responses against the operation's
+    /// response schema, noting mismatches in the result.
+    pub validate: bool,
+}
+
+/// Derives the schema of an operation's JSON result from its first 2xx
+/// response.
+///
+/// Publishing it as the `outputSchema` of tools, with results returned as
+/// `structuredContent`, needs an MCP SDK that models both. The pinned `rmcp`
+/// does not, so the schema only validates responses until it is upgraded.
+pub fn schema(operation: &Operation, spec: &OpenAPI) -> Option<Value> {
+    let (_, response) = operation.responses.responses.iter().find(|(status, _)| match status {
+        StatusCode::Code(code) => (200..300).contains(code),
+        StatusCode::Range(range) => *range == 2,
+    })?;
+    let response = reference::resolve(response, spec)?;
+    let media = response.content.iter().find_map(|(media_type, media)| {
+        let media_type = media_type.split(';').next().unwrap_or_default().trim();
+        is_json(&media_type.to_ascii_lowercase()).then_some(media)
+    })?;
+
+    // Write-only properties are accepted by the server but never returned.
+    let mut schema = resolve_schema(media.schema.as_ref()?, spec);
+    remove_flagged_properties(&mut schema, "writeOnly");
+    Some(schema)
+}
+
+/// Maps an upstream response to a tool result based on its `Content-Type`.
+///
+/// Non-2xx statuses are reported as tool errors, with the response body as
+/// content so agents can see why the call failed. Streamed bodies are
+/// forwarded to `progress` while they are read. Bodies cut short are reported
+/// as errors, with what was received of them.
+pub async fn into_result(
+    response: reqwest::Response,
+    options: &ResponseOptions,
+    schema: Option<&Validator>,
+    progress: Option<&Progress>,
+) -> Result<CallToolResult, rmcp::Error> {
+    let status = response.status();
+    let url = response.url().to_string();
+    let headers = response.headers().clone();
+    let media_type = headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.split(';').next().unwrap_or_default().trim().to_ascii_lowercase());
+
+    let textual =
+        media_type.as_deref().is_none_or(|media_type| is_json(media_type) || is_text(media_type));
+    let (body, error) =
+        progress::read_body(response, media_type.as_deref(), textual, progress).await;
+    let error = match error {
+        Some(e) if body.is_empty() => return Ok(failed(e)),
+        error => error,
+    };
+
+    let mut content = Vec::new();
+    if options.envelope {
+        let selected = options
+            .headers
+            .iter()
+            .filter_map(|name| {
+                let value = headers.get(name.as_str())?.to_str().ok()?;
+                Some((name.to_ascii_lowercase(), json!(value)))
+            })
+            .collect::<Map<_, _>>();
+        content.push(json_content(json!({
+            "status": status.as_u16(),
+            "headers": selected,
+        }))?);
+    }
+
+    if !body.is_empty() {
+        content.push(body_content(&url, media_type.as_deref(), body.clone())?);
+        if options.validate
+            && error.is_none()
+            && status.is_success()
+            && let Some(schema) = schema
+            && media_type.as_deref().is_some_and(is_json)
+            && let Ok(value) = serde_json::from_slice::<Value>(&body)
+            && let Err(err) = schema.validate(&value)
+        {
+            content.push(Content::text(format!(
+                "Response does not match the operation's response schema: {err}"
+            )));
+        }
+    } else if !options.envelope {
+        content.push(json_content(json!({
+            "status": status.as_u16(),
+        }))?);
+    }
+
+    if let Some(e) = error {
+        content.push(Content::text(format!("Response truncated: {e}")));
+        return Ok(CallToolResult::error(content));
+    }
+    if status.is_success() {
+        Ok(CallToolResult::success(content))
+    } else {
+        Ok(CallToolResult::error(content))
+    }
+}
+
+fn body_content(
+    url: &str,
+    media_type: Option<&str>,
+    body: Vec<u8>,
+) -> Result<Content, rmcp::Error> {
+    match media_type {
+        Some(media_type) if is_json(media_type) => {
+            match serde_json::from_slice::<Value>(&body) {
+                Ok(value) => json_content(value),
+                // Fall back to the raw text so agents can still see the payload.
+                Err(_) => Ok(text_or_blob(url, Some(media_type), body)),
+            }
+        }
+        Some(media_type) if media_type.starts_with("image/") => {
+            Ok(Content::image(BASE64_STANDARD.encode(body), media_type))
+        }
+        Some(media_type) if is_text(media_type) => Ok(text_or_blob(url, Some(media_type), body)),
+        Some(media_type) => Ok(blob(url, Some(media_type), &body)),
+        None => Ok(text_or_blob(url, None, body)),
+    }
+}
+
+fn is_json(media_type: &str) -> bool {
+    media_type == "application/json" || media_type.ends_with("+json")
+}
+
+fn is_text(media_type: &str) -> bool {
+    media_type.starts_with("text/")
+        || media_type.ends_with("+xml")
+        || matches!(
+            media_type,
+            "application/xml"
+                | "application/javascript"
+                | "application/x-www-form-urlencoded"
+                | "application/yaml"
+        )
+}
+
+fn text_or_blob(url: &str, media_type: Option<&str>, body: Vec<u8>) -> Content {
+    match String::from_utf8(body) {
+        Ok(text) => Content::text(text),
+        Err(e) => blob(url, media_type, e.as_bytes()),
+    }
+}
+
+fn blob(url: &str, media_type: Option<&str>, body: &[u8]) -> Content {
+    Content::resource(ResourceContents::BlobResourceContents {
+        uri: url.to_string(),
+        mime_type: Some(media_type.unwrap_or("application/octet-stream").to_string()),
+        blob: BASE64_STANDARD.encode(body),
+    })
+}
+
+/// Reports an upstream call that got no response, or whose body could not be
+/// read.
+pub(super) fn failed(e: reqwest::Error) -> CallToolResult {
+    CallToolResult::error(vec![Content::text(format!("HTTP request failed: {e}"))])
+}
+
+pub(super) fn json_content(value: Value) -> Result<Content, rmcp::Error> {
+    Content::json(value).map_err(|e| {
+        rmcp::Error::internal_error(format!("failed to create JSON content: {e}"), None)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_json_snapshot;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path},
+    };
+
+    use super::*;
+
+    async fn respond(template: ResponseTemplate, options: &ResponseOptions) -> CallToolResult {
+        respond_with_schema(template, options, None).await
+    }
+
+    async fn respond_with_schema(
+        template: ResponseTemplate,
+        options: &ResponseOptions,
+        schema: Option<&Validator>,
+    ) -> CallToolResult {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/resource"))
+            .respond_with(template)
+            .mount(&mock_server)
+            .await;
+
+        let response =
+            reqwest::get(format!("{}/resource", mock_server.uri())).await.expect("request failed");
+        into_result(response, options, schema, None).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_json_response() {
+        let result = respond(
+            ResponseTemplate::new(200).set_body_json(json!({"id": 1})),
+            &ResponseOptions::default(),
+        )
+        .await;
+
+        assert_json_snapshot!(result, @r###"
+        {
+          "content": [
+            {
+              "type": "text",
+              "text": "{\"id\":1}"
+            }
+          ],
+          "isError": false
+        }
+        "###);
+    }
+
+    #[tokio::test]
+    async fn test_image_response() {
+        let result = respond(
+            ResponseTemplate::new(200).set_body_raw(vec![0x89, b'P', b'N', b'G'], "image/png"),
+            &ResponseOptions::default(),
+        )
+        .await;
+
+        assert_json_snapshot!(result, @r###"
+        {
+          "content": [
+            {
+              "type": "image",
+              "data": "iVBORw==",
+              "mimeType": "image/png"
+            }
+          ],
+          "isError": false
+        }
+        "###);
+    }
+
+    #[tokio::test]
+    async fn test_binary_response_is_embedded_blob() {
+        let result = respond(
+            ResponseTemplate::new(200).set_body_raw(b"%PDF-1.7".to_vec(), "application/pdf"),
+            &ResponseOptions::default(),
+        )
+        .await;
+
+        let content = result.content[0].as_resource().expect("expected an embedded resource");
+        let ResourceContents::BlobResourceContents { uri, mime_type, blob } = &content.resource
+        else {
+            panic!("expected a blob resource");
+        };
+        assert!(uri.ends_with("/resource"));
+        assert_eq!(mime_type.as_deref(), Some("application/pdf"));
+        assert_eq!(BASE64_STANDARD.decode(blob).unwrap(), b"%PDF-1.7");
+    }
+
+    #[tokio::test]
+    async fn test_error_status_with_envelope() {
+        let result = respond(
+            ResponseTemplate::new(429)
+                .insert_header("Retry-After", "30")
+                .insert_header("X-Request-Id", "abc")
+                .set_body_string("slow down"),
+            &ResponseOptions {
+                envelope: true,
+                headers: vec!["retry-after".to_string(), "x-missing".to_string()],
+                ..Default::default()
+            },
+        )
+        .await;
+
+        assert_json_snapshot!(result, @r###"
+        {
+          "content": [
+            {
+              "type": "text",
+              "text": "{\"headers\":{\"retry-after\":\"30\"},\"status\":429}"
+            },
+            {
+              "type": "text",
+              "text": "slow down"
+            }
+          ],
+          "isError": true
+        }
+        "###);
+    }
+
+    #[tokio::test]
+    async fn test_empty_response() {
+        let result = respond(ResponseTemplate::new(204), &ResponseOptions::default()).await;
+
+        assert_json_snapshot!(result, @r###"
+        {
+          "content": [
+            {
+              "type": "text",
+              "text": "{\"status\":204}"
+            }
+          ],
+          "isError": false
+        }
+        "###);
+    }
+
+    #[tokio::test]
+    async fn test_truncated_response() {
+        use tokio::{
+            io::{AsyncReadExt, AsyncWriteExt},
+            net::TcpListener,
+        };
+
+        // An upstream that closes the connection halfway through the body
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/resource", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let _ = socket.read(&mut [0; 1024]).await.unwrap();
+            let response = "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\
+                            Content-Length: 10\r\n\r\nhello";
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let response = reqwest::get(url).await.unwrap();
+        let result = into_result(response, &ResponseOptions::default(), None, None).await.unwrap();
+        assert_eq!(result.is_error, Some(true));
+        assert_eq!(result.content[0].as_text().unwrap().text, "hello");
+        assert!(result.content[1].as_text().unwrap().text.starts_with("Response truncated: "));
+    }
+
+    #[test]
+    fn test_response_schema() {
+        let spec: OpenAPI = serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Test API", "version": "1.0.0"},
+            "paths": {},
+            "components": {
+                "responses": {
+                    "User": {
+                        "description": "A user",
+                        "content": {"application/json; charset=utf-8": {"schema": {
+                            "type": "object",
+                            "properties": {
+                                "name": {"type": "string"},
+                                "password": {"type": "string", "writeOnly": true},
+                            },
+                            "required": ["name", "password"],
+                        }}},
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        let operation: Operation = serde_json::from_value(json!({
+            "responses": {
+                "default": {"description": "Error", "content": {"application/json": {}}},
+                "201": {"$ref": "#/components/responses/User"},
+            }
+        }))
+        .unwrap();
+
+        assert_json_snapshot!(schema(&operation, &spec).unwrap(), @r###"
+        {
+          "properties": {
+            "name": {
+              "type": "string"
+            }
+          },
+          "required": [
+            "name"
+          ],
+          "type": "object"
+        }
+        "###);
+    }
+
+    #[tokio::test]
+    async fn test_response_validation() {
+        let validator = jsonschema::validator_for(&json!({
+            "type": "object",
+            "properties": {"id": {"type": "integer"}},
+        }))
+        .unwrap();
+        let options = ResponseOptions { validate: true, ..Default::default() };
+
+        let template = ResponseTemplate::new(200).set_body_json(json!({"id": 1}));
+        let result = respond_with_schema(template, &options, Some(&validator)).await;
+        assert_eq!(result.content.len(), 1);
+
+        let template = ResponseTemplate::new(200).set_body_json(json!({"id": "1"}));
+        let result = respond_with_schema(template, &options, Some(&validator)).await;
+        assert_eq!(result.is_error, Some(false));
+        assert_eq!(
+            result.content[1].as_text().unwrap().text,
+            r#"Response does not match the operation's response schema: "1" is not of type "integer""#
+        );
+    }
+}
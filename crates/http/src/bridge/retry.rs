@@ -0,0 +1,303 @@
+use std::{
+    future::Future,
+    time::{Duration, Instant, SystemTime},
+};
+
+use reqwest::{Client, Method, Request, RequestBuilder, Response, StatusCode, header::RETRY_AFTER};
+use tracing::warn;
+
+/// Statuses upstreams answer with when a later attempt may succeed.
+const RETRY_STATUSES: [StatusCode; 4] = [
+    StatusCode::TOO_MANY_REQUESTS,
+    StatusCode::BAD_GATEWAY,
+    StatusCode::SERVICE_UNAVAILABLE,
+    StatusCode::GATEWAY_TIMEOUT,
+];
+
+/// Controls how failed upstream calls are retried.
+///
+/// Only requests that are safe to repeat are retried: those with idempotent
+/// methods, and others carrying an `Idempotency-Key` header.
+#[derive(Debug, Clone)]
+pub struct RetryOptions {
+    /// Attempts made after the first one. Zero disables retries.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each one after it.
+    pub initial_backoff: Duration,
+    /// Upper bound of the delay before a retry. Responses asking to wait
+    /// longer with `Retry-After` are returned rather than retried.
+    pub max_backoff: Duration,
+    /// Time a tool call may take, all attempts included. Each attempt times
+    /// out when it runs out, and retries that could not start in time are
+    /// given up. Requests with a timeout of their own, such as operations
+    /// with `x-brwse-timeout`, take that instead.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for RetryOptions {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            timeout: None,
+        }
+    }
+}
+
+impl RetryOptions {
+    /// Returns the delay before retry number `attempt`, counting from zero,
+    /// with jitter so that concurrent calls do not retry in lockstep.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self.initial_backoff.saturating_mul(2u32.saturating_pow(attempt));
+        let backoff = backoff.min(self.max_backoff);
+        backoff.mul_f64(rand::random_range(0.5..=1.0))
+    }
+}
+
+/// Sends a request with `send`, retrying transient failures as configured.
+///
+/// Attempts of `streamed` calls, whose responses are forwarded as they
+/// arrive, are not timed out at the deadline. Their streams may run for
+/// longer, and are cut when they go idle for the client's read timeout.
+///
+/// The outcome of the last attempt is returned when retries run out.
+pub async fn send<F, Fut>(
+    options: &RetryOptions,
+    request: RequestBuilder,
+    streamed: bool,
+    send: F,
+) -> Result<reqwest::Result<Response>, rmcp::Error>
+where
+    F: Fn(RequestBuilder) -> Fut,
+    Fut: Future<Output = Result<reqwest::Result<Response>, rmcp::Error>>,
+{
+    let (client, request) = request.build_split();
+    let request = match request {
+        Ok(request) => request,
+        Err(e) => return Ok(Err(e)),
+    };
+    let timeout = request.timeout().copied().or(options.timeout);
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let attempt_deadline = deadline.filter(|_| !streamed);
+    let retryable = options.max_retries > 0 && is_idempotent(&request);
+    let mut attempt = 0;
+    loop {
+        // Requests with streamed bodies cannot be cloned, nor retried.
+        let retry =
+            (retryable && attempt < options.max_retries).then(|| request.try_clone()).flatten();
+        let Some(retry) = retry else {
+            return send(within(&client, request, attempt_deadline)).await;
+        };
+
+        let result = send(within(&client, retry, attempt_deadline)).await?;
+        let delay = match &result {
+            Ok(response) if RETRY_STATUSES.contains(&response.status()) => {
+                match retry_after(response) {
+                    Some(delay) if delay > options.max_backoff => return Ok(result),
+                    Some(delay) => delay,
+                    None => options.backoff(attempt),
+                }
+            }
+            Err(err) if err.is_connect() || err.is_timeout() => options.backoff(attempt),
+            _ => return Ok(result),
+        };
+        if deadline.is_some_and(|deadline| Instant::now() + delay >= deadline) {
+            return Ok(result);
+        }
+
+        match &result {
+            Ok(response) => {
+                warn!("Retrying after {:?}: upstream returned {}", delay, response.status())
+            }
+            Err(err) => warn!("Retrying after {:?}: {}", delay, err),
+        }
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Times an attempt out at `deadline`.
+fn within(client: &Client, mut request: Request, deadline: Option<Instant>) -> RequestBuilder {
+    if let Some(deadline) = deadline {
+        *request.timeout_mut() = Some(deadline.saturating_duration_since(Instant::now()));
+    }
+    RequestBuilder::from_parts(client.clone(), request)
+}
+
+fn is_idempotent(request: &Request) -> bool {
+    matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE
+    ) || request.headers().contains_key("idempotency-key")
+}
+
+/// Reads the delay requested by a `Retry-After` header, given either in
+/// seconds or as an HTTP date.
+pub(super) fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => {
+            let date = httpdate::parse_http_date(value).ok()?;
+            Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path},
+    };
+
+    use super::*;
+
+    fn options(max_retries: u32) -> RetryOptions {
+        RetryOptions {
+            max_retries,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(10),
+            timeout: None,
+        }
+    }
+
+    async fn send_to(
+        mock_server: &MockServer,
+        options: &RetryOptions,
+        request: RequestBuilder,
+    ) -> StatusCode {
+        let send = |request: RequestBuilder| async { Ok(request.send().await) };
+        let result = super::send(options, request, false, send).await;
+        let status = result.unwrap().unwrap().status();
+        mock_server.verify().await;
+        status
+    }
+
+    #[test]
+    fn test_backoff() {
+        let options = RetryOptions { max_retries: 5, ..Default::default() };
+        let delay = options.backoff(1);
+        assert!(delay >= Duration::from_millis(500) && delay <= Duration::from_secs(1));
+        assert!(options.backoff(10) <= Duration::from_secs(30));
+    }
+
+    #[tokio::test]
+    async fn test_retries_idempotent_requests() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(503).insert_header("Retry-After", "0"))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let request = client.get(format!("{}/flaky", mock_server.uri()));
+        assert_eq!(send_to(&mock_server, &options(3), request).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_retries_only_with_idempotency_key() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/orders"))
+            .respond_with(ResponseTemplate::new(429))
+            // Once without the key, then once plus two retries with it
+            .expect(1 + 3)
+            .mount(&mock_server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let url = format!("{}/orders", mock_server.uri());
+        let options = options(2);
+        let result =
+            send(&options, client.post(&url), false, |request| async { Ok(request.send().await) });
+        assert_eq!(result.await.unwrap().unwrap().status(), StatusCode::TOO_MANY_REQUESTS);
+
+        let request = client.post(&url).header("Idempotency-Key", "abc");
+        assert_eq!(send_to(&mock_server, &options, request).await, StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_at_timeout() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/busy"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "60"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let options = RetryOptions {
+            max_backoff: Duration::from_secs(120),
+            timeout: Some(Duration::from_secs(5)),
+            ..options(3)
+        };
+        let client = reqwest::Client::new();
+        let request = client.get(format!("{}/busy", mock_server.uri()));
+        assert_eq!(send_to(&mock_server, &options, request).await, StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_beyond_max_backoff() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/busy"))
+            .respond_with(ResponseTemplate::new(503).insert_header("Retry-After", "86400"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let request = client.get(format!("{}/busy", mock_server.uri()));
+        let options = options(3);
+        let status =
+            tokio::time::timeout(Duration::from_secs(5), send_to(&mock_server, &options, request));
+        assert_eq!(status.await.unwrap(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout_replaces_timeout() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/report"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(300)))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let options = RetryOptions { timeout: Some(Duration::from_millis(100)), ..options(3) };
+        let client = reqwest::Client::new();
+        let request =
+            client.get(format!("{}/report", mock_server.uri())).timeout(Duration::from_secs(5));
+        assert_eq!(send_to(&mock_server, &options, request).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_attempts_time_out_at_timeout() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/slow"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+            .mount(&mock_server)
+            .await;
+
+        let options = RetryOptions { timeout: Some(Duration::from_millis(200)), ..options(3) };
+        let client = reqwest::Client::new();
+        let request = client.get(format!("{}/slow", mock_server.uri()));
+        let started = Instant::now();
+        let result =
+            send(&options, request, false, |request| async { Ok(request.send().await) }).await;
+        assert!(result.unwrap().unwrap_err().is_timeout());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+
+use openapiv3::Server;
+
+/// Expands the `{variables}` of a server URL.
+///
+/// Values in `overrides` take precedence over the declared defaults, but must
+/// be one of the variable's `enum` values when it declares any. Overrides for
+/// variables the server does not declare are ignored.
+pub fn server_url(server: &Server, overrides: &HashMap<String, String>) -> Result<String, String> {
+    let mut url = server.url.clone();
+    for (name, variable) in server.variables.iter().flatten() {
+        let value = match overrides.get(name) {
+            Some(value)
+                if !variable.enumeration.is_empty() && !variable.enumeration.contains(value) =>
+            {
+                return Err(format!(
+                    "invalid value `{value}` for server variable `{name}`, expected one of: {}",
+                    variable.enumeration.join(", ")
+                ));
+            }
+            Some(value) => value,
+            None => &variable.default,
+        };
+        url = url.replace(&format!("{{{name}}}"), value);
+    }
+
+    if let Some(start) = url.find('{')
+        && let Some(end) = url[start..].find('}')
+    {
+        return Err(format!(
+            "server URL `{}` uses undeclared variable `{}`",
+            server.url,
+            &url[start + 1..start + end]
+        ));
+    }
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_server_url() {
+        let server: Server = serde_json::from_value(json!({
+            "url": "https://{region}.api.example.com/{version}",
+            "variables": {
+                "region": {"default": "eu", "enum": ["eu", "us"]},
+                "version": {"default": "v1"},
+            },
+        }))
+        .unwrap();
+
+        let url = server_url(&server, &HashMap::new());
+        assert_eq!(url.unwrap(), "https://eu.api.example.com/v1");
+
+        let overrides = HashMap::from([
+            ("version".to_string(), "v2".to_string()),
+            ("tenant".to_string(), "acme".to_string()),
+        ]);
+        let url = server_url(&server, &overrides);
+        assert_eq!(url.unwrap(), "https://eu.api.example.com/v2");
+
+        let overrides = HashMap::from([("region".to_string(), "ap".to_string())]);
+        assert_eq!(
+            server_url(&server, &overrides).unwrap_err(),
+            "invalid value `ap` for server variable `region`, expected one of: eu, us"
+        );
+
+        let server = Server { url: "https://{host}/api".to_string(), ..Default::default() };
+        assert_eq!(
+            server_url(&server, &HashMap::new()).unwrap_err(),
+            "server URL `https://{host}/api` uses undeclared variable `host`"
+        );
+    }
+}
@@ -0,0 +1,310 @@
+use std::{path::PathBuf, time::Duration};
+
+use openssl::pkey::PKey;
+use reqwest::{
+    Certificate, Identity, NoProxy, Proxy,
+    header::{HeaderMap, HeaderName, HeaderValue},
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("Failed to read {0}: {1}")]
+    FileReadError(PathBuf, std::io::Error),
+
+    #[error("Invalid certificate in {0}: {1}")]
+    InvalidCertificate(PathBuf, reqwest::Error),
+
+    #[error("Invalid private key in {0}: {1}")]
+    InvalidKey(PathBuf, openssl::error::ErrorStack),
+
+    #[error("Invalid proxy URL: {0}")]
+    InvalidProxy(reqwest::Error),
+
+    #[error("Invalid header: {0}")]
+    InvalidHeader(String),
+
+    #[error("Failed to build HTTP client: {0}")]
+    BuildError(#[from] reqwest::Error),
+}
+
+/// Settings of the client upstream APIs are called with.
+#[derive(Debug, Clone, Default)]
+pub struct ClientOptions {
+    /// Time a request may take, response body included.
+    pub timeout: Option<Duration>,
+    /// Time a response may go without sending data, which bounds streamed
+    /// responses without cutting those that keep sending.
+    pub read_timeout: Option<Duration>,
+    /// PEM file of certificate authorities to trust besides the system's.
+    pub ca_bundle: Option<PathBuf>,
+    /// PEM file of the certificate, and its chain, presented for mutual TLS.
+    pub client_cert: Option<PathBuf>,
+    /// PEM file of the private key of `client_cert`, which may hold it
+    /// instead.
+    pub client_key: Option<PathBuf>,
+    /// URL of the proxy to send all requests through, replacing the one set by
+    /// the `HTTP_PROXY` and `HTTPS_PROXY` environment variables.
+    pub proxy: Option<String>,
+    /// Hosts, domains and IP ranges reached without `proxy`, as in the
+    /// `NO_PROXY` environment variable. Ignored without `proxy`, as the proxies
+    /// of the environment follow `NO_PROXY` itself.
+    pub no_proxy: Vec<String>,
+    /// Headers sent with every request, unless set by the request. The bridge
+    /// requests OAuth2 tokens with the same client, so these reach token
+    /// endpoints too.
+    pub headers: Vec<(String, String)>,
+}
+
+impl ClientOptions {
+    pub fn build(&self) -> Result<reqwest::Client, ClientError> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = self.read_timeout {
+            builder = builder.read_timeout(timeout);
+        }
+
+        if let Some(path) = &self.ca_bundle {
+            let certificates = Certificate::from_pem_bundle(&read(path)?)
+                .map_err(|e| ClientError::InvalidCertificate(path.clone(), e))?;
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        if let Some(path) = &self.client_cert {
+            let certificate = read(path)?;
+            let key_path = self.client_key.as_ref().unwrap_or(path);
+            // Keys may come in any PEM format, but must be handed over as PKCS#8.
+            let key = PKey::private_key_from_pem(&read(key_path)?)
+                .and_then(|key| key.private_key_to_pem_pkcs8())
+                .map_err(|e| ClientError::InvalidKey(key_path.clone(), e))?;
+            let identity = Identity::from_pkcs8_pem(&certificate, &key)
+                .map_err(|e| ClientError::InvalidCertificate(path.clone(), e))?;
+            builder = builder.identity(identity);
+        }
+
+        if let Some(url) = &self.proxy {
+            let proxy = Proxy::all(url).map_err(ClientError::InvalidProxy)?;
+            builder = builder.proxy(proxy.no_proxy(NoProxy::from_string(&self.no_proxy.join(","))));
+        }
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let invalid = |what| ClientError::InvalidHeader(format!("invalid {what} `{name}`"));
+            let header = HeaderName::try_from(name).map_err(|_| invalid("name"))?;
+            headers.append(header, HeaderValue::try_from(value).map_err(|_| invalid("value for"))?);
+        }
+
+        Ok(builder.default_headers(headers).build()?)
+    }
+}
+
+fn read(path: &PathBuf) -> Result<Vec<u8>, ClientError> {
+    std::fs::read(path).map_err(|e| ClientError::FileReadError(path.clone(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use openssl::{
+        asn1::Asn1Time,
+        bn::{BigNum, MsbOption},
+        hash::MessageDigest,
+        pkey::Private,
+        rsa::Rsa,
+        ssl::{SslAcceptor, SslMethod, SslVerifyMode},
+        x509::{
+            X509, X509NameBuilder,
+            extension::{BasicConstraints, SubjectAlternativeName},
+        },
+    };
+    use tempfile::NamedTempFile;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{header, method, path},
+    };
+
+    use super::*;
+
+    type KeyPair = (X509, PKey<Private>);
+
+    /// Issues a certificate for localhost signed by `issuer`, or a
+    /// certificate authority without one.
+    fn certificate(issuer: Option<&KeyPair>) -> KeyPair {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", if issuer.is_some() { "localhost" } else { "Test CA" })
+            .unwrap();
+        let name = name.build();
+        let mut serial = BigNum::new().unwrap();
+        serial.rand(64, MsbOption::MAYBE_ZERO, false).unwrap();
+
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_serial_number(&serial.to_asn1_integer().unwrap()).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(issuer.map_or(&name, |(ca, _)| ca.subject_name())).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        builder.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+        match issuer {
+            Some((ca, ca_key)) => {
+                let names = SubjectAlternativeName::new()
+                    .dns("localhost")
+                    .ip("127.0.0.1")
+                    .build(&builder.x509v3_context(Some(ca), None))
+                    .unwrap();
+                builder.append_extension(names).unwrap();
+                builder.sign(ca_key, MessageDigest::sha256()).unwrap();
+            }
+            None => {
+                builder
+                    .append_extension(BasicConstraints::new().critical().ca().build().unwrap())
+                    .unwrap();
+                builder.sign(&key, MessageDigest::sha256()).unwrap();
+            }
+        }
+        (builder.build(), key)
+    }
+
+    /// Serves `ok` over TLS with `server`'s certificate, requiring clients to
+    /// present one issued by `client_ca` when given. Returns the server URL.
+    fn serve_tls(server: &KeyPair, client_ca: Option<&X509>) -> String {
+        let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+        acceptor.set_certificate(&server.0).unwrap();
+        acceptor.set_private_key(&server.1).unwrap();
+        if let Some(ca) = client_ca {
+            acceptor.cert_store_mut().add_cert(ca.clone()).unwrap();
+            acceptor.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+        }
+        let acceptor = acceptor.build();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = acceptor.accept(stream.unwrap()) else {
+                    continue;
+                };
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buffer) {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => request.extend_from_slice(&buffer[..read]),
+                    }
+                }
+                let response =
+                    "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok";
+                let _ = stream.write_all(response.as_bytes());
+                let _ = stream.shutdown();
+            }
+        });
+        format!("https://localhost:{port}/")
+    }
+
+    fn pem_file(contents: &[u8]) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(contents).unwrap();
+        file
+    }
+
+    async fn get(options: &ClientOptions, url: &str) -> reqwest::Result<String> {
+        options.build().unwrap().get(url).send().await?.error_for_status()?.text().await
+    }
+
+    #[tokio::test]
+    async fn test_ca_bundle() {
+        let ca = certificate(None);
+        let url = serve_tls(&certificate(Some(&ca)), None);
+
+        assert!(get(&ClientOptions::default(), &url).await.is_err());
+
+        let bundle = pem_file(&ca.0.to_pem().unwrap());
+        let options =
+            ClientOptions { ca_bundle: Some(bundle.path().to_path_buf()), ..Default::default() };
+        assert_eq!(get(&options, &url).await.unwrap(), "ok");
+    }
+
+    #[tokio::test]
+    async fn test_client_certificate() {
+        let ca = certificate(None);
+        let url = serve_tls(&certificate(Some(&ca)), Some(&ca.0));
+        let bundle = pem_file(&ca.0.to_pem().unwrap());
+        let options =
+            ClientOptions { ca_bundle: Some(bundle.path().to_path_buf()), ..Default::default() };
+
+        assert!(get(&options, &url).await.is_err());
+
+        // A PKCS#1 key in its own file
+        let client = certificate(Some(&ca));
+        let cert = pem_file(&client.0.to_pem().unwrap());
+        let key = pem_file(&client.1.rsa().unwrap().private_key_to_pem().unwrap());
+        let options = ClientOptions {
+            client_cert: Some(cert.path().to_path_buf()),
+            client_key: Some(key.path().to_path_buf()),
+            ..options
+        };
+        assert_eq!(get(&options, &url).await.unwrap(), "ok");
+
+        // A PKCS#8 key next to the certificate
+        let combined = [client.0.to_pem().unwrap(), client.1.private_key_to_pem_pkcs8().unwrap()];
+        let combined = pem_file(&combined.concat());
+        let options = ClientOptions {
+            client_cert: Some(combined.path().to_path_buf()),
+            client_key: None,
+            ..options
+        };
+        assert_eq!(get(&options, &url).await.unwrap(), "ok");
+
+        let options = ClientOptions { client_key: Some(bundle.path().to_path_buf()), ..options };
+        assert!(matches!(options.build(), Err(ClientError::InvalidKey(..))));
+    }
+
+    #[tokio::test]
+    async fn test_proxy_and_headers() {
+        let proxy = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/status"))
+            .and(header("host", "api.internal.test"))
+            .and(header("x-tenant", "acme"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("proxied"))
+            .expect(1)
+            .mount(&proxy)
+            .await;
+        let direct = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/status"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("direct"))
+            .expect(1)
+            .mount(&direct)
+            .await;
+
+        let options = ClientOptions {
+            proxy: Some(proxy.uri()),
+            no_proxy: vec!["127.0.0.1".to_string()],
+            headers: vec![("X-Tenant".to_string(), "acme".to_string())],
+            ..Default::default()
+        };
+        let proxied = get(&options, "http://api.internal.test/status").await;
+        assert_eq!(proxied.unwrap(), "proxied");
+        assert_eq!(get(&options, &format!("{}/status", direct.uri())).await.unwrap(), "direct");
+
+        let options = ClientOptions {
+            headers: vec![("X-Tenant".to_string(), "a\nb".to_string())],
+            ..Default::default()
+        };
+        assert_eq!(
+            options.build().unwrap_err().to_string(),
+            "Invalid header: invalid value for `X-Tenant`"
+        );
+    }
+}
@@ -1,13 +1,19 @@
-use std::path::Path;
-
 use openapiv3::OpenAPI;
 use thiserror::Error;
 
+mod bundle;
+mod schema;
+mod swagger;
+mod v31;
+
 #[derive(Error, Debug)]
 pub enum OpenApiError {
     #[error("Failed to read file: {0}")]
     FileReadError(#[from] std::io::Error),
 
+    #[error("Failed to fetch spec: {0}")]
+    HttpError(#[from] reqwest::Error),
+
     #[error("Failed to parse JSON: {0}")]
     JsonParseError(#[from] serde_json::Error),
 
@@ -16,23 +22,49 @@ pub enum OpenApiError {
 
     #[error("Unsupported file format: {0}")]
     UnsupportedFormat(String),
+
+    #[error("Invalid spec location: {0}")]
+    InvalidLocation(String),
+
+    #[error("Unresolvable reference: {0}")]
+    InvalidReference(String),
+
+    #[error("Unsupported OpenAPI version: {0}")]
+    UnsupportedVersion(String),
 }
 
-pub async fn load_spec(path: &str) -> Result<OpenAPI, OpenApiError> {
-    let path = Path::new(path);
-    let contents = tokio::fs::read_to_string(path).await?;
-
-    let spec = match path.extension().and_then(|ext| ext.to_str()) {
-        Some("json") => serde_json::from_str(&contents)?,
-        Some("yaml") | Some("yml") => serde_yaml::from_str(&contents)?,
-        Some(ext) => return Err(OpenApiError::UnsupportedFormat(ext.to_string())),
-        None => {
-            // Try JSON first, then YAML
-            serde_json::from_str(&contents).or_else(|_| serde_yaml::from_str(&contents))?
-        }
+/// Loads a spec from a file path or an http(s) URL.
+///
+/// References to other files or URLs are resolved relative to the document
+/// containing them and bundled into the returned spec. Swagger 2.0 and
+/// OpenAPI 3.1 documents are converted to OpenAPI 3.0.
+pub async fn load_spec(location: &str) -> Result<OpenAPI, OpenApiError> {
+    let spec = bundle::bundle(location).await?;
+    let spec = upgrade(spec)?;
+    Ok(serde_yaml::from_value(spec)?)
+}
+
+fn upgrade(spec: serde_yaml::Value) -> Result<serde_yaml::Value, OpenApiError> {
+    // Unquoted versions such as `swagger: 2.0` are parsed as numbers by YAML.
+    let version = |key| match spec.get(key)? {
+        serde_yaml::Value::String(version) => Some(version.clone()),
+        serde_yaml::Value::Number(version) => Some(version.to_string()),
+        _ => None,
     };
 
-    Ok(spec)
+    if let Some(version) = version("swagger") {
+        return match version.as_str() {
+            "2.0" => Ok(swagger::upgrade(spec)),
+            _ => Err(OpenApiError::UnsupportedVersion(version)),
+        };
+    }
+    match version("openapi") {
+        Some(version) if version.starts_with("3.1.") => Ok(v31::upgrade(spec)),
+        Some(version) if !version.starts_with("3.0.") => {
+            Err(OpenApiError::UnsupportedVersion(version))
+        }
+        _ => Ok(spec),
+    }
 }
 
 #[cfg(test)]
@@ -273,4 +305,83 @@ paths: {}
         assert!(!spec.paths.paths.is_empty());
         assert!(spec.components.is_some());
     }
+
+    #[tokio::test]
+    async fn test_load_swagger_2_spec() {
+        let spec_content = r##"
+swagger: 2.0
+info:
+  title: Legacy API
+  version: 1.0.0
+host: legacy.example.com
+basePath: /api
+paths:
+  /items:
+    post:
+      operationId: createItem
+      parameters:
+        - name: item
+          in: body
+          required: true
+          schema:
+            $ref: "#/definitions/Item"
+      responses:
+        201:
+          description: Created
+definitions:
+  Item:
+    type: object
+        "##;
+
+        let mut temp_file = NamedTempFile::with_suffix(".yaml").unwrap();
+        write!(temp_file, "{spec_content}").unwrap();
+
+        let spec = load_spec(temp_file.path().to_str().unwrap()).await.unwrap();
+        assert_eq!(spec.openapi, "3.0.3");
+        assert_eq!(spec.servers[0].url, "https://legacy.example.com/api");
+        let (_, _, operation) = spec.operations().next().unwrap();
+        assert_eq!(operation.operation_id.as_deref(), Some("createItem"));
+        assert!(operation.request_body.is_some());
+        assert!(spec.components.unwrap().schemas.contains_key("Item"));
+    }
+
+    #[tokio::test]
+    async fn test_load_openapi_31_spec() {
+        let spec_content = r#"
+        {
+            "openapi": "3.1.0",
+            "info": {
+                "title": "Modern API",
+                "version": "1.0.0"
+            },
+            "components": {
+                "schemas": {
+                    "Name": {
+                        "type": ["string", "null"]
+                    }
+                }
+            }
+        }
+        "#;
+
+        let mut temp_file = NamedTempFile::with_suffix(".json").unwrap();
+        write!(temp_file, "{spec_content}").unwrap();
+
+        let spec = load_spec(temp_file.path().to_str().unwrap()).await.unwrap();
+        assert!(spec.paths.paths.is_empty());
+        let components = spec.components.unwrap();
+        let openapiv3::ReferenceOr::Item(schema) = &components.schemas["Name"] else {
+            panic!("expected an inline schema");
+        };
+        assert!(schema.schema_data.nullable);
+    }
+
+    #[tokio::test]
+    async fn test_load_unsupported_version() {
+        let mut temp_file = NamedTempFile::with_suffix(".json").unwrap();
+        write!(temp_file, r#"{{"swagger": "1.2", "info": {{}}}}"#).unwrap();
+
+        let result = load_spec(temp_file.path().to_str().unwrap()).await;
+        assert!(matches!(result, Err(OpenApiError::UnsupportedVersion(_))));
+    }
 }
@@ -0,0 +1,459 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    path::Path,
+};
+
+use serde_yaml::{Mapping, Value};
+use url::Url;
+
+use super::OpenApiError;
+
+/// Loads a spec and every document it references, and merges them into a
+/// single self-contained document.
+///
+/// Documents are kept as YAML values, which preserve the order of their keys
+/// and accept the non-string keys YAML allows.
+///
+/// External references are replaced by the value they point to. References
+/// that loop back onto a value being inlined are hoisted into the root's
+/// reusable schemas instead, so recursive schemas stay representable.
+pub async fn bundle(location: &str) -> Result<Value, OpenApiError> {
+    let root = parse_location(location)?;
+    let documents = load_documents(&root).await?;
+
+    // Swagger 2.0 keeps reusable schemas under `definitions`.
+    let schemas_path: &[&str] = match documents[&root].get("swagger") {
+        Some(_) => &["definitions"],
+        None => &["components", "schemas"],
+    };
+    let existing = schemas_path.iter().try_fold(&documents[&root], |value, key| value.get(key));
+
+    let mut bundler = Bundler {
+        root: &root,
+        documents: &documents,
+        schemas_prefix: format!("#/{}/", schemas_path.join("/")),
+        stack: Vec::new(),
+        hoisted: HashMap::new(),
+        names: existing
+            .and_then(Value::as_mapping)
+            .map(|schemas| schemas.keys().filter_map(Value::as_str).map(str::to_string).collect())
+            .unwrap_or_default(),
+        schemas: Mapping::new(),
+    };
+    let mut spec = bundler.bundle_value(&documents[&root], &root)?;
+
+    if !bundler.schemas.is_empty() {
+        let mut target = &mut spec;
+        for key in schemas_path {
+            target = target
+                .as_mapping_mut()
+                .ok_or_else(|| OpenApiError::InvalidReference(location.to_string()))?
+                .entry((*key).into())
+                .or_insert_with(|| Value::Mapping(Mapping::new()));
+        }
+        if let Some(schemas) = target.as_mapping_mut() {
+            schemas.extend(bundler.schemas);
+        }
+    }
+
+    Ok(spec)
+}
+
+/// Parses a spec location, which is either an http(s) URL or a file path.
+fn parse_location(location: &str) -> Result<Url, OpenApiError> {
+    if location.starts_with("http://") || location.starts_with("https://") {
+        return Url::parse(location)
+            .map_err(|e| OpenApiError::InvalidLocation(format!("{location}: {e}")));
+    }
+
+    let path = std::path::absolute(location)?;
+    Url::from_file_path(&path).map_err(|()| OpenApiError::InvalidLocation(location.to_string()))
+}
+
+/// Loads the root document and, transitively, every document it references.
+async fn load_documents(root: &Url) -> Result<HashMap<Url, Value>, OpenApiError> {
+    let client = reqwest::Client::new();
+    let mut documents = HashMap::new();
+    let mut queue = VecDeque::from([root.clone()]);
+
+    while let Some(url) = queue.pop_front() {
+        if documents.contains_key(&url) {
+            continue;
+        }
+
+        let document = load_document(&client, &url).await?;
+        let mut references = Vec::new();
+        collect_references(&document, &mut references);
+        for reference in references {
+            let mut target = url
+                .join(reference)
+                .map_err(|_| OpenApiError::InvalidReference(reference.to_string()))?;
+            target.set_fragment(None);
+            if !documents.contains_key(&target) {
+                queue.push_back(target);
+            }
+        }
+        documents.insert(url, document);
+    }
+
+    Ok(documents)
+}
+
+async fn load_document(client: &reqwest::Client, url: &Url) -> Result<Value, OpenApiError> {
+    let contents = match url.scheme() {
+        "file" => {
+            let path =
+                url.to_file_path().map_err(|()| OpenApiError::InvalidLocation(url.to_string()))?;
+            tokio::fs::read_to_string(path).await?
+        }
+        "http" | "https" => {
+            client.get(url.clone()).send().await?.error_for_status()?.text().await?
+        }
+        scheme => {
+            return Err(OpenApiError::InvalidLocation(format!("unsupported scheme {scheme}")));
+        }
+    };
+
+    let document = match Path::new(url.path()).extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str(&contents)?,
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&contents)?,
+        Some(ext) => return Err(OpenApiError::UnsupportedFormat(ext.to_string())),
+        None => {
+            // Try JSON first, then YAML
+            serde_json::from_str(&contents).or_else(|_| serde_yaml::from_str(&contents))?
+        }
+    };
+
+    Ok(document)
+}
+
+fn collect_references<'a>(value: &'a Value, references: &mut Vec<&'a str>) {
+    match value {
+        Value::Mapping(mapping) => {
+            if let Some(Value::String(reference)) = mapping.get("$ref") {
+                references.push(reference);
+            } else {
+                mapping.values().for_each(|value| collect_references(value, references));
+            }
+        }
+        Value::Sequence(items) => {
+            items.iter().for_each(|value| collect_references(value, references))
+        }
+        _ => {}
+    }
+}
+
+/// Looks up a JSON pointer in a document.
+fn resolve_pointer<'a>(document: &'a Value, pointer: &str) -> Option<&'a Value> {
+    if pointer.is_empty() {
+        return Some(document);
+    }
+    pointer.strip_prefix('/')?.split('/').try_fold(document, |value, token| {
+        let token = token.replace("~1", "/").replace("~0", "~");
+        match value {
+            Value::Sequence(items) => items.get(token.parse::<usize>().ok()?),
+            value => value.get(token.as_str()),
+        }
+    })
+}
+
+/// Sections of the root document whose entries stay referenced rather than
+/// inlined. The last three are Swagger 2.0's counterparts of `components`.
+const REUSABLE_SECTIONS: [&str; 4] =
+    ["/components/", "/definitions/", "/parameters/", "/responses/"];
+
+struct Bundler<'a> {
+    root: &'a Url,
+    documents: &'a HashMap<Url, Value>,
+    /// Reference prefix of the root's reusable schemas.
+    schemas_prefix: String,
+    /// Targets currently being inlined, used to detect cycles.
+    stack: Vec<String>,
+    /// Targets hoisted into the root's schemas, with their component name.
+    hoisted: HashMap<String, String>,
+    /// Component names already in use in the root's schemas.
+    names: HashSet<String>,
+    /// Hoisted schemas, added to the root once bundling completes.
+    schemas: Mapping,
+}
+
+impl Bundler<'_> {
+    fn bundle_value(&mut self, value: &Value, base: &Url) -> Result<Value, OpenApiError> {
+        match value {
+            Value::Mapping(mapping) => {
+                if let Some(Value::String(reference)) = mapping.get("$ref") {
+                    return self.bundle_reference(reference, base);
+                }
+                mapping
+                    .iter()
+                    .map(|(key, value)| Ok((key.clone(), self.bundle_value(value, base)?)))
+                    .collect::<Result<Mapping, _>>()
+                    .map(Value::Mapping)
+            }
+            Value::Sequence(items) => items
+                .iter()
+                .map(|value| self.bundle_value(value, base))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::Sequence),
+            _ => Ok(value.clone()),
+        }
+    }
+
+    fn bundle_reference(&mut self, reference: &str, base: &Url) -> Result<Value, OpenApiError> {
+        let invalid = || OpenApiError::InvalidReference(reference.to_string());
+
+        let target = base.join(reference).map_err(|_| invalid())?;
+        let fragment = target.fragment().unwrap_or_default().to_string();
+        let pointer = urlencoding::decode(&fragment).map_err(|_| invalid())?;
+        let mut document = target.clone();
+        document.set_fragment(None);
+
+        // Reusable sections of the root document stay referenced, as they are
+        // resolved once the spec is loaded.
+        if &document == self.root
+            && REUSABLE_SECTIONS.iter().any(|section| pointer.starts_with(section))
+        {
+            return Ok(reference_value(format!("#{fragment}")));
+        }
+
+        let key = target.to_string();
+        if let Some(name) = self.hoisted.get(&key) {
+            return Ok(reference_value(format!("{}{name}", self.schemas_prefix)));
+        }
+        if self.stack.contains(&key) {
+            let name = self.hoist_name(&document, &pointer);
+            let reference = reference_value(format!("{}{name}", self.schemas_prefix));
+            self.hoisted.insert(key, name);
+            return Ok(reference);
+        }
+
+        let value = self
+            .documents
+            .get(&document)
+            .and_then(|document| resolve_pointer(document, &pointer))
+            .ok_or_else(invalid)?;
+
+        self.stack.push(key.clone());
+        let bundled = self.bundle_value(value, &document)?;
+        self.stack.pop();
+
+        match self.hoisted.get(&key) {
+            Some(name) => {
+                self.schemas.insert(name.as_str().into(), bundled);
+                Ok(reference_value(format!("{}{name}", self.schemas_prefix)))
+            }
+            None => Ok(bundled),
+        }
+    }
+
+    /// Picks an unused component name for a hoisted schema, based on the last
+    /// segment of its pointer or the name of its document.
+    fn hoist_name(&mut self, document: &Url, pointer: &str) -> String {
+        let segment = pointer
+            .rsplit('/')
+            .find(|segment| !segment.is_empty())
+            .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+            .or_else(|| {
+                Path::new(document.path()).file_stem().map(|stem| stem.to_string_lossy().into())
+            })
+            .unwrap_or_else(|| "Schema".to_string());
+        let base = segment
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || "._-".contains(c) { c } else { '_' })
+            .collect::<String>();
+
+        let mut name = base.clone();
+        let mut suffix = 1;
+        while !self.names.insert(name.clone()) {
+            suffix += 1;
+            name = format!("{base}{suffix}");
+        }
+        name
+    }
+}
+
+fn reference_value(reference: String) -> Value {
+    Value::Mapping(Mapping::from_iter([("$ref".into(), reference.into())]))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::json;
+    use tempfile::TempDir;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path},
+    };
+
+    use super::*;
+
+    fn to_json(value: &Value) -> serde_json::Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    fn write(dir: &TempDir, name: &str, contents: &str) {
+        let path = dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_bundle_relative_file_references() {
+        let dir = TempDir::new().unwrap();
+        write(
+            &dir,
+            "openapi.yaml",
+            r#"
+openapi: 3.0.0
+info: { title: Split API, version: 1.0.0 }
+paths:
+  /users:
+    $ref: "./paths/users.yaml"
+components:
+  schemas:
+    Error: { type: string }
+"#,
+        );
+        write(
+            &dir,
+            "paths/users.yaml",
+            r#"
+get:
+  operationId: listUsers
+  responses:
+    "200":
+      description: Users
+      content:
+        application/json:
+          schema: { $ref: "../schemas/user.yaml#/User" }
+    default:
+      description: Error
+      content:
+        application/json:
+          schema: { $ref: "../openapi.yaml#/components/schemas/Error" }
+"#,
+        );
+        write(
+            &dir,
+            "schemas/user.yaml",
+            r##"
+User:
+  type: object
+  properties:
+    address: { $ref: "#/Address" }
+Address:
+  type: object
+  properties:
+    city: { type: string }
+"##,
+        );
+
+        let spec = bundle(dir.path().join("openapi.yaml").to_str().unwrap()).await.unwrap();
+        let get = &spec["paths"]["/users"]["get"];
+        assert_eq!(get["operationId"], "listUsers");
+        assert_eq!(
+            to_json(&get["responses"]["200"]["content"]["application/json"]["schema"]),
+            json!({
+                "type": "object",
+                "properties": {
+                    "address": {
+                        "type": "object",
+                        "properties": {"city": {"type": "string"}}
+                    }
+                }
+            })
+        );
+        assert_eq!(
+            to_json(&get["responses"]["default"]["content"]["application/json"]["schema"]),
+            json!({"$ref": "#/components/schemas/Error"})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bundle_hoists_circular_references() {
+        let dir = TempDir::new().unwrap();
+        write(
+            &dir,
+            "openapi.json",
+            r#"{
+                "openapi": "3.0.0",
+                "info": {"title": "Tree API", "version": "1.0.0"},
+                "paths": {},
+                "components": {
+                    "schemas": {
+                        "Node": {"type": "string"},
+                        "Tree": {"$ref": "tree.json#/Node"}
+                    }
+                }
+            }"#,
+        );
+        write(
+            &dir,
+            "tree.json",
+            r##"{
+                "Node": {
+                    "type": "object",
+                    "properties": {
+                        "children": {"type": "array", "items": {"$ref": "#/Node"}}
+                    }
+                }
+            }"##,
+        );
+
+        let spec = bundle(dir.path().join("openapi.json").to_str().unwrap()).await.unwrap();
+        assert_eq!(
+            to_json(&spec["components"]["schemas"]["Tree"]),
+            json!({"$ref": "#/components/schemas/Node2"})
+        );
+        assert_eq!(
+            to_json(&spec["components"]["schemas"]["Node2"]["properties"]["children"]["items"]),
+            json!({"$ref": "#/components/schemas/Node2"})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bundle_from_url() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/openapi.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "openapi": "3.0.0",
+                "info": {"title": "Remote API", "version": "1.0.0"},
+                "paths": {
+                    "/pets": {"$ref": "/specs/pets.yaml#/paths/~1pets"}
+                }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/specs/pets.yaml"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "paths:\n  /pets:\n    get:\n      operationId: listPets\n      responses: {}\n",
+            ))
+            .mount(&mock_server)
+            .await;
+
+        let spec = bundle(&format!("{}/openapi.json", mock_server.uri())).await.unwrap();
+        assert_eq!(spec["paths"]["/pets"]["get"]["operationId"], "listPets");
+    }
+
+    #[tokio::test]
+    async fn test_bundle_missing_reference_target() {
+        let dir = TempDir::new().unwrap();
+        write(
+            &dir,
+            "openapi.json",
+            r#"{
+                "openapi": "3.0.0",
+                "info": {"title": "Broken API", "version": "1.0.0"},
+                "paths": {"/a": {"$ref": "paths.json#/missing"}}
+            }"#,
+        );
+        write(&dir, "paths.json", "{}");
+
+        let result = bundle(dir.path().join("openapi.json").to_str().unwrap()).await;
+        assert!(matches!(result, Err(OpenApiError::InvalidReference(_))));
+    }
+}
@@ -0,0 +1,49 @@
+use serde_yaml::{Mapping, Value};
+
+/// Keys whose values are literal data rather than part of the document.
+const DATA_KEYS: [&str; 5] = ["example", "examples", "default", "enum", "const"];
+
+/// Keys whose values map user-chosen names to objects, so their keys must not
+/// be mistaken for keywords.
+const NAME_MAPS: [&str; 14] = [
+    "properties",
+    "patternProperties",
+    "dependentSchemas",
+    "$defs",
+    "definitions",
+    "schemas",
+    "parameters",
+    "responses",
+    "requestBodies",
+    "headers",
+    "securitySchemes",
+    "links",
+    "callbacks",
+    "pathItems",
+];
+
+/// Calls `f` on every object of a document that may be a schema, skipping
+/// literal data such as examples, defaults and extensions.
+pub fn walk(value: &mut Value, f: &mut impl FnMut(&mut Mapping)) {
+    walk_object(value, f, false);
+}
+
+fn walk_object(value: &mut Value, f: &mut impl FnMut(&mut Mapping), names: bool) {
+    match value {
+        Value::Mapping(mapping) => {
+            if !names {
+                f(mapping);
+            }
+            for (key, value) in mapping.iter_mut() {
+                let key = key.as_str().unwrap_or_default();
+                if names {
+                    walk_object(value, f, false);
+                } else if !DATA_KEYS.contains(&key) && !key.starts_with("x-") {
+                    walk_object(value, f, NAME_MAPS.contains(&key));
+                }
+            }
+        }
+        Value::Sequence(items) => items.iter_mut().for_each(|value| walk_object(value, f, false)),
+        _ => {}
+    }
+}
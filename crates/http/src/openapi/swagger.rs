@@ -0,0 +1,843 @@
+use serde_yaml::{Mapping, Value};
+
+use super::schema;
+
+/// Keys of a Swagger 2.0 parameter that describe its value, and move into the
+/// `schema` of the equivalent OpenAPI 3.0 parameter.
+const SCHEMA_KEYS: [&str; 17] = [
+    "type",
+    "format",
+    "items",
+    "default",
+    "maximum",
+    "exclusiveMaximum",
+    "minimum",
+    "exclusiveMinimum",
+    "maxLength",
+    "minLength",
+    "pattern",
+    "maxItems",
+    "minItems",
+    "uniqueItems",
+    "enum",
+    "multipleOf",
+    "x-nullable",
+];
+
+const METHODS: [&str; 7] = ["get", "put", "post", "delete", "options", "head", "patch"];
+
+/// Rewrites a Swagger 2.0 document into the OpenAPI 3.0 model.
+///
+/// Body and form parameters become request bodies, `consumes` and `produces`
+/// become media types, and `host`, `basePath` and `schemes` become servers.
+pub fn upgrade(spec: Value) -> Value {
+    let Value::Mapping(root) = spec else {
+        return spec;
+    };
+    let swagger = Swagger {
+        consumes: media_types(root.get("consumes"), "application/json"),
+        produces: media_types(root.get("produces"), "application/json"),
+        parameters: root.get("parameters").and_then(Value::as_mapping),
+    };
+
+    let mut upgraded = Mapping::new();
+    upgraded.insert("openapi".into(), "3.0.3".into());
+    for key in ["info", "tags", "externalDocs", "security"] {
+        if let Some(value) = root.get(key) {
+            upgraded.insert(key.into(), value.clone());
+        }
+    }
+    let servers = servers(&root);
+    if !servers.is_empty() {
+        upgraded.insert("servers".into(), Value::Sequence(servers));
+    }
+
+    let paths = root
+        .get("paths")
+        .and_then(Value::as_mapping)
+        .map(|paths| {
+            paths
+                .iter()
+                .map(|(path, item)| {
+                    let item = match item {
+                        Value::Mapping(item) => Value::Mapping(swagger.path_item(item)),
+                        item => item.clone(),
+                    };
+                    (path.clone(), item)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    upgraded.insert("paths".into(), Value::Mapping(paths));
+    upgraded.insert("components".into(), Value::Mapping(swagger.components(&root)));
+
+    for (key, value) in &root {
+        if key.as_str().is_some_and(|key| key.starts_with("x-")) {
+            upgraded.insert(key.clone(), value.clone());
+        }
+    }
+
+    let mut upgraded = Value::Mapping(upgraded);
+    rewrite_references(&mut upgraded);
+    schema::walk(&mut upgraded, &mut upgrade_schema);
+    upgraded
+}
+
+struct Swagger<'a> {
+    consumes: Vec<Value>,
+    produces: Vec<Value>,
+    parameters: Option<&'a Mapping>,
+}
+
+impl Swagger<'_> {
+    fn components(&self, root: &Mapping) -> Mapping {
+        let mut components = Mapping::new();
+        if let Some(definitions) = root.get("definitions") {
+            components.insert("schemas".into(), definitions.clone());
+        }
+
+        let mut parameters = Mapping::new();
+        let mut request_bodies = Mapping::new();
+        for (name, parameter) in self.parameters.into_iter().flatten() {
+            let Some(parameter) = parameter.as_mapping() else {
+                continue;
+            };
+            match location(parameter) {
+                "body" => {
+                    request_bodies.insert(name.clone(), body(parameter, &self.consumes));
+                }
+                // Form parameters are merged into the request body of each
+                // operation that references them.
+                "formData" => {}
+                _ => {
+                    parameters.insert(name.clone(), Value::Mapping(upgrade_parameter(parameter)));
+                }
+            }
+        }
+        if !parameters.is_empty() {
+            components.insert("parameters".into(), Value::Mapping(parameters));
+        }
+        if !request_bodies.is_empty() {
+            components.insert("requestBodies".into(), Value::Mapping(request_bodies));
+        }
+
+        if let Some(responses) = root.get("responses").and_then(Value::as_mapping) {
+            let responses =
+                responses.iter().map(|(name, r)| (name.clone(), self.response(r))).collect();
+            components.insert("responses".into(), Value::Mapping(responses));
+        }
+
+        if let Some(schemes) = root.get("securityDefinitions").and_then(Value::as_mapping) {
+            let schemes = schemes
+                .iter()
+                .map(|(name, scheme)| (name.clone(), security_scheme(scheme)))
+                .collect();
+            components.insert("securitySchemes".into(), Value::Mapping(schemes));
+        }
+
+        components
+    }
+
+    fn path_item(&self, item: &Mapping) -> Mapping {
+        let mut upgraded = Mapping::new();
+
+        // Body and form parameters shared by the path's operations are moved
+        // down into each operation's request body.
+        let (shared, path_parameters) = self.split_parameters(item.get("parameters"));
+        if !path_parameters.is_empty() {
+            upgraded.insert("parameters".into(), Value::Sequence(path_parameters));
+        }
+
+        for (key, value) in item {
+            match (key.as_str(), value) {
+                (Some(method), Value::Mapping(operation)) if METHODS.contains(&method) => {
+                    upgraded
+                        .insert(key.clone(), Value::Mapping(self.operation(operation, &shared)));
+                }
+                (Some("parameters"), _) => {}
+                _ => {
+                    upgraded.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        upgraded
+    }
+
+    fn operation(&self, operation: &Mapping, shared: &Inputs) -> Mapping {
+        let consumes = operation
+            .get("consumes")
+            .map_or_else(|| self.consumes.clone(), |types| media_types(Some(types), ""));
+        let produces = operation
+            .get("produces")
+            .map_or_else(|| self.produces.clone(), |types| media_types(Some(types), ""));
+
+        let (mut inputs, parameters) = self.split_parameters(operation.get("parameters"));
+        if inputs.body.is_none() {
+            inputs.body = shared.body.clone();
+        }
+        for parameter in &shared.form {
+            let name = parameter.get("name");
+            if !inputs.form.iter().any(|form| form.get("name") == name) {
+                inputs.form.push(parameter.clone());
+            }
+        }
+
+        let mut upgraded = Mapping::new();
+        for (key, value) in operation {
+            match key.as_str() {
+                Some("consumes" | "produces" | "schemes" | "parameters") => {}
+                Some("responses") => {
+                    let responses = value
+                        .as_mapping()
+                        .into_iter()
+                        .flatten()
+                        .map(|(code, response)| {
+                            (code.clone(), self.response_for(response, &produces))
+                        })
+                        .collect();
+                    upgraded.insert(key.clone(), Value::Mapping(responses));
+                }
+                _ => {
+                    upgraded.insert(key.clone(), value.clone());
+                }
+            }
+        }
+
+        if !parameters.is_empty() {
+            upgraded.insert("parameters".into(), Value::Sequence(parameters));
+        }
+        let request_body = match inputs.body {
+            Some(Input::Reference(name)) => {
+                Some(reference(&format!("#/components/requestBodies/{name}")))
+            }
+            Some(Input::Inline(parameter)) => Some(body(&parameter, &consumes)),
+            None if !inputs.form.is_empty() => Some(form_body(&inputs.form, &consumes)),
+            None => None,
+        };
+        if let Some(request_body) = request_body {
+            upgraded.insert("requestBody".into(), request_body);
+        }
+        upgraded
+    }
+
+    /// Separates body and form parameters from the ones that remain parameters
+    /// in OpenAPI 3.0, resolving references to shared parameters as needed.
+    fn split_parameters(&self, parameters: Option<&Value>) -> (Inputs, Vec<Value>) {
+        let mut inputs = Inputs::default();
+        let mut remaining = Vec::new();
+
+        for parameter in parameters.and_then(Value::as_sequence).into_iter().flatten() {
+            let shared = parameter
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(|reference| reference.strip_prefix("#/parameters/"))
+                .map(|name| (name, self.parameters.and_then(|shared| shared.get(name))));
+
+            match shared {
+                Some((name, Some(Value::Mapping(resolved)))) => match location(resolved) {
+                    "body" => inputs.body = Some(Input::Reference(name.to_string())),
+                    "formData" => inputs.form.push(resolved.clone()),
+                    _ => remaining.push(parameter.clone()),
+                },
+                Some(_) => remaining.push(parameter.clone()),
+                None => match parameter.as_mapping() {
+                    Some(inline) if location(inline) == "body" => {
+                        inputs.body = Some(Input::Inline(inline.clone()))
+                    }
+                    Some(inline) if location(inline) == "formData" => {
+                        inputs.form.push(inline.clone())
+                    }
+                    Some(inline) => remaining.push(Value::Mapping(upgrade_parameter(inline))),
+                    None => remaining.push(parameter.clone()),
+                },
+            }
+        }
+
+        (inputs, remaining)
+    }
+
+    fn response(&self, response: &Value) -> Value {
+        self.response_for(response, &self.produces)
+    }
+
+    fn response_for(&self, response: &Value, produces: &[Value]) -> Value {
+        let Some(response) = response.as_mapping() else {
+            return response.clone();
+        };
+        if response.contains_key("$ref") {
+            return Value::Mapping(response.clone());
+        }
+
+        let mut upgraded = Mapping::new();
+        upgraded.insert(
+            "description".into(),
+            response.get("description").cloned().unwrap_or_else(|| "".into()),
+        );
+        if let Some(headers) = response.get("headers").and_then(Value::as_mapping) {
+            let headers = headers
+                .iter()
+                .map(|(name, header)| {
+                    let header = header.as_mapping().map_or_else(
+                        || header.clone(),
+                        |header| Value::Mapping(upgrade_parameter(header)),
+                    );
+                    (name.clone(), header)
+                })
+                .collect();
+            upgraded.insert("headers".into(), Value::Mapping(headers));
+        }
+        if let Some(schema) = response.get("schema") {
+            let examples = response.get("examples");
+            let content = produces
+                .iter()
+                .map(|media_type| {
+                    let mut media = Mapping::new();
+                    media.insert("schema".into(), schema.clone());
+                    if let Some(example) = examples.and_then(|examples| examples.get(media_type)) {
+                        media.insert("example".into(), example.clone());
+                    }
+                    (media_type.clone(), Value::Mapping(media))
+                })
+                .collect();
+            upgraded.insert("content".into(), Value::Mapping(content));
+        }
+        copy_extensions(response, &mut upgraded);
+        Value::Mapping(upgraded)
+    }
+}
+
+/// The parameters of an operation that make up its request body.
+#[derive(Default)]
+struct Inputs {
+    body: Option<Input>,
+    form: Vec<Mapping>,
+}
+
+#[derive(Clone)]
+enum Input {
+    /// A body parameter shared under `#/parameters/`.
+    Reference(String),
+    Inline(Mapping),
+}
+
+fn location(parameter: &Mapping) -> &str {
+    parameter.get("in").and_then(Value::as_str).unwrap_or_default()
+}
+
+fn media_types(types: Option<&Value>, default: &str) -> Vec<Value> {
+    let types = types.and_then(Value::as_sequence).cloned().unwrap_or_default();
+    if types.is_empty() && !default.is_empty() { vec![default.into()] } else { types }
+}
+
+fn servers(root: &Mapping) -> Vec<Value> {
+    let host = root.get("host").and_then(Value::as_str);
+    let base_path = root.get("basePath").and_then(Value::as_str).unwrap_or_default();
+    let schemes = root
+        .get("schemes")
+        .and_then(Value::as_sequence)
+        .map(|schemes| schemes.iter().filter_map(Value::as_str).collect::<Vec<_>>())
+        .filter(|schemes| !schemes.is_empty())
+        .unwrap_or_else(|| vec!["https"]);
+
+    let urls = match host {
+        Some(host) => {
+            schemes.iter().map(|scheme| format!("{scheme}://{host}{base_path}")).collect()
+        }
+        None if !base_path.is_empty() => vec![base_path.to_string()],
+        None => vec![],
+    };
+    urls.into_iter()
+        .map(|url| Value::Mapping(Mapping::from_iter([("url".into(), url.into())])))
+        .collect()
+}
+
+/// Moves the value constraints of a parameter or header into its `schema`.
+fn upgrade_parameter(parameter: &Mapping) -> Mapping {
+    let mut upgraded = Mapping::new();
+    let mut schema = Mapping::new();
+    for (key, value) in parameter {
+        match key.as_str() {
+            Some(key) if SCHEMA_KEYS.contains(&key) => {
+                schema.insert(key.into(), value.clone());
+            }
+            Some("collectionFormat") => {}
+            _ => {
+                upgraded.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    if schema.get("type").and_then(Value::as_str) == Some("array") {
+        let format = parameter.get("collectionFormat").and_then(Value::as_str).unwrap_or("csv");
+        let (style, explode) = match (location(parameter), format) {
+            (_, "multi") => ("form", true),
+            ("query", "ssv") => ("spaceDelimited", false),
+            ("query", "pipes") => ("pipeDelimited", false),
+            ("query" | "formData", _) => ("form", false),
+            _ => ("simple", false),
+        };
+        // Response headers carry neither a location nor a style.
+        if parameter.contains_key("in") {
+            upgraded.insert("style".into(), style.into());
+            upgraded.insert("explode".into(), explode.into());
+        }
+    }
+    if !schema.is_empty() {
+        upgraded.insert("schema".into(), Value::Mapping(schema));
+    }
+    upgraded
+}
+
+fn body(parameter: &Mapping, consumes: &[Value]) -> Value {
+    let schema = parameter.get("schema").cloned().unwrap_or_else(|| Value::Mapping(Mapping::new()));
+    let content = consumes
+        .iter()
+        .map(|media_type| {
+            let media = Mapping::from_iter([("schema".into(), schema.clone())]);
+            (media_type.clone(), Value::Mapping(media))
+        })
+        .collect();
+
+    let mut body = Mapping::new();
+    if let Some(description) = parameter.get("description") {
+        body.insert("description".into(), description.clone());
+    }
+    body.insert("content".into(), Value::Mapping(content));
+    if let Some(required) = parameter.get("required") {
+        body.insert("required".into(), required.clone());
+    }
+    Value::Mapping(body)
+}
+
+fn form_body(parameters: &[Mapping], consumes: &[Value]) -> Value {
+    let has_file = parameters
+        .iter()
+        .any(|parameter| parameter.get("type").and_then(Value::as_str) == Some("file"));
+    let media_type = if has_file
+        || consumes.iter().any(|media_type| media_type.as_str() == Some("multipart/form-data"))
+    {
+        "multipart/form-data"
+    } else {
+        "application/x-www-form-urlencoded"
+    };
+
+    let mut properties = Mapping::new();
+    let mut required = Vec::new();
+    for parameter in parameters {
+        let Some(name) = parameter.get("name") else {
+            continue;
+        };
+        let upgraded = upgrade_parameter(parameter);
+        let mut schema =
+            upgraded.get("schema").and_then(Value::as_mapping).cloned().unwrap_or_default();
+        if let Some(description) = parameter.get("description") {
+            schema.insert("description".into(), description.clone());
+        }
+        properties.insert(name.clone(), Value::Mapping(schema));
+        if parameter.get("required").and_then(Value::as_bool) == Some(true) {
+            required.push(name.clone());
+        }
+    }
+
+    let mut schema = Mapping::new();
+    schema.insert("type".into(), "object".into());
+    schema.insert("properties".into(), Value::Mapping(properties));
+    if !required.is_empty() {
+        schema.insert("required".into(), Value::Sequence(required.clone()));
+    }
+
+    let media = Mapping::from_iter([("schema".into(), Value::Mapping(schema))]);
+    let mut body = Mapping::new();
+    body.insert(
+        "content".into(),
+        Value::Mapping(Mapping::from_iter([(media_type.into(), Value::Mapping(media))])),
+    );
+    if !required.is_empty() {
+        body.insert("required".into(), true.into());
+    }
+    Value::Mapping(body)
+}
+
+fn security_scheme(scheme: &Value) -> Value {
+    let Some(scheme) = scheme.as_mapping() else {
+        return scheme.clone();
+    };
+
+    let mut upgraded = Mapping::new();
+    match scheme.get("type").and_then(Value::as_str) {
+        Some("basic") => {
+            upgraded.insert("type".into(), "http".into());
+            upgraded.insert("scheme".into(), "basic".into());
+        }
+        Some("oauth2") => {
+            let flow = match scheme.get("flow").and_then(Value::as_str) {
+                Some("implicit") => "implicit",
+                Some("password") => "password",
+                Some("accessCode") => "authorizationCode",
+                _ => "clientCredentials",
+            };
+            let mut settings = Mapping::new();
+            for key in ["authorizationUrl", "tokenUrl"] {
+                if let Some(url) = scheme.get(key) {
+                    settings.insert(key.into(), url.clone());
+                }
+            }
+            settings.insert(
+                "scopes".into(),
+                scheme.get("scopes").cloned().unwrap_or_else(|| Value::Mapping(Mapping::new())),
+            );
+            upgraded.insert("type".into(), "oauth2".into());
+            upgraded.insert(
+                "flows".into(),
+                Value::Mapping(Mapping::from_iter([(flow.into(), Value::Mapping(settings))])),
+            );
+        }
+        _ => {
+            for key in ["type", "name", "in"] {
+                if let Some(value) = scheme.get(key) {
+                    upgraded.insert(key.into(), value.clone());
+                }
+            }
+        }
+    }
+    if let Some(description) = scheme.get("description") {
+        upgraded.insert("description".into(), description.clone());
+    }
+    copy_extensions(scheme, &mut upgraded);
+    Value::Mapping(upgraded)
+}
+
+fn copy_extensions(from: &Mapping, to: &mut Mapping) {
+    for (key, value) in from {
+        if key.as_str().is_some_and(|key| key.starts_with("x-")) {
+            to.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+fn reference(reference: &str) -> Value {
+    Value::Mapping(Mapping::from_iter([("$ref".into(), reference.into())]))
+}
+
+/// Points references to Swagger 2.0 sections at their OpenAPI 3.0 components.
+fn rewrite_references(value: &mut Value) {
+    match value {
+        Value::Mapping(mapping) => {
+            if let Some(Value::String(reference)) = mapping.get_mut("$ref") {
+                for (from, to) in [
+                    ("#/definitions/", "#/components/schemas/"),
+                    ("#/parameters/", "#/components/parameters/"),
+                    ("#/responses/", "#/components/responses/"),
+                ] {
+                    if let Some(name) = reference.strip_prefix(from) {
+                        *reference = format!("{to}{name}");
+                        break;
+                    }
+                }
+            }
+            mapping.values_mut().for_each(rewrite_references);
+        }
+        Value::Sequence(items) => items.iter_mut().for_each(rewrite_references),
+        _ => {}
+    }
+}
+
+/// Maps schema keywords that are specific to Swagger 2.0.
+fn upgrade_schema(schema: &mut Mapping) {
+    if schema.get("type").and_then(Value::as_str) == Some("file") {
+        schema.insert("type".into(), "string".into());
+        schema.insert("format".into(), "binary".into());
+    }
+    if let Some(nullable) = schema.remove("x-nullable") {
+        schema.insert("nullable".into(), nullable);
+    }
+    if let Some(Value::String(property)) = schema.get("discriminator") {
+        let discriminator = Mapping::from_iter([("propertyName".into(), property.clone().into())]);
+        schema.insert("discriminator".into(), Value::Mapping(discriminator));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_json_snapshot;
+
+    use super::*;
+
+    #[test]
+    fn test_upgrade_swagger_document() {
+        let spec: Value = serde_yaml::from_str(
+            r##"
+swagger: "2.0"
+info: { title: Pet Store, version: 1.0.0 }
+host: petstore.example.com
+basePath: /v1
+schemes: [https, http]
+consumes: [application/json]
+produces: [application/json]
+securityDefinitions:
+  key: { type: apiKey, in: header, name: X-API-Key }
+  oauth:
+    type: oauth2
+    flow: application
+    tokenUrl: https://petstore.example.com/token
+    scopes: { read: Read pets }
+parameters:
+  Limit: { name: limit, in: query, type: integer, maximum: 100 }
+  NewPet: { name: pet, in: body, required: true, schema: { $ref: "#/definitions/Pet" } }
+paths:
+  /pets:
+    get:
+      operationId: listPets
+      parameters:
+        - $ref: "#/parameters/Limit"
+        - { name: tags, in: query, type: array, items: { type: string }, collectionFormat: multi }
+        - { name: ids, in: query, type: array, items: { type: integer } }
+      responses:
+        "200":
+          description: Pets
+          headers:
+            X-Total: { type: integer }
+          schema: { type: array, items: { $ref: "#/definitions/Pet" } }
+    post:
+      operationId: createPet
+      parameters:
+        - $ref: "#/parameters/NewPet"
+      responses:
+        "201": { $ref: "#/responses/Created" }
+  /pets/{id}/photo:
+    parameters:
+      - { name: id, in: path, required: true, type: string }
+    post:
+      operationId: uploadPhoto
+      consumes: [multipart/form-data]
+      parameters:
+        - { name: file, in: formData, type: file, required: true }
+        - { name: caption, in: formData, type: string, description: Photo caption }
+      responses:
+        "204": { description: Uploaded }
+responses:
+  Created:
+    description: Created
+    schema: { $ref: "#/definitions/Pet" }
+definitions:
+  Pet:
+    type: object
+    discriminator: kind
+    properties:
+      kind: { type: string }
+      name: { type: string, x-nullable: true }
+"##,
+        )
+        .unwrap();
+
+        assert_json_snapshot!(upgrade(spec), @r###"
+        {
+          "openapi": "3.0.3",
+          "info": {
+            "title": "Pet Store",
+            "version": "1.0.0"
+          },
+          "servers": [
+            {
+              "url": "https://petstore.example.com/v1"
+            },
+            {
+              "url": "http://petstore.example.com/v1"
+            }
+          ],
+          "paths": {
+            "/pets": {
+              "get": {
+                "operationId": "listPets",
+                "responses": {
+                  "200": {
+                    "description": "Pets",
+                    "headers": {
+                      "X-Total": {
+                        "schema": {
+                          "type": "integer"
+                        }
+                      }
+                    },
+                    "content": {
+                      "application/json": {
+                        "schema": {
+                          "type": "array",
+                          "items": {
+                            "$ref": "#/components/schemas/Pet"
+                          }
+                        }
+                      }
+                    }
+                  }
+                },
+                "parameters": [
+                  {
+                    "$ref": "#/components/parameters/Limit"
+                  },
+                  {
+                    "name": "tags",
+                    "in": "query",
+                    "style": "form",
+                    "explode": true,
+                    "schema": {
+                      "type": "array",
+                      "items": {
+                        "type": "string"
+                      }
+                    }
+                  },
+                  {
+                    "name": "ids",
+                    "in": "query",
+                    "style": "form",
+                    "explode": false,
+                    "schema": {
+                      "type": "array",
+                      "items": {
+                        "type": "integer"
+                      }
+                    }
+                  }
+                ]
+              },
+              "post": {
+                "operationId": "createPet",
+                "responses": {
+                  "201": {
+                    "$ref": "#/components/responses/Created"
+                  }
+                },
+                "requestBody": {
+                  "$ref": "#/components/requestBodies/NewPet"
+                }
+              }
+            },
+            "/pets/{id}/photo": {
+              "parameters": [
+                {
+                  "name": "id",
+                  "in": "path",
+                  "required": true,
+                  "schema": {
+                    "type": "string"
+                  }
+                }
+              ],
+              "post": {
+                "operationId": "uploadPhoto",
+                "responses": {
+                  "204": {
+                    "description": "Uploaded"
+                  }
+                },
+                "requestBody": {
+                  "content": {
+                    "multipart/form-data": {
+                      "schema": {
+                        "type": "object",
+                        "properties": {
+                          "file": {
+                            "type": "string",
+                            "format": "binary"
+                          },
+                          "caption": {
+                            "type": "string",
+                            "description": "Photo caption"
+                          }
+                        },
+                        "required": [
+                          "file"
+                        ]
+                      }
+                    }
+                  },
+                  "required": true
+                }
+              }
+            }
+          },
+          "components": {
+            "schemas": {
+              "Pet": {
+                "type": "object",
+                "discriminator": {
+                  "propertyName": "kind"
+                },
+                "properties": {
+                  "kind": {
+                    "type": "string"
+                  },
+                  "name": {
+                    "type": "string",
+                    "nullable": true
+                  }
+                }
+              }
+            },
+            "parameters": {
+              "Limit": {
+                "name": "limit",
+                "in": "query",
+                "schema": {
+                  "type": "integer",
+                  "maximum": 100
+                }
+              }
+            },
+            "requestBodies": {
+              "NewPet": {
+                "content": {
+                  "application/json": {
+                    "schema": {
+                      "$ref": "#/components/schemas/Pet"
+                    }
+                  }
+                },
+                "required": true
+              }
+            },
+            "responses": {
+              "Created": {
+                "description": "Created",
+                "content": {
+                  "application/json": {
+                    "schema": {
+                      "$ref": "#/components/schemas/Pet"
+                    }
+                  }
+                }
+              }
+            },
+            "securitySchemes": {
+              "key": {
+                "type": "apiKey",
+                "name": "X-API-Key",
+                "in": "header"
+              },
+              "oauth": {
+                "type": "oauth2",
+                "flows": {
+                  "clientCredentials": {
+                    "tokenUrl": "https://petstore.example.com/token",
+                    "scopes": {
+                      "read": "Read pets"
+                    }
+                  }
+                }
+              }
+            }
+          }
+        }
+        "###);
+    }
+
+    #[test]
+    fn test_servers_without_host() {
+        let root: Mapping = serde_yaml::from_str("basePath: /api").unwrap();
+        assert_eq!(servers(&root), vec![serde_yaml::from_str::<Value>("url: /api").unwrap()]);
+
+        let root: Mapping = serde_yaml::from_str("{}").unwrap();
+        assert!(servers(&root).is_empty());
+    }
+}
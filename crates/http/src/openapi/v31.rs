@@ -0,0 +1,242 @@
+use serde_yaml::{Mapping, Value};
+
+use super::schema;
+
+/// Rewrites an OpenAPI 3.1 document into the OpenAPI 3.0 model.
+///
+/// JSON Schema 2020-12 keywords are mapped onto their closest 3.0
+/// counterparts, `$defs` of component schemas are hoisted into components of
+/// their own, and webhooks, which the bridge cannot call, are dropped.
+pub fn upgrade(mut spec: Value) -> Value {
+    if let Some(root) = spec.as_mapping_mut() {
+        root.insert("openapi".into(), "3.0.3".into());
+        root.remove("webhooks");
+        root.remove("jsonSchemaDialect");
+        // Paths are optional in 3.1.
+        root.entry("paths".into()).or_insert_with(|| Value::Mapping(Mapping::new()));
+    }
+
+    hoist_defs(&mut spec);
+    schema::walk(&mut spec, &mut downgrade_schema);
+    spec
+}
+
+/// Moves the `$defs` of component schemas into `#/components/schemas/`,
+/// naming them `{Schema}_{Def}`, and points references at their new location.
+fn hoist_defs(spec: &mut Value) {
+    let Some(schemas) = spec
+        .get_mut("components")
+        .and_then(|components| components.get_mut("schemas"))
+        .and_then(Value::as_mapping_mut)
+    else {
+        return;
+    };
+
+    let mut hoisted = Vec::new();
+    for (name, schema) in schemas.iter_mut() {
+        let (Some(name), Some(schema)) = (name.as_str(), schema.as_mapping_mut()) else {
+            continue;
+        };
+        if let Some(Value::Mapping(defs)) = schema.remove("$defs") {
+            for (def, value) in defs {
+                if let Some(def) = def.as_str() {
+                    hoisted.push((
+                        format!("#/components/schemas/{name}/$defs/{def}"),
+                        format!("{name}_{def}"),
+                        value,
+                    ));
+                }
+            }
+        }
+    }
+    if hoisted.is_empty() {
+        return;
+    }
+
+    let mut renames = Vec::new();
+    for (reference, name, value) in hoisted {
+        schemas.insert(name.as_str().into(), value);
+        renames.push((reference, format!("#/components/schemas/{name}")));
+    }
+    rename_references(spec, &renames);
+}
+
+fn rename_references(value: &mut Value, renames: &[(String, String)]) {
+    match value {
+        Value::Mapping(mapping) => {
+            if let Some(Value::String(reference)) = mapping.get_mut("$ref")
+                && let Some((_, to)) = renames.iter().find(|(from, _)| from == reference)
+            {
+                *reference = to.clone();
+            }
+            mapping.values_mut().for_each(|value| rename_references(value, renames));
+        }
+        Value::Sequence(items) => {
+            items.iter_mut().for_each(|value| rename_references(value, renames))
+        }
+        _ => {}
+    }
+}
+
+/// Maps JSON Schema 2020-12 keywords onto OpenAPI 3.0 schema keywords.
+fn downgrade_schema(schema: &mut Mapping) {
+    // `type: [string, "null"]` becomes a nullable string, and unions of
+    // several types become an `anyOf`.
+    match schema.get("type") {
+        Some(Value::Sequence(types)) => {
+            let nullable = types.iter().any(|t| t.as_str() == Some("null"));
+            let mut types =
+                types.iter().filter(|t| t.as_str() != Some("null")).cloned().collect::<Vec<_>>();
+            schema.remove("type");
+            match types.len() {
+                0 => {}
+                1 => {
+                    schema.insert("type".into(), types.remove(0));
+                }
+                _ => {
+                    let variants = types
+                        .into_iter()
+                        .map(|t| Value::Mapping(Mapping::from_iter([("type".into(), t)])))
+                        .collect();
+                    schema.insert("anyOf".into(), Value::Sequence(variants));
+                }
+            }
+            if nullable {
+                schema.insert("nullable".into(), true.into());
+            }
+        }
+        Some(Value::String(t)) if t == "null" => {
+            schema.remove("type");
+            schema.insert("nullable".into(), true.into());
+        }
+        _ => {}
+    }
+
+    if let Some(value) = schema.remove("const") {
+        schema.insert("enum".into(), Value::Sequence(vec![value]));
+    }
+
+    if let Some(Value::Sequence(mut examples)) = schema.remove("examples")
+        && !examples.is_empty()
+        && !schema.contains_key("example")
+    {
+        schema.insert("example".into(), examples.remove(0));
+    }
+
+    for (exclusive, bound) in [("exclusiveMinimum", "minimum"), ("exclusiveMaximum", "maximum")] {
+        if let Some(Value::Number(limit)) = schema.get(exclusive) {
+            let limit = Value::Number(limit.clone());
+            schema.insert(bound.into(), limit);
+            schema.insert(exclusive.into(), true.into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_json_snapshot;
+
+    use super::*;
+
+    #[test]
+    fn test_upgrade_openapi_31_document() {
+        let spec: Value = serde_yaml::from_str(
+            r##"
+openapi: 3.1.0
+info: { title: Modern API, version: 1.0.0, license: { name: MIT, identifier: MIT } }
+jsonSchemaDialect: https://spec.openapis.org/oas/3.1/dialect/base
+webhooks:
+  newPet:
+    post:
+      responses: { "200": { description: Ok } }
+components:
+  schemas:
+    Pet:
+      type: object
+      properties:
+        name: { type: [string, "null"], examples: [Rex, Fido] }
+        kind: { const: dog }
+        age: { type: integer, exclusiveMinimum: 0, exclusiveMaximum: 30 }
+        id: { type: [string, integer] }
+        const: { type: string, default: { type: [a] } }
+        owner: { $ref: "#/components/schemas/Pet/$defs/Owner" }
+      $defs:
+        Owner:
+          type: object
+          properties:
+            name: { type: string }
+"##,
+        )
+        .unwrap();
+
+        assert_json_snapshot!(upgrade(spec), @r###"
+        {
+          "openapi": "3.0.3",
+          "info": {
+            "title": "Modern API",
+            "version": "1.0.0",
+            "license": {
+              "name": "MIT",
+              "identifier": "MIT"
+            }
+          },
+          "components": {
+            "schemas": {
+              "Pet": {
+                "type": "object",
+                "properties": {
+                  "name": {
+                    "nullable": true,
+                    "type": "string",
+                    "example": "Rex"
+                  },
+                  "kind": {
+                    "enum": [
+                      "dog"
+                    ]
+                  },
+                  "age": {
+                    "type": "integer",
+                    "exclusiveMinimum": true,
+                    "exclusiveMaximum": true,
+                    "minimum": 0,
+                    "maximum": 30
+                  },
+                  "id": {
+                    "anyOf": [
+                      {
+                        "type": "string"
+                      },
+                      {
+                        "type": "integer"
+                      }
+                    ]
+                  },
+                  "const": {
+                    "type": "string",
+                    "default": {
+                      "type": [
+                        "a"
+                      ]
+                    }
+                  },
+                  "owner": {
+                    "$ref": "#/components/schemas/Pet_Owner"
+                  }
+                }
+              },
+              "Pet_Owner": {
+                "type": "object",
+                "properties": {
+                  "name": {
+                    "type": "string"
+                  }
+                }
+              }
+            }
+          },
+          "paths": {}
+        }
+        "###);
+    }
+}
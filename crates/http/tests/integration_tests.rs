@@ -1,12 +1,15 @@
 use std::{io::Write, sync::Arc};
 
-use brwse_bridge_http::{bridge::HTTPBridge, openapi};
+use brwse_bridge_http::{
+    bridge::{HTTPBridge, OAuth2Config},
+    openapi,
+};
 use insta::assert_json_snapshot;
 use serde_json::json;
 use tempfile::NamedTempFile;
 use wiremock::{
     Mock, MockServer, ResponseTemplate,
-    matchers::{header, method, path, query_param},
+    matchers::{body_json, header, method, path, query_param},
 };
 
 #[tokio::test]
@@ -282,6 +285,11 @@ async fn test_complete_openapi_to_mcp_workflow() {
             },
             "include": {
               "items": {
+                "enum": [
+                  "profile",
+                  "settings",
+                  "preferences"
+                ],
                 "type": "string"
               },
               "type": "array"
@@ -323,3 +331,445 @@ async fn test_complete_openapi_to_mcp_workflow() {
     }
     "###);
 }
+
+#[tokio::test]
+async fn test_shared_parameter_and_request_body_components() {
+    let openapi_spec = r##"
+    {
+        "openapi": "3.0.0",
+        "info": {
+            "title": "Shared Components API",
+            "version": "1.0.0"
+        },
+        "paths": {
+            "/orgs/{org}/projects": {
+                "parameters": [
+                    { "$ref": "#/components/parameters/Org" },
+                    {
+                        "name": "verbose",
+                        "in": "query",
+                        "description": "Path-level flag",
+                        "schema": { "type": "boolean" }
+                    }
+                ],
+                "get": {
+                    "operationId": "listProjects",
+                    "parameters": [
+                        { "$ref": "#/components/parameters/Limit" },
+                        {
+                            "name": "verbose",
+                            "in": "query",
+                            "description": "Operation-level flag",
+                            "schema": { "type": "string", "enum": ["yes", "no"] }
+                        }
+                    ],
+                    "responses": {
+                        "200": { "description": "Projects" }
+                    }
+                },
+                "post": {
+                    "operationId": "createProject",
+                    "requestBody": { "$ref": "#/components/requestBodies/Project" },
+                    "responses": {
+                        "201": { "description": "Created" }
+                    }
+                }
+            }
+        },
+        "components": {
+            "parameters": {
+                "Org": {
+                    "name": "org",
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "string" }
+                },
+                "Limit": {
+                    "name": "limit",
+                    "in": "query",
+                    "schema": { "type": "integer", "maximum": 50 }
+                }
+            },
+            "requestBodies": {
+                "Project": {
+                    "required": true,
+                    "content": {
+                        "application/json": {
+                            "schema": { "$ref": "#/components/schemas/Project" }
+                        }
+                    }
+                }
+            },
+            "schemas": {
+                "Project": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" }
+                    },
+                    "required": ["name"]
+                }
+            }
+        }
+    }
+    "##;
+
+    let mut temp_file = NamedTempFile::with_suffix(".json").unwrap();
+    write!(temp_file, "{openapi_spec}").unwrap();
+    let spec = openapi::load_spec(temp_file.path().to_str().unwrap()).await.unwrap();
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/orgs/brwse/projects"))
+        .and(query_param("limit", "5"))
+        .and(query_param("verbose", "yes"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{"name": "bridge"}])))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/orgs/brwse/projects"))
+        .and(body_json(json!({"name": "bridge"})))
+        .respond_with(ResponseTemplate::new(201).set_body_json(json!({"name": "bridge"})))
+        .mount(&mock_server)
+        .await;
+
+    let service =
+        HTTPBridge::new(Arc::new(spec), mock_server.uri(), Arc::new(reqwest::Client::new()));
+
+    let tools = service.tools(None).collect::<Vec<_>>();
+    assert_json_snapshot!(tools, @r###"
+    [
+      {
+        "name": "listProjects",
+        "description": "GET /orgs/{org}/projects",
+        "inputSchema": {
+          "properties": {
+            "limit": {
+              "maximum": 50,
+              "type": "integer"
+            },
+            "org": {
+              "type": "string"
+            },
+            "verbose": {
+              "enum": [
+                "yes",
+                "no"
+              ],
+              "type": "string"
+            }
+          },
+          "required": [
+            "org"
+          ],
+          "type": "object"
+        }
+      },
+      {
+        "name": "createProject",
+        "description": "POST /orgs/{org}/projects",
+        "inputSchema": {
+          "properties": {
+            "body": {
+              "properties": {
+                "name": {
+                  "type": "string"
+                }
+              },
+              "required": [
+                "name"
+              ],
+              "type": "object"
+            },
+            "org": {
+              "type": "string"
+            },
+            "verbose": {
+              "type": "boolean"
+            }
+          },
+          "required": [
+            "org",
+            "body"
+          ],
+          "type": "object"
+        }
+      }
+    ]
+    "###);
+
+    let list_result = service
+        .execute_tool("listProjects", json!({"org": "brwse", "limit": 5, "verbose": "yes"}))
+        .await
+        .unwrap();
+    assert_ne!(list_result.is_error, Some(true));
+
+    let create_result = service
+        .execute_tool("createProject", json!({"org": "brwse", "body": {"name": "bridge"}}))
+        .await
+        .unwrap();
+    assert_ne!(create_result.is_error, Some(true));
+
+    // The shared path parameter is required for every operation on the path.
+    let missing_org =
+        service.execute_tool("createProject", json!({"body": {"name": "bridge"}})).await;
+    assert!(missing_org.is_err());
+}
+
+#[tokio::test]
+async fn test_bridge_held_credentials() {
+    let openapi_spec = r#"
+    {
+        "openapi": "3.0.0",
+        "info": {
+            "title": "Secured API",
+            "version": "1.0.0"
+        },
+        "security": [{ "bearerAuth": [] }],
+        "paths": {
+            "/reports": {
+                "get": {
+                    "operationId": "listReports",
+                    "parameters": [
+                        {
+                            "name": "Authorization",
+                            "in": "header",
+                            "required": true,
+                            "schema": { "type": "string" }
+                        },
+                        {
+                            "name": "year",
+                            "in": "query",
+                            "schema": { "type": "integer" }
+                        }
+                    ],
+                    "responses": {
+                        "200": { "description": "Reports" }
+                    }
+                }
+            },
+            "/search": {
+                "get": {
+                    "operationId": "search",
+                    "security": [{ "apiKey": [] }],
+                    "parameters": [
+                        {
+                            "name": "key",
+                            "in": "query",
+                            "required": true,
+                            "schema": { "type": "string" }
+                        }
+                    ],
+                    "responses": {
+                        "200": { "description": "Results" }
+                    }
+                }
+            },
+            "/health": {
+                "get": {
+                    "operationId": "health",
+                    "security": [],
+                    "responses": {
+                        "200": { "description": "Healthy" }
+                    }
+                }
+            }
+        },
+        "components": {
+            "securitySchemes": {
+                "bearerAuth": { "type": "http", "scheme": "bearer" },
+                "apiKey": { "type": "apiKey", "in": "query", "name": "key" }
+            }
+        }
+    }
+    "#;
+
+    let mut temp_file = NamedTempFile::with_suffix(".json").unwrap();
+    write!(temp_file, "{openapi_spec}").unwrap();
+    let spec = openapi::load_spec(temp_file.path().to_str().unwrap()).await.unwrap();
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/reports"))
+        .and(header("Authorization", "Bearer s3cr3t"))
+        .and(query_param("year", "2024"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/search"))
+        .and(query_param("key", "k3y"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/health"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+
+    let service =
+        HTTPBridge::new(Arc::new(spec), mock_server.uri(), Arc::new(reqwest::Client::new()))
+            .with_credentials(
+                [("bearerAuth", "s3cr3t"), ("apiKey", "k3y")]
+                    .into_iter()
+                    .map(|(scheme, secret)| (scheme.to_string(), secret.to_string()))
+                    .collect(),
+            );
+
+    // Inputs carrying credentials are hidden from agents.
+    let tools = service.tools(None).collect::<Vec<_>>();
+    assert_json_snapshot!(tools, @r###"
+    [
+      {
+        "name": "listReports",
+        "description": "GET /reports",
+        "inputSchema": {
+          "properties": {
+            "year": {
+              "type": "integer"
+            }
+          },
+          "required": [],
+          "type": "object"
+        }
+      },
+      {
+        "name": "search",
+        "description": "GET /search",
+        "inputSchema": {
+          "properties": {},
+          "required": [],
+          "type": "object"
+        }
+      },
+      {
+        "name": "health",
+        "description": "GET /health",
+        "inputSchema": {
+          "properties": {},
+          "required": [],
+          "type": "object"
+        }
+      }
+    ]
+    "###);
+
+    let reports = service.execute_tool("listReports", json!({"year": 2024})).await.unwrap();
+    assert_eq!(reports.is_error, Some(false));
+
+    let search = service.execute_tool("search", json!({})).await.unwrap();
+    assert_eq!(search.is_error, Some(false));
+
+    let health = service.execute_tool("health", json!({})).await.unwrap();
+    assert_eq!(health.is_error, Some(false));
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let health_request = requests.iter().find(|r| r.url.path() == "/health").unwrap();
+    assert!(!health_request.headers.contains_key("authorization"));
+}
+
+#[tokio::test]
+async fn test_oauth2_client_credentials() {
+    let mock_server = MockServer::start().await;
+    let openapi_spec = format!(
+        r#"
+    {{
+        "openapi": "3.0.0",
+        "info": {{
+            "title": "OAuth2 API",
+            "version": "1.0.0"
+        }},
+        "security": [{{ "oauth": ["read"] }}],
+        "paths": {{
+            "/me": {{
+                "get": {{
+                    "operationId": "getMe",
+                    "responses": {{
+                        "200": {{ "description": "Current user" }}
+                    }}
+                }}
+            }}
+        }},
+        "components": {{
+            "securitySchemes": {{
+                "oauth": {{
+                    "type": "oauth2",
+                    "flows": {{
+                        "clientCredentials": {{
+                            "tokenUrl": "{}/oauth/token",
+                            "scopes": {{ "read": "Read access" }}
+                        }}
+                    }}
+                }}
+            }}
+        }}
+    }}
+    "#,
+        mock_server.uri()
+    );
+
+    let mut temp_file = NamedTempFile::with_suffix(".json").unwrap();
+    write!(temp_file, "{openapi_spec}").unwrap();
+    let spec = openapi::load_spec(temp_file.path().to_str().unwrap()).await.unwrap();
+
+    // The first token is revoked upstream, so the bridge must fetch a new one
+    // and retry.
+    Mock::given(method("POST"))
+        .and(path("/oauth/token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "revoked",
+            "token_type": "Bearer",
+            "expires_in": 3600,
+        })))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/oauth/token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "valid",
+            "token_type": "Bearer",
+            "expires_in": 3600,
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .and(header("Authorization", "Bearer revoked"))
+        .respond_with(ResponseTemplate::new(401))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .and(header("Authorization", "Bearer valid"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"name": "bridge"})))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let service =
+        HTTPBridge::new(Arc::new(spec), mock_server.uri(), Arc::new(reqwest::Client::new()))
+            .with_oauth2(
+                [(
+                    "oauth".to_string(),
+                    OAuth2Config {
+                        client_id: "bridge".to_string(),
+                        client_secret: Some("secret".to_string()),
+                        scopes: vec!["read".to_string()],
+                        ..Default::default()
+                    },
+                )]
+                .into(),
+            );
+
+    let first = service.execute_tool("getMe", json!({})).await.unwrap();
+    assert_eq!(first.is_error, Some(false));
+
+    // The renewed token is cached for later calls.
+    let second = service.execute_tool("getMe", json!({})).await.unwrap();
+    assert_eq!(second.is_error, Some(false));
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let token_requests = requests.iter().filter(|r| r.url.path() == "/oauth/token").count();
+    assert_eq!(token_requests, 2);
+}
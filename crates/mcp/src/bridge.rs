@@ -243,6 +243,16 @@ fn service_error_to_mcp_error(e: rmcp::ServiceError) -> rmcp::Error {
 pub async fn start<T>(addr: &str, service: T) -> io::Result<CancellationToken>
 where
     T: ServerHandler + Clone,
+{
+    start_with(addr, move || service.clone()).await
+}
+
+/// Starts the MCP server, serving each session with the service made by
+/// `service`.
+pub async fn start_with<T, F>(addr: &str, service: F) -> io::Result<CancellationToken>
+where
+    T: ServerHandler,
+    F: Fn() -> T + Send + 'static,
 {
     let ctoken = CancellationToken::new();
     let config = SseServerConfig {
@@ -254,6 +264,6 @@ where
     };
 
     let sse_server = SseServer::serve_with_config(config).await?;
-    sse_server.with_service(move || service.clone());
+    sse_server.with_service(service);
     Ok(ctoken)
 }